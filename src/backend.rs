@@ -0,0 +1,475 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use ollama_rs::{
+    error::OllamaError,
+    generation::{
+        chat::{
+            request::ChatMessageRequest, ChatMessage, ChatMessageResponse,
+            ChatMessageResponseStream,
+        },
+        completion::{request::GenerationRequest, GenerationResponse, GenerationResponseStream},
+        functions::tools::Tool,
+    },
+    models::LocalModel,
+};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
+    RequestBuilder, Url,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::{models, utf8::Utf8Buffer};
+
+type Error = Box<dyn std::error::Error + 'static>;
+
+/// The server answering shark's requests, Ollama unless testing.
+#[async_trait]
+pub trait Backend: Send + Sync {
+    async fn generate_stream(
+        &self,
+        request: GenerationRequest,
+    ) -> Result<GenerationResponseStream, OllamaError>;
+
+    async fn chat(&self, request: ChatMessageRequest) -> Result<ChatMessageResponse, OllamaError>;
+
+    /// Whether the server is given the tools with [`Backend::chat_with_tools`] and asks for
+    /// them in its own format. Otherwise shark describes the tools in a system message.
+    fn native_tools(&self) -> bool {
+        false
+    }
+
+    /// Like [`Backend::chat`], offering `tools` to the model when the server takes them
+    /// natively. A requested tool comes back as `<function=name>{...}</function>`, the way
+    /// llama 3.1 writes it.
+    async fn chat_with_tools(
+        &self,
+        request: ChatMessageRequest,
+        _tools: &[Arc<dyn Tool>],
+    ) -> Result<ChatMessageResponse, OllamaError> {
+        self.chat(request).await
+    }
+
+    async fn chat_stream(
+        &self,
+        request: ChatMessageRequest,
+    ) -> Result<ChatMessageResponseStream, OllamaError>;
+
+    /// Downloads `model` so it can answer requests.
+    async fn pull(&self, model: &str) -> Result<(), Error>;
+
+    /// The context window of `model` in tokens, when the server tells.
+    async fn context_length(&self, model: &str) -> Option<usize>;
+}
+
+/// The Ollama server, reached with shark's own HTTP client since ollama-rs can't add headers
+/// to its requests. Every request carries the API token, when one is configured, for
+/// servers behind an authenticating proxy.
+#[derive(Debug, Clone)]
+pub struct OllamaClient {
+    url: Url,
+    http: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct LocalModels {
+    models: Vec<LocalModel>,
+}
+
+/// An HTTP client sending JSON, and `token` as a bearer token when there is one.
+pub fn http_client(token: Option<&str>) -> Result<reqwest::Client, String> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    if let Some(token) = token {
+        let mut value = HeaderValue::from_str(&format!("Bearer {token}"))
+            .map_err(|_| "the API token can't be sent in a header".to_string())?;
+        value.set_sensitive(true);
+        headers.insert(AUTHORIZATION, value);
+    }
+    reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// Sends `request` and reads the JSON answer. Errors carry the server's message, like
+/// ollama-rs does, so missing models can be recognized.
+pub async fn send<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, OllamaError> {
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(response
+            .text()
+            .await
+            .unwrap_or_else(|e| e.to_string())
+            .into());
+    }
+    let body = response.bytes().await.map_err(|e| e.to_string())?;
+    Ok(serde_json::from_slice(&body).map_err(|e| e.to_string())?)
+}
+
+impl OllamaClient {
+    pub fn new(url: Url, api_token: Option<&str>) -> Result<Self, String> {
+        let http = http_client(api_token)?;
+        Ok(Self { url, http })
+    }
+
+    /// The server's address, ending with a slash.
+    pub fn url_str(&self) -> &str {
+        self.url.as_str()
+    }
+
+    fn get(&self, endpoint: &str) -> RequestBuilder {
+        self.http.get(format!("{}{endpoint}", self.url_str()))
+    }
+
+    fn post(&self, endpoint: &str, body: &Value) -> RequestBuilder {
+        self.http
+            .post(format!("{}{endpoint}", self.url_str()))
+            .body(body.to_string())
+    }
+
+    /// The models pulled on the server.
+    pub async fn list_local_models(&self) -> Result<Vec<LocalModel>, OllamaError> {
+        let response: LocalModels = send(self.get("api/tags")).await?;
+        Ok(response.models)
+    }
+
+    /// Asks the server about `model`, see `/api/show`.
+    pub async fn show(&self, model: &str) -> Result<Value, OllamaError> {
+        send(self.post("api/show", &json!({ "model": model }))).await
+    }
+}
+
+#[async_trait]
+impl Backend for OllamaClient {
+    async fn generate_stream(
+        &self,
+        request: GenerationRequest,
+    ) -> Result<GenerationResponseStream, OllamaError> {
+        let stream =
+            post_stream::<GenerationResponse>(self, "api/generate", json!(request)).await?;
+        Ok(Box::pin(stream.filter(
+            |responses| !matches!(responses, Ok(responses) if responses.is_empty()),
+        )))
+    }
+
+    async fn chat(&self, request: ChatMessageRequest) -> Result<ChatMessageResponse, OllamaError> {
+        let mut request = json!(request);
+        request["stream"] = json!(false);
+        send(self.post("api/chat", &request)).await
+    }
+
+    async fn chat_stream(
+        &self,
+        request: ChatMessageRequest,
+    ) -> Result<ChatMessageResponseStream, OllamaError> {
+        let stream = post_stream(self, "api/chat", json!(request)).await?;
+        Ok(Box::pin(flatten_chat_responses(stream)))
+    }
+
+    async fn pull(&self, model: &str) -> Result<(), Error> {
+        models::pull(self, model).await
+    }
+
+    async fn context_length(&self, model: &str) -> Option<usize> {
+        models::context_length(self, model)
+            .await
+            .inspect_err(|e| tracing::debug!("failed to look up the context length: {e}"))
+            .ok()
+            .flatten()
+    }
+}
+
+/// Posts `request` to Ollama's `endpoint` and streams the newline-delimited JSON answer,
+/// the lines completed by each network chunk at a time. ollama-rs parses every chunk on
+/// its own and drops lines, or characters, split across two of them.
+pub async fn post_stream<T: DeserializeOwned>(
+    ollama: &OllamaClient,
+    endpoint: &str,
+    mut request: Value,
+) -> Result<impl Stream<Item = Result<Vec<T>, OllamaError>> + Send, OllamaError> {
+    request["stream"] = json!(true);
+    read_stream(ollama.post(endpoint, &request), JsonLines::default()).await
+}
+
+/// Sends `request` and streams the JSON in its answer as `lines` reads it, the values
+/// completed by each network chunk at a time.
+pub async fn read_stream<T: DeserializeOwned>(
+    request: RequestBuilder,
+    mut lines: JsonLines,
+) -> Result<impl Stream<Item = Result<Vec<T>, OllamaError>> + Send, OllamaError> {
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(response
+            .text()
+            .await
+            .unwrap_or_else(|e| e.to_string())
+            .into());
+    }
+
+    let chunks = response.bytes_stream().map(Some);
+    Ok(chunks
+        .chain(tokio_stream::once(None))
+        .map(move |chunk| match chunk {
+            Some(Ok(bytes)) => Ok(lines.push(&bytes)),
+            Some(Err(e)) => Err(OllamaError::from(format!("Failed to read response: {e}"))),
+            None => Ok(lines.finish()),
+        }))
+}
+
+/// Newline-delimited JSON arriving in pieces. The unfinished last line is held back until
+/// a later piece ends it, so every line is parsed whole and as valid UTF-8.
+#[derive(Default)]
+pub struct JsonLines {
+    utf8: Utf8Buffer,
+    line: String,
+    events: bool,
+}
+
+impl JsonLines {
+    /// Server-sent events, which carry the JSON in `data:` lines and end with `data: [DONE]`.
+    pub fn server_sent_events() -> Self {
+        Self {
+            events: true,
+            ..Self::default()
+        }
+    }
+
+    fn push<T: DeserializeOwned>(&mut self, bytes: &[u8]) -> Vec<T> {
+        self.line.push_str(&self.utf8.push(bytes));
+        let Some(end) = self.line.rfind('\n') else {
+            return Vec::new();
+        };
+        let complete: String = self.line.drain(..=end).collect();
+        complete
+            .lines()
+            .filter_map(|line| self.parse(line))
+            .collect()
+    }
+
+    fn finish<T: DeserializeOwned>(&mut self) -> Vec<T> {
+        self.line.push_str(&self.utf8.finish());
+        std::mem::take(&mut self.line)
+            .lines()
+            .filter_map(|line| self.parse(line))
+            .collect()
+    }
+
+    fn parse<T: DeserializeOwned>(&self, line: &str) -> Option<T> {
+        if !self.events {
+            return parse_line(line);
+        }
+        let data = line.strip_prefix("data:")?.trim();
+        if data == "[DONE]" {
+            return None;
+        }
+        parse_line(data)
+    }
+}
+
+fn parse_line<T: DeserializeOwned>(line: &str) -> Option<T> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    serde_json::from_str(line)
+        .inspect_err(|e| tracing::debug!("skipping unreadable line {line}: {e}"))
+        .ok()
+}
+
+/// The responses completed by each network chunk one by one, so that every streamed token
+/// stays a response of its own.
+pub fn flatten_chat_responses(
+    stream: impl Stream<Item = Result<Vec<ChatMessageResponse>, OllamaError>> + Send,
+) -> impl Stream<Item = Result<ChatMessageResponse, ()>> + Send {
+    futures::StreamExt::flat_map(stream, |responses| {
+        let responses = match responses {
+            Ok(responses) => responses.into_iter().map(Ok).collect(),
+            Err(e) => {
+                tracing::debug!("{e}");
+                vec![Err(())]
+            }
+        };
+        futures::stream::iter(responses)
+    })
+}
+
+/// Answers every request with canned text streamed word by word, so shark runs without
+/// an Ollama server. The requests it received are kept for tests to inspect.
+#[derive(Clone)]
+pub struct MockBackend {
+    answer: String,
+    tool_call: Option<String>,
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self::new("This is a mock answer from shark.")
+    }
+}
+
+impl MockBackend {
+    pub fn new(answer: impl ToString) -> Self {
+        Self {
+            answer: answer.to_string(),
+            tool_call: None,
+            requests: Arc::default(),
+        }
+    }
+
+    /// Asks for the tool `name` with `arguments` whenever shark offers tools.
+    pub fn with_tool_call(mut self, name: &str, arguments: Value) -> Self {
+        self.tool_call = Some(format!("<function={name}>{arguments}</function>"));
+        self
+    }
+
+    /// Every request received so far, as the JSON that would have been sent to Ollama.
+    pub fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn record(&self, request: Value) {
+        self.requests.lock().unwrap().push(request);
+    }
+
+    /// The answer split after every space, the way a model streams tokens.
+    fn chunks(&self) -> Vec<String> {
+        self.answer
+            .split_inclusive(' ')
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Backend for MockBackend {
+    async fn generate_stream(
+        &self,
+        request: GenerationRequest,
+    ) -> Result<GenerationResponseStream, OllamaError> {
+        self.record(json!(request));
+        let chunks = self.chunks();
+        let last = chunks.len().saturating_sub(1);
+        let responses = chunks.into_iter().enumerate().map(move |(i, chunk)| {
+            Ok(vec![GenerationResponse {
+                model: request.model_name.clone(),
+                created_at: String::new(),
+                response: chunk,
+                done: i == last,
+                context: None,
+                total_duration: None,
+                prompt_eval_count: None,
+                prompt_eval_duration: None,
+                eval_count: None,
+                eval_duration: None,
+            }])
+        });
+        Ok(Box::pin(tokio_stream::iter(responses)))
+    }
+
+    async fn chat(&self, request: ChatMessageRequest) -> Result<ChatMessageResponse, OllamaError> {
+        self.record(json!(request));
+        let content = self
+            .tool_call
+            .clone()
+            .unwrap_or_else(|| self.answer.clone());
+        Ok(ChatMessageResponse {
+            model: request.model_name,
+            created_at: String::new(),
+            message: Some(ChatMessage::assistant(content)),
+            done: true,
+            final_data: None,
+        })
+    }
+
+    async fn chat_stream(
+        &self,
+        request: ChatMessageRequest,
+    ) -> Result<ChatMessageResponseStream, OllamaError> {
+        self.record(json!(request));
+        let chunks = self.chunks();
+        let last = chunks.len().saturating_sub(1);
+        let responses = chunks.into_iter().enumerate().map(move |(i, chunk)| {
+            Ok(ChatMessageResponse {
+                model: request.model_name.clone(),
+                created_at: String::new(),
+                message: Some(ChatMessage::assistant(chunk)),
+                done: i == last,
+                final_data: None,
+            })
+        });
+        Ok(Box::pin(tokio_stream::iter(responses)))
+    }
+
+    async fn pull(&self, _model: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn context_length(&self, _model: &str) -> Option<usize> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_split_across_chunks_are_parsed_whole() {
+        let stream = concat!(
+            r#"{"model":"m","created_at":"","response":"Grüß","done":false}"#,
+            "\n",
+            r#"{"model":"m","created_at":"","response":"e 🦈","done":true}"#,
+        );
+        let (first, rest) = stream.as_bytes().split_at(46);
+        let (second, third) = rest.split_at(62);
+
+        let mut lines = JsonLines::default();
+        let mut responses: Vec<GenerationResponse> = lines.push(first);
+        assert!(responses.is_empty());
+        responses.extend(lines.push::<GenerationResponse>(second));
+        assert_eq!(responses.len(), 1);
+        assert!(lines.push::<GenerationResponse>(third).is_empty());
+        responses.extend(lines.finish::<GenerationResponse>());
+        let text: String = responses.iter().map(|r| r.response.as_str()).collect();
+        assert_eq!(text, "Grüße 🦈");
+        assert!(responses[1].done);
+    }
+
+    #[test]
+    fn server_sent_events_are_read_from_data_lines() {
+        let events = concat!(
+            "data: {\"text\":\"Hel\"}\n\n",
+            ": keep-alive\n\n",
+            "data: {\"text\":\"lo\"}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let mut lines = JsonLines::server_sent_events();
+        let values: Vec<Value> = lines.push(events.as_bytes());
+        assert_eq!(values, vec![json!({"text": "Hel"}), json!({"text": "lo"})]);
+        assert!(lines.finish::<Value>().is_empty());
+    }
+
+    #[tokio::test]
+    async fn chat_responses_of_one_chunk_stay_apart() {
+        let response = |content: &str, done| ChatMessageResponse {
+            model: "m".to_string(),
+            created_at: String::new(),
+            message: Some(ChatMessage::assistant(content.to_string())),
+            done,
+            final_data: None,
+        };
+        let chunks = tokio_stream::iter(vec![
+            Ok(vec![response("Hel", false), response("lo", false)]),
+            Ok(Vec::new()),
+            Ok(vec![response("!", true)]),
+        ]);
+        let contents: Vec<String> = flatten_chat_responses(chunks)
+            .map(|response| response.unwrap().message.unwrap().content)
+            .collect()
+            .await;
+        assert_eq!(contents, ["Hel", "lo", "!"]);
+    }
+}
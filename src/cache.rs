@@ -0,0 +1,124 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use ollama_rs::generation::options::GenerationOptions;
+use sha2::{Digest, Sha256};
+
+/// On-disk cache of final answers, one file per prompt.
+#[derive(Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl Cache {
+    pub fn new(dir: impl AsRef<Path>, ttl: Duration) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            ttl,
+        }
+    }
+
+    /// `$XDG_CACHE_HOME/shark`, falling back to `~/.cache/shark`.
+    pub fn default_dir() -> PathBuf {
+        if let Ok(dir) = std::env::var("XDG_CACHE_HOME") {
+            return Path::new(&dir).join("shark");
+        }
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        Path::new(&home).join(".cache").join("shark")
+    }
+
+    pub fn key(model: &str, prompt: &str, options: &GenerationOptions) -> String {
+        let options = serde_json::to_string(options).unwrap_or_default();
+        let mut hasher = Sha256::new();
+        for part in [model, prompt, &options] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    /// Returns the cached answer unless it is missing or older than the TTL.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let path = self.path(key);
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        if age > self.ttl {
+            let _ = std::fs::remove_file(&path);
+            return None;
+        }
+        std::fs::read_to_string(path).ok()
+    }
+
+    pub fn put(&self, key: &str, answer: &str) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.path(key), answer)
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(test: &str, ttl: Duration) -> Cache {
+        let dir = std::env::temp_dir().join(format!("shark-cache-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Cache::new(dir, ttl)
+    }
+
+    #[test]
+    fn keys_depend_on_model_prompt_and_options() {
+        let options = GenerationOptions::default();
+        let key = Cache::key("llama3.1", "hi", &options);
+        assert_eq!(key.len(), 64);
+        assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(key, Cache::key("llama3.1", "hi", &options));
+        assert_ne!(key, Cache::key("llama3.2", "hi", &options));
+        assert_ne!(key, Cache::key("llama3.1", "hi!", &options));
+        assert_ne!(
+            key,
+            Cache::key(
+                "llama3.1",
+                "hi",
+                &GenerationOptions::default().temperature(0.2)
+            )
+        );
+        // Parts are separated, so moving text from one to the other changes the key.
+        assert_ne!(
+            Cache::key("ab", "c", &options),
+            Cache::key("a", "bc", &options)
+        );
+    }
+
+    #[test]
+    fn answers_are_cached_until_the_ttl() {
+        let cache = cache("ttl", Duration::from_secs(60 * 60));
+        assert_eq!(cache.get("missing"), None);
+
+        cache.put("fresh", "an answer").unwrap();
+        assert_eq!(cache.get("fresh").as_deref(), Some("an answer"));
+
+        cache.put("stale", "an old answer").unwrap();
+        let two_hours_ago = SystemTime::now() - Duration::from_secs(2 * 60 * 60);
+        std::fs::File::options()
+            .write(true)
+            .open(cache.path("stale"))
+            .unwrap()
+            .set_modified(two_hours_ago)
+            .unwrap();
+        assert_eq!(cache.get("stale"), None);
+        assert!(!cache.path("stale").exists());
+    }
+}
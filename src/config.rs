@@ -0,0 +1,728 @@
+use std::path::{Path, PathBuf};
+
+use ollama_rs::generation::{
+    options::GenerationOptions,
+    parameters::{KeepAlive, TimeUnit},
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use termcolor::Color;
+
+use crate::{
+    output::{parse_color, Theme},
+    tools::{FunctionConfig, ToolsConfig},
+};
+
+const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub addr: String,
+    /// The API spoken by the server at `addr`.
+    #[serde(default)]
+    pub backend: BackendKind,
+    /// Sent as a bearer token with every request, for servers behind an authenticating
+    /// proxy. `OLLAMA_API_TOKEN` takes precedence.
+    pub api_token: Option<String>,
+    /// The key for an OpenAI-compatible server. `OPENAI_API_KEY` takes precedence.
+    pub api_key: Option<String>,
+    pub model: String,
+    /// Cheaper model summarizing tool output, the main model when unset.
+    pub summary_model: Option<String>,
+    /// Check at startup that the configured models are pulled on the server.
+    #[serde(default)]
+    pub check_models: bool,
+    /// Let the model summarize tool output instead of printing it as it is.
+    #[serde(default = "default_summarize")]
+    pub summarize: bool,
+    /// How long the summary of tool output should be.
+    #[serde(default)]
+    pub summary_length: SummaryLength,
+    /// Pull a model the server doesn't have instead of failing.
+    #[serde(default)]
+    pub auto_pull: bool,
+    pub color: String,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// Tool names, or `[[functions]]` tables with `name`, `enabled` and `description_override`.
+    pub functions: Vec<FunctionConfig>,
+    pub history_path: Option<String>,
+    pub history_max_messages: Option<usize>,
+    /// How many answers are kept for `--last`, next to the config file.
+    #[serde(default = "default_last_answers")]
+    pub last_answers: usize,
+    /// Replay answers to prompts seen before instead of asking the model again.
+    #[serde(default)]
+    pub cache: bool,
+    /// Defaults to `$XDG_CACHE_HOME/shark` or `~/.cache/shark`.
+    pub cache_dir: Option<PathBuf>,
+    #[serde(default = "default_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+    /// Ask before running destructive tools, like the ones changing the toolchain or running
+    /// shell commands.
+    #[serde(default)]
+    pub require_confirmation: bool,
+    /// Tool calls allowed per question: after an error the model may try again with fixed
+    /// arguments until this many calls were made.
+    #[serde(default = "default_max_tool_iterations")]
+    pub max_tool_iterations: u32,
+    /// Tool output passed to the summary at most, half the summary model's context by default.
+    pub max_tool_output_tokens: Option<usize>,
+    /// Streamed tokens after which shark stops the answer, whatever the server does.
+    pub max_output_tokens: Option<usize>,
+    /// Refuse to start when `functions` lists a tool that doesn't exist.
+    #[serde(default)]
+    pub strict_tools: bool,
+    #[serde(default)]
+    pub mode: Mode,
+    #[serde(default)]
+    pub render: Render,
+    /// Word-wrap streamed answers at `wrap_width`, or the terminal's width when unset.
+    #[serde(default)]
+    pub wrap: bool,
+    pub wrap_width: Option<usize>,
+    /// Stream answers into `$PAGER`, or `less -R`, when stdout is a terminal.
+    #[serde(default)]
+    pub pager: bool,
+    /// Files replacing the embedded prompt templates.
+    pub generation_template: Option<PathBuf>,
+    pub summary_template: Option<PathBuf>,
+    /// Persona for the model, replacing the default shark assistant.
+    pub system_prompt: Option<String>,
+    /// Give up on an answer that takes longer than this many seconds.
+    pub timeout_secs: Option<u64>,
+    /// How long Ollama keeps the model loaded after answering, its own default when unset.
+    pub keep_alive: Option<KeepAliveConfig>,
+    #[serde(default)]
+    pub options: ModelOptions,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub tools: ToolsConfig,
+}
+
+fn default_summarize() -> bool {
+    true
+}
+
+fn default_max_tool_iterations() -> u32 {
+    3
+}
+
+fn default_last_answers() -> usize {
+    10
+}
+
+fn default_cache_ttl_secs() -> u64 {
+    24 * 60 * 60
+}
+
+/// Colors from the `[theme]` table, written like `color`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ThemeConfig {
+    /// Answer text, replacing `color`.
+    pub answer: Option<String>,
+    pub error: Option<String>,
+    pub spinner: Option<String>,
+}
+
+impl ThemeConfig {
+    /// Parses every color, the answer falling back to `color`.
+    pub fn resolve(&self, color: &str) -> Result<Theme, String> {
+        let parse = |key: &str, value: &str| parse_color(value).map_err(|e| format!("{key}: {e}"));
+        Ok(Theme {
+            answer: match &self.answer {
+                Some(answer) => parse("theme.answer", answer)?,
+                None => parse("color", color)?,
+            },
+            error: match &self.error {
+                Some(error) => parse("theme.error", error)?,
+                None => Color::Red,
+            },
+            spinner: self
+                .spinner
+                .as_deref()
+                .map(|spinner| parse("theme.spinner", spinner))
+                .transpose()?,
+        })
+    }
+}
+
+/// `keep_alive` as written in the config: `-1` keeps the model loaded forever, `0` unloads it
+/// right after the answer, other numbers are seconds and strings are durations like `"10m"`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "KeepAliveValue")]
+pub struct KeepAliveConfig(pub KeepAlive);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeepAliveValue {
+    Seconds(i64),
+    Duration(String),
+}
+
+impl TryFrom<KeepAliveValue> for KeepAliveConfig {
+    type Error = String;
+
+    fn try_from(value: KeepAliveValue) -> Result<Self, Self::Error> {
+        let value = match value {
+            KeepAliveValue::Seconds(seconds) => seconds.to_string(),
+            KeepAliveValue::Duration(duration) => duration,
+        };
+        parse_keep_alive(value.trim()).map(Self).ok_or_else(|| {
+            format!(
+                "invalid keep_alive '{value}', expected -1, 0, seconds or a duration like \"10m\""
+            )
+        })
+    }
+}
+
+fn parse_keep_alive(value: &str) -> Option<KeepAlive> {
+    match value {
+        "-1" => return Some(KeepAlive::Indefinitely),
+        "0" => return Some(KeepAlive::UnloadOnCompletion),
+        _ => {}
+    }
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let time: u64 = value[..split].parse().ok()?;
+    let (time, unit) = match &value[split..] {
+        "" | "s" => (time, TimeUnit::Seconds),
+        "m" => (time, TimeUnit::Minutes),
+        // ollama-rs writes hours as "hr", which the server doesn't accept.
+        "h" => (time * 60, TimeUnit::Minutes),
+        _ => return None,
+    };
+    Some(KeepAlive::Until { time, unit })
+}
+
+/// The API of the server answering shark.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    Ollama,
+    /// OpenAI's chat completions API, as served by OpenAI, vLLM, llama.cpp and others.
+    OpenAi,
+}
+
+/// Which Ollama endpoint answers are generated with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Prompts rendered from the templates, with tool output summarized by a second prompt.
+    #[default]
+    Generate,
+    /// Chat messages carrying the system prompt, the history and the tool output.
+    Chat,
+}
+
+/// Length of the summary of tool output, rendered as `length` into the summary template.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryLength {
+    /// One or two sentences.
+    #[default]
+    Short,
+    /// A paragraph of about five sentences.
+    Medium,
+    /// Several paragraphs keeping the relevant details.
+    Detailed,
+}
+
+impl SummaryLength {
+    pub fn instruction(self) -> &'static str {
+        match self {
+            Self::Short => "a short summary of one or two sentences",
+            Self::Medium => "a summary of one paragraph, about five sentences",
+            Self::Detailed => {
+                "a detailed summary of several paragraphs that keeps the relevant names, numbers and steps"
+            }
+        }
+    }
+}
+
+/// How answers are written to the terminal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Render {
+    /// Stream text as it arrives.
+    #[default]
+    Plain,
+    /// Wait for the full answer and render it as markdown.
+    Markdown,
+}
+
+/// Sampling options from the `[options]` table. Unset values keep the model's defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModelOptions {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    pub num_predict: Option<i32>,
+    /// Seeds the sampling, so the same prompt gets the same answer from models that honor it.
+    pub seed: Option<i32>,
+}
+
+impl ModelOptions {
+    /// Fills the options that aren't set from `defaults`.
+    pub fn or(self, defaults: ModelOptions) -> ModelOptions {
+        ModelOptions {
+            temperature: self.temperature.or(defaults.temperature),
+            top_p: self.top_p.or(defaults.top_p),
+            top_k: self.top_k.or(defaults.top_k),
+            num_predict: self.num_predict.or(defaults.num_predict),
+            seed: self.seed.or(defaults.seed),
+        }
+    }
+
+    pub fn generation_options(&self) -> GenerationOptions {
+        let mut options = GenerationOptions::default();
+        if let Some(temperature) = self.temperature {
+            options = options.temperature(temperature);
+        }
+        if let Some(top_p) = self.top_p {
+            options = options.top_p(top_p);
+        }
+        if let Some(top_k) = self.top_k {
+            options = options.top_k(top_k);
+        }
+        if let Some(num_predict) = self.num_predict {
+            options = options.num_predict(num_predict);
+        }
+        if let Some(seed) = self.seed {
+            options = options.seed(seed);
+        }
+        options
+    }
+}
+
+/// Quick sampling settings for `--creative` and `--precise`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Temperature 1.2 and top_p 0.95, for brainstorming and varied wording.
+    Creative,
+    /// Temperature 0.1 and top_p 0.5, for factual answers and code.
+    Precise,
+}
+
+impl Preset {
+    pub fn options(self) -> ModelOptions {
+        let (temperature, top_p) = match self {
+            Self::Creative => (1.2, 0.95),
+            Self::Precise => (0.1, 0.5),
+        };
+        ModelOptions {
+            temperature: Some(temperature),
+            top_p: Some(top_p),
+            ..ModelOptions::default()
+        }
+    }
+}
+
+/// Retries for requests that fail because the Ollama server can't be reached,
+/// e.g. while it's still starting up.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every following attempt up to a minute.
+    pub base_delay_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay_ms: 500,
+        }
+    }
+}
+
+/// Values that may replace the ones read from the config file.
+#[derive(Debug, Default)]
+pub struct Overrides {
+    pub model: Option<String>,
+    pub addr: Option<String>,
+    pub color: Option<String>,
+    pub options: ModelOptions,
+}
+
+/// Locates the config file, trying in order:
+/// `$CONFIG`, `$XDG_CONFIG_HOME/shark/config.toml`, `~/.config/shark/config.toml`
+/// and `./shark.toml`.
+pub fn resolve_config_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+
+    config_candidates().into_iter().find(|path| path.is_file())
+}
+
+fn config_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+        candidates.push(Path::new(&dir).join("shark").join("config.toml"));
+    }
+    if let Ok(home) = std::env::var("HOME") {
+        candidates.push(Path::new(&home).join(".config").join("shark").join("config.toml"));
+    }
+    candidates.push(PathBuf::from("shark.toml"));
+    candidates
+}
+
+/// Explains where a config file is expected when none was found.
+pub fn missing_config_message() -> String {
+    let locations = config_candidates()
+        .iter()
+        .map(|path| format!("  {}", path.display()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "No config file found. Set CONFIG or create one at any of:\n{locations}\n\
+         See example-config.toml for the available options."
+    )
+}
+
+/// Syntax of the config file, picked by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Files without an extension are read as TOML.
+    fn from_path(path: &Path) -> Result<Self, ConfigError> {
+        let Some(extension) = path.extension() else {
+            return Ok(Self::Toml);
+        };
+        match extension.to_string_lossy().to_lowercase().as_str() {
+            "toml" => Ok(Self::Toml),
+            "json" => Ok(Self::Json),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            other => Err(ConfigError::UnsupportedFormat(
+                path.to_path_buf(),
+                other.to_string(),
+            )),
+        }
+    }
+
+    fn parse(self, source: &str) -> Result<Map<String, Value>, String> {
+        match self {
+            Self::Toml => toml::from_str(source).map_err(|e| e.to_string()),
+            Self::Json => serde_json::from_str(source).map_err(|e| e.to_string()),
+            Self::Yaml => serde_yaml::from_str(source).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    NotFound(PathBuf),
+    PermissionDenied(PathBuf),
+    Read(PathBuf, std::io::Error),
+    UnsupportedFormat(PathBuf, String),
+    Parse(PathBuf, String),
+    UnknownProfile {
+        name: String,
+        available: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(path) => write!(f, "config file {} does not exist", path.display()),
+            Self::PermissionDenied(path) => {
+                write!(f, "no permission to read config file {}", path.display())
+            }
+            Self::Read(path, e) => write!(f, "failed to read config file {}: {e}", path.display()),
+            Self::UnsupportedFormat(path, extension) => write!(
+                f,
+                "unsupported config file {}: '.{extension}' is not .toml, .json, .yaml or .yml",
+                path.display()
+            ),
+            Self::Parse(path, e) => write!(f, "invalid config file {}: {e}", path.display()),
+            Self::UnknownProfile { name, available } if available.is_empty() => {
+                write!(
+                    f,
+                    "unknown profile '{name}', the config file has no profiles"
+                )
+            }
+            Self::UnknownProfile { name, available } => write!(
+                f,
+                "unknown profile '{name}', available profiles: {}",
+                available.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Reads the config file as TOML, JSON or YAML depending on its extension, applying the
+/// `[profiles.<name>]` table on top of the top-level keys when `profile` is given.
+///
+/// Without an explicit profile the `default` profile is used if the file has one,
+/// otherwise the top-level keys are used as they are.
+pub fn parse_config(path: &Path, profile: Option<&str>) -> Result<Config, ConfigError> {
+    let format = ConfigFormat::from_path(path)?;
+    let file = std::fs::read_to_string(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => ConfigError::NotFound(path.to_path_buf()),
+        std::io::ErrorKind::PermissionDenied => ConfigError::PermissionDenied(path.to_path_buf()),
+        _ => ConfigError::Read(path.to_path_buf(), e),
+    })?;
+    let mut table = format
+        .parse(&file)
+        .map_err(|e| ConfigError::Parse(path.to_path_buf(), e))?;
+
+    let profiles = match table.remove("profiles") {
+        Some(Value::Object(profiles)) => profiles,
+        _ => Map::new(),
+    };
+    let selected = match profile {
+        Some(name) => Some(
+            profiles
+                .get(name)
+                .ok_or_else(|| ConfigError::UnknownProfile {
+                    name: name.to_string(),
+                    available: profiles.keys().cloned().collect(),
+                })?,
+        ),
+        None => profiles.get(DEFAULT_PROFILE),
+    };
+    if let Some(Value::Object(selected)) = selected {
+        merge_tables(&mut table, selected.clone());
+    }
+
+    let config: Config = serde_json::from_value(Value::Object(table))
+        .map_err(|e| ConfigError::Parse(path.to_path_buf(), e.to_string()))?;
+    if config.max_output_tokens == Some(0) {
+        return Err(ConfigError::Parse(
+            path.to_path_buf(),
+            "max_output_tokens must be at least 1".to_string(),
+        ));
+    }
+    Ok(config)
+}
+
+/// Overlays `overlay` onto `base`, merging nested tables key by key.
+fn merge_tables(base: &mut Map<String, Value>, overlay: Map<String, Value>) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Object(base)), Value::Object(overlay)) => merge_tables(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Applies overrides on top of the parsed config.
+///
+/// Each field resolves in this order: CLI flag, then the `SHARK_MODEL`,
+/// `SHARK_ADDR` and `SHARK_COLOR` environment variables, then the config file.
+/// The API token comes from `OLLAMA_API_TOKEN` and the API key from `OPENAI_API_KEY`,
+/// then the config file. Sampling options only come from CLI flags or the config file.
+/// The translator tool falls back to the resolved server, token and model, when that
+/// server is Ollama.
+pub fn merge(mut config: Config, overrides: Overrides) -> Config {
+    if let Some(answer) = config.theme.answer.take() {
+        config.color = answer;
+    }
+    config.model = resolve(overrides.model, "SHARK_MODEL", config.model);
+    config.addr = resolve(overrides.addr, "SHARK_ADDR", config.addr);
+    config.color = resolve(overrides.color, "SHARK_COLOR", config.color);
+    config.api_token = std::env::var("OLLAMA_API_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+        .or(config.api_token);
+    config.api_key = std::env::var("OPENAI_API_KEY")
+        .ok()
+        .filter(|key| !key.is_empty())
+        .or(config.api_key);
+
+    config.options = overrides.options.or(config.options);
+
+    let translator = &mut config.tools.translator;
+    if translator.addr.is_none() && config.backend == BackendKind::Ollama {
+        translator.addr = Some(config.addr.clone());
+        translator.api_token = translator.api_token.take().or(config.api_token.clone());
+    }
+    translator.model.get_or_insert_with(|| config.model.clone());
+    config
+}
+
+fn resolve(flag: Option<String>, env: &str, file: String) -> String {
+    flag.or_else(|| std::env::var(env).ok()).unwrap_or(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"
+addr = "http://localhost:11434"
+model = "llama3.1"
+color = "green"
+functions = []
+"#;
+
+    /// Writes `source` to `name` in a directory of its own, named after the test.
+    fn write_config(test: &str, name: &str, source: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shark-config-{test}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, source).unwrap();
+        path
+    }
+
+    #[test]
+    fn unknown_profiles_list_the_available_ones() {
+        let source = format!(
+            "{BASE}\n[profiles.work]\nmodel = \"qwen2.5\"\n[profiles.home]\nmodel = \"llama3.2\"\n"
+        );
+        let path = write_config("unknown", "config.toml", &source);
+        let error = parse_config(&path, Some("play")).unwrap_err().to_string();
+        assert!(error.starts_with("unknown profile 'play'"), "{error}");
+        assert!(error.contains("work") && error.contains("home"), "{error}");
+
+        let path = write_config("no-profiles", "config.toml", BASE);
+        let error = parse_config(&path, Some("play")).unwrap_err().to_string();
+        assert!(
+            error.ends_with("the config file has no profiles"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn the_default_profile_applies_without_one_given() {
+        let source = format!(
+            "{BASE}\n[profiles.default]\nmodel = \"qwen2.5\"\n[profiles.work]\nmodel = \"llama3.2\"\n"
+        );
+        let path = write_config("default", "config.toml", &source);
+        assert_eq!(parse_config(&path, None).unwrap().model, "qwen2.5");
+        assert_eq!(parse_config(&path, Some("work")).unwrap().model, "llama3.2");
+    }
+
+    #[test]
+    fn profiles_override_nested_tables_key_by_key() {
+        let source = format!(
+            "{BASE}\n[tools.http_fetch]\nmax_chars = 100\ntimeout_secs = 5\n\
+             [profiles.work.tools.http_fetch]\nmax_chars = 200\n"
+        );
+        let path = write_config("nested", "config.toml", &source);
+        let config = parse_config(&path, Some("work")).unwrap();
+        assert_eq!(config.tools.http_fetch.max_chars, 200);
+        assert_eq!(config.tools.http_fetch.timeout_secs, 5);
+        assert_eq!(config.model, "llama3.1");
+    }
+
+    #[test]
+    fn json_and_yaml_read_like_toml() {
+        let toml = format!("{BASE}\nmode = \"chat\"\n[options]\ntemperature = 0.5\n");
+        let json = r#"{
+            "addr": "http://localhost:11434",
+            "model": "llama3.1",
+            "color": "green",
+            "functions": [],
+            "mode": "chat",
+            "options": {"temperature": 0.5}
+        }"#;
+        let yaml = "addr: http://localhost:11434\nmodel: llama3.1\ncolor: green\nfunctions: []\n\
+                    mode: chat\noptions:\n  temperature: 0.5\n";
+
+        let expected = parse_config(&write_config("formats", "config.toml", &toml), None);
+        let expected = format!("{:?}", expected.unwrap());
+        for (name, source) in [
+            ("config.json", json),
+            ("config.yaml", yaml),
+            ("config.yml", yaml),
+        ] {
+            let config = parse_config(&write_config("formats", name, source), None).unwrap();
+            assert_eq!(format!("{config:?}"), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn unsupported_extensions_are_named() {
+        let path = write_config("ini", "config.ini", BASE);
+        let error = parse_config(&path, None).unwrap_err().to_string();
+        assert!(
+            error.contains("config.ini: '.ini' is not .toml, .json, .yaml or .yml"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn flags_win_over_the_environment_which_wins_over_the_file() {
+        let config: Config = toml::from_str(BASE).unwrap();
+        std::env::set_var("SHARK_MODEL", "env-model");
+        std::env::set_var("SHARK_ADDR", "http://env:11434");
+        std::env::remove_var("SHARK_COLOR");
+        let overrides = Overrides {
+            model: Some("flag-model".to_string()),
+            ..Overrides::default()
+        };
+        let config = merge(config, overrides);
+        std::env::remove_var("SHARK_MODEL");
+        std::env::remove_var("SHARK_ADDR");
+
+        assert_eq!(config.model, "flag-model");
+        assert_eq!(config.addr, "http://env:11434");
+        assert_eq!(config.color, "green");
+    }
+
+    fn keep_alive(value: &str) -> Result<String, String> {
+        let config: KeepAliveConfig = serde_json::from_str(value).map_err(|e| e.to_string())?;
+        Ok(format!("{:?}", config.0))
+    }
+
+    #[test]
+    fn keep_alive_takes_durations_and_seconds() {
+        let minutes = |time| {
+            format!(
+                "{:?}",
+                KeepAlive::Until {
+                    time,
+                    unit: TimeUnit::Minutes
+                }
+            )
+        };
+        assert_eq!(keep_alive("\"10m\""), Ok(minutes(10)));
+        assert_eq!(keep_alive("\"1h\""), Ok(minutes(60)));
+        assert_eq!(
+            keep_alive("-1"),
+            Ok(format!("{:?}", KeepAlive::Indefinitely))
+        );
+        assert_eq!(
+            keep_alive("\"-1\""),
+            Ok(format!("{:?}", KeepAlive::Indefinitely))
+        );
+        assert_eq!(
+            keep_alive("0"),
+            Ok(format!("{:?}", KeepAlive::UnloadOnCompletion))
+        );
+        assert_eq!(
+            keep_alive("90"),
+            Ok(format!(
+                "{:?}",
+                KeepAlive::Until {
+                    time: 90,
+                    unit: TimeUnit::Seconds
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn malformed_keep_alive_is_an_error() {
+        for value in ["\"10 minutes\"", "\"m\"", "\"-5\"", "\"1.5h\""] {
+            let error = keep_alive(value).unwrap_err();
+            assert!(error.starts_with("invalid keep_alive"), "{value}: {error}");
+        }
+    }
+}
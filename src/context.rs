@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use crate::output;
+
+/// Combined size of the `--context` files, longer contents are cut off.
+pub const MAX_CONTEXT_CHARS: usize = 32_000;
+
+/// Reads the `--context` files into one block, each labeled with its path.
+pub fn load(paths: &[PathBuf]) -> Result<Option<String>, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read context file {}: {e}", path.display()))?;
+        files.push((path.display().to_string(), contents));
+    }
+
+    let (context, truncated) = join(&files, MAX_CONTEXT_CHARS);
+    if !truncated.is_empty() {
+        output::warning(&format!(
+            "context files are limited to {MAX_CONTEXT_CHARS} characters, truncated {}",
+            truncated.join(", ")
+        ));
+    }
+    Ok((!files.is_empty()).then_some(context))
+}
+
+/// Labels every file with its name and cuts the contents off after `max_chars` characters
+/// in total, leaving out files once the budget is used up. Returns the names of the files
+/// that didn't fit whole.
+fn join(files: &[(String, String)], max_chars: usize) -> (String, Vec<String>) {
+    let mut context = String::new();
+    let mut remaining = max_chars;
+    let mut truncated = Vec::new();
+    for (name, contents) in files {
+        let contents = contents.trim_end();
+        if remaining == 0 {
+            truncated.push(name.clone());
+            continue;
+        }
+        let kept = match contents.char_indices().nth(remaining) {
+            Some((end, _)) => {
+                truncated.push(name.clone());
+                &contents[..end]
+            }
+            None => contents,
+        };
+        remaining -= kept.chars().count();
+        context.push_str(&format!("--- {name} ---\n{kept}\n"));
+    }
+    (context, truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, contents: &str) -> (String, String) {
+        (name.to_string(), contents.to_string())
+    }
+
+    #[test]
+    fn files_are_labeled_and_capped_together() {
+        let files = [file("a.md", "alpha\n"), file("b.txt", "beta")];
+        let (context, truncated) = join(&files, 100);
+        assert_eq!(context, "--- a.md ---\nalpha\n--- b.txt ---\nbeta\n");
+        assert!(truncated.is_empty());
+
+        let files = [
+            file("a.md", "alpha"),
+            file("b.txt", "beta"),
+            file("c", "gamma"),
+        ];
+        let (context, truncated) = join(&files, 7);
+        assert_eq!(context, "--- a.md ---\nalpha\n--- b.txt ---\nbe\n");
+        assert_eq!(truncated, ["b.txt", "c"]);
+    }
+
+    #[test]
+    fn missing_files_are_reported() {
+        let error = load(&[PathBuf::from("missing.md")]).unwrap_err();
+        assert!(
+            error.starts_with("cannot read context file missing.md"),
+            "{error}"
+        );
+        assert_eq!(load(&[]), Ok(None));
+    }
+}
@@ -0,0 +1,130 @@
+use std::{error::Error, process::ExitCode};
+
+use ollama_rs::error::OllamaError;
+
+use crate::{
+    config::ConfigError,
+    shark::{ModelNotPulled, Timeout},
+};
+
+/// Messages of errors raised when the server can't be reached, or stops answering.
+pub const CONNECTION_ERRORS: [&str; 5] = [
+    "error sending request",
+    "connection refused",
+    "connection reset",
+    "connection closed",
+    "timed out",
+];
+
+/// What went wrong, so scripts calling shark can branch on the exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The Ollama server can't be reached. Exits with 2.
+    Connection,
+    /// The model isn't available on the server. Exits with 3.
+    Model,
+    /// No answer within `timeout_secs`. Exits with 4.
+    Timeout,
+    /// The config file or a prompt template can't be used. Exits with 5.
+    Config,
+    /// Anything else. Exits with 1.
+    Other,
+}
+
+impl ErrorKind {
+    /// The category of `e` or of one of its sources, if it is recognized.
+    pub fn of(e: &(dyn Error + 'static)) -> Option<Self> {
+        std::iter::successors(Some(e), |&e| e.source()).find_map(|e| {
+            if e.is::<Timeout>() {
+                Some(Self::Timeout)
+            } else if e.is::<ModelNotPulled>() {
+                Some(Self::Model)
+            } else if e.is::<ConfigError>() || e.is::<minijinja::Error>() {
+                Some(Self::Config)
+            } else if e.is::<OllamaError>() || e.is::<reqwest::Error>() {
+                is_connection_error(&e.to_string()).then_some(Self::Connection)
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn code(self) -> u8 {
+        match self {
+            Self::Connection => 2,
+            Self::Model => 3,
+            Self::Timeout => 4,
+            Self::Config => 5,
+            Self::Other => 1,
+        }
+    }
+
+    pub fn exit_code(self) -> ExitCode {
+        ExitCode::from(self.code())
+    }
+
+    /// The friendly line shown above the error itself.
+    pub fn headline(self) -> &'static str {
+        match self {
+            Self::Connection => {
+                "Sorry I can't reach the Ollama server, please check that it is running.😭"
+            }
+            Self::Model => "Sorry the model isn't available on the server.😭",
+            Self::Timeout => "Sorry the answer is taking too long, please try again later.😭",
+            Self::Config => "Sorry I can't answer with this setup, please check the config.😭",
+            Self::Other => {
+                "Sorry I can't answer your question right now, please try again later.😭"
+            }
+        }
+    }
+}
+
+pub fn is_connection_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    CONNECTION_ERRORS
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn errors_are_categorized() {
+        let timeout = Timeout(Duration::from_secs(1));
+        assert_eq!(ErrorKind::of(&timeout), Some(ErrorKind::Timeout));
+        let missing = ModelNotPulled("llama3".to_string());
+        assert_eq!(ErrorKind::of(&missing), Some(ErrorKind::Model));
+        let config = ConfigError::NotFound("shark.toml".into());
+        assert_eq!(ErrorKind::of(&config), Some(ErrorKind::Config));
+        let template = minijinja::Environment::new()
+            .render_str("{{ question | nope }}", ())
+            .unwrap_err();
+        assert_eq!(ErrorKind::of(&template), Some(ErrorKind::Config));
+
+        let refused = OllamaError::from("error sending request: connection refused".to_string());
+        assert_eq!(ErrorKind::of(&refused), Some(ErrorKind::Connection));
+        let rejected = OllamaError::from("invalid options".to_string());
+        assert_eq!(ErrorKind::of(&rejected), None);
+        let other: Box<dyn Error> = "something else".into();
+        assert_eq!(ErrorKind::of(&*other), None);
+    }
+
+    #[test]
+    fn every_category_has_its_own_exit_code() {
+        let kinds = [
+            ErrorKind::Connection,
+            ErrorKind::Model,
+            ErrorKind::Timeout,
+            ErrorKind::Config,
+            ErrorKind::Other,
+        ];
+        let mut codes: Vec<u8> = kinds.iter().map(|kind| kind.code()).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes, [1, 2, 3, 4, 5]);
+    }
+}
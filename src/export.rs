@@ -0,0 +1,95 @@
+use std::{fs::OpenOptions, io::Write, path::Path};
+
+use ollama_rs::generation::chat::{ChatMessage, MessageRole};
+
+/// Renders the question and answer turns of `messages` under `## You` / `## Shark` headers.
+pub fn to_markdown(messages: &[ChatMessage]) -> String {
+    let mut markdown = String::new();
+    for message in messages {
+        let header = match message.role {
+            MessageRole::User => "You",
+            MessageRole::Assistant => "Shark",
+            MessageRole::System | MessageRole::Tool => continue,
+        };
+        if !markdown.is_empty() {
+            markdown.push('\n');
+        }
+        markdown.push_str(&format!("## {header}\n\n{}\n", message.content.trim()));
+    }
+    markdown
+}
+
+fn front_matter(model: &str) -> String {
+    let exported_at = chrono::Local::now().format("%Y-%m-%d %H:%M");
+    format!("---\nmodel: {model}\nexported_at: {exported_at}\n---\n\n")
+}
+
+/// Writes `messages` to `path` as markdown, starting with a front matter block naming `model`.
+/// With `append`, the turns are added to the end of an existing transcript instead.
+/// Returns `false`, leaving the file untouched, when there is nothing to export.
+pub fn export(
+    path: &Path,
+    messages: &[ChatMessage],
+    model: &str,
+    append: bool,
+) -> std::io::Result<bool> {
+    let turns = to_markdown(messages);
+    if turns.is_empty() {
+        return Ok(false);
+    }
+
+    let continues = append && path.metadata().is_ok_and(|m| m.len() > 0);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)?;
+    if continues {
+        file.write_all(b"\n")?;
+    } else {
+        file.write_all(front_matter(model).as_bytes())?;
+    }
+    file.write_all(turns.as_bytes())?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns_get_a_header_each() {
+        let messages = [
+            ChatMessage::system("persona".to_string()),
+            ChatMessage::user("what is rust?".to_string()),
+            ChatMessage::assistant("A language.\n".to_string()),
+            ChatMessage::new(MessageRole::Tool, "{}".to_string()),
+        ];
+        assert_eq!(
+            to_markdown(&messages),
+            "## You\n\nwhat is rust?\n\n## Shark\n\nA language.\n"
+        );
+    }
+
+    #[test]
+    fn appending_keeps_a_single_front_matter() {
+        let path = std::env::temp_dir().join(format!("shark-export-{}.md", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let turn = [
+            ChatMessage::user("q".to_string()),
+            ChatMessage::assistant("a".to_string()),
+        ];
+
+        assert!(!export(&path, &[], "llama3.1", true).unwrap());
+        assert!(!path.exists());
+        assert!(export(&path, &turn, "llama3.1", true).unwrap());
+        assert!(export(&path, &turn, "llama3.1", true).unwrap());
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(content.starts_with("---\nmodel: llama3.1\n"));
+        assert_eq!(content.matches("---\n").count(), 2);
+        assert_eq!(content.matches("## You").count(), 2);
+    }
+}
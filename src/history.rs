@@ -0,0 +1,160 @@
+use std::path::{Path, PathBuf};
+
+use ollama_rs::generation::chat::{ChatMessage, MessageRole};
+
+type Error = Box<dyn std::error::Error + 'static>;
+
+const DEFAULT_MAX_MESSAGES: usize = 20;
+
+/// Where the session is saved next to the config file when `history_path` isn't set.
+pub const FILE_NAME: &str = "history.json";
+
+/// Conversation turns kept between questions, optionally persisted as JSON.
+pub struct History {
+    messages: Vec<ChatMessage>,
+    max_messages: usize,
+    path: Option<PathBuf>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            messages: Vec::new(),
+            max_messages: DEFAULT_MAX_MESSAGES,
+            path: None,
+        }
+    }
+}
+
+impl History {
+    pub fn new(max_messages: Option<usize>) -> Self {
+        Self {
+            max_messages: max_messages.unwrap_or(DEFAULT_MAX_MESSAGES),
+            ..Default::default()
+        }
+    }
+
+    /// An empty history that replaces whatever `path` held on the next save.
+    pub fn fresh(path: impl AsRef<Path>, max_messages: Option<usize>) -> Self {
+        Self {
+            path: Some(path.as_ref().to_path_buf()),
+            ..Self::new(max_messages)
+        }
+    }
+
+    /// Loads history from `path`, starting empty if the file doesn't exist yet.
+    pub fn load(path: impl AsRef<Path>, max_messages: Option<usize>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let mut history = Self::new(max_messages);
+        if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            history.messages = serde_json::from_str(&content)?;
+            history.evict();
+        }
+        history.path = Some(path);
+        Ok(history)
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&self.messages)?)?;
+        Ok(())
+    }
+
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    pub fn push(&mut self, message: ChatMessage) {
+        self.messages.push(message);
+        self.evict();
+    }
+
+    /// Adds a question and its answer, then saves the history.
+    pub fn record(&mut self, question: ChatMessage, answer: String) -> Result<(), Error> {
+        self.push(question);
+        self.push(ChatMessage::assistant(answer));
+        self.save()
+    }
+
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    /// Drops the oldest turns past `max_messages`, a question and its answer together, so
+    /// the history never starts with an answer to a question that's gone.
+    fn evict(&mut self) {
+        let mut start = self.messages.len().saturating_sub(self.max_messages);
+        if start == 0 {
+            return;
+        }
+        while self
+            .messages
+            .get(start)
+            .is_some_and(|message| message.role != MessageRole::User)
+        {
+            start += 1;
+        }
+        self.messages.drain(..start);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(history: &History) -> Vec<&str> {
+        history
+            .messages()
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn recorded_turns_are_loaded_back() {
+        let path = std::env::temp_dir().join(format!("shark-history-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut history = History::load(&path, None).unwrap();
+        assert!(history.messages().is_empty());
+        history
+            .record(ChatMessage::user("q1".to_string()), "a1".to_string())
+            .unwrap();
+        history
+            .record(ChatMessage::user("q2".to_string()), "a2".to_string())
+            .unwrap();
+
+        let loaded = History::load(&path, None).unwrap();
+        assert_eq!(contents(&loaded), ["q1", "a1", "q2", "a2"]);
+        assert_eq!(loaded.messages()[1].role, MessageRole::Assistant);
+
+        let shorter = History::load(&path, Some(2)).unwrap();
+        assert_eq!(contents(&shorter), ["q2", "a2"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn whole_turns_are_evicted() {
+        let mut history = History::new(Some(3));
+        for turn in 1..=3 {
+            history
+                .record(ChatMessage::user(format!("q{turn}")), format!("a{turn}"))
+                .unwrap();
+        }
+        assert_eq!(contents(&history), ["q3", "a3"]);
+
+        let mut history = History::new(Some(4));
+        for turn in 1..=3 {
+            history
+                .record(ChatMessage::user(format!("q{turn}")), format!("a{turn}"))
+                .unwrap();
+        }
+        assert_eq!(contents(&history), ["q2", "a2", "q3", "a3"]);
+    }
+}
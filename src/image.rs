@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ollama_rs::generation::images::Image;
+
+/// Images above this size are refused rather than sent to the model.
+pub const MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Names the image format from the first bytes of a file.
+fn format(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some("png"),
+        [0xff, 0xd8, 0xff, ..] => Some("jpeg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("webp"),
+        _ => None,
+    }
+}
+
+/// Reads the image at `path` for `--image`, base64-encoded the way Ollama expects.
+pub fn load(path: &Path) -> Result<Image, String> {
+    let display = path.display();
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("cannot read {display}: {e}"))?
+        .len();
+    if size > MAX_IMAGE_BYTES {
+        return Err(format!(
+            "{display} is {} MB, images are limited to {} MB",
+            size / (1024 * 1024),
+            MAX_IMAGE_BYTES / (1024 * 1024)
+        ));
+    }
+
+    let bytes = std::fs::read(path).map_err(|e| format!("cannot read {display}: {e}"))?;
+    if format(&bytes).is_none() {
+        return Err(format!(
+            "{display} is not a supported image, use png, jpeg, gif or webp"
+        ));
+    }
+    Ok(Image::from_base64(STANDARD.encode(bytes)))
+}
+
+/// `image` as a `data:` URL, the way OpenAI-compatible servers take images.
+pub fn data_url(image: &Image) -> String {
+    let base64 = serde_json::to_value(image)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default();
+    // 16 characters decode to the 12 bytes telling every supported format apart.
+    let head = STANDARD
+        .decode(base64.get(..16).unwrap_or(&base64))
+        .unwrap_or_default();
+    let format = format(&head).unwrap_or("png");
+    format!("data:image/{format};base64,{base64}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_are_detected_from_magic_bytes() {
+        assert_eq!(format(b"\x89PNG\r\n\x1a\n...."), Some("png"));
+        assert_eq!(format(b"\xff\xd8\xff\xe0...."), Some("jpeg"));
+        assert_eq!(format(b"GIF89a...."), Some("gif"));
+        assert_eq!(format(b"RIFF\x10\0\0\0WEBPVP8 "), Some("webp"));
+        assert_eq!(format(b"RIFF\x10\0\0\0WAVEfmt "), None);
+        assert_eq!(format(b"fn main() {}"), None);
+        assert_eq!(format(b""), None);
+    }
+
+    #[test]
+    fn data_urls_name_the_format() {
+        let gif = Image::from_base64(STANDARD.encode(b"GIF89a\x01\0\x01\0\0\0\0;"));
+        let url = data_url(&gif);
+        assert!(url.starts_with("data:image/gif;base64,R0lGODlh"), "{url}");
+    }
+
+    #[test]
+    fn only_images_are_loaded() {
+        let error = load(Path::new("Cargo.toml")).unwrap_err();
+        assert!(error.contains("not a supported image"), "{error}");
+        assert!(load(Path::new("missing.png")).is_err());
+    }
+}
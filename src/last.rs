@@ -0,0 +1,105 @@
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+type Error = Box<dyn std::error::Error + 'static>;
+
+pub const FILE_NAME: &str = "last_answers.json";
+
+/// An answer kept around so `--last` can show it again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Answer {
+    pub question: String,
+    pub answer: String,
+    pub model: String,
+    pub used_tool: Option<String>,
+    pub answered_at: String,
+}
+
+/// The most recent answers, newest first, persisted as JSON.
+pub struct LastAnswers {
+    answers: VecDeque<Answer>,
+    max_answers: usize,
+    path: PathBuf,
+}
+
+impl LastAnswers {
+    /// Loads the answers stored at `path`, starting empty if the file doesn't exist yet.
+    pub fn load(path: impl AsRef<Path>, max_answers: usize) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let mut answers = VecDeque::new();
+        if path.exists() {
+            answers = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            answers.truncate(max_answers);
+        }
+        Ok(Self {
+            answers,
+            max_answers,
+            path,
+        })
+    }
+
+    /// The `n`th most recent answer, starting at 1.
+    pub fn get(&self, n: usize) -> Option<&Answer> {
+        self.answers.get(n.checked_sub(1)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.answers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty()
+    }
+
+    pub fn push(&mut self, answer: Answer) -> Result<(), Error> {
+        if self.max_answers == 0 {
+            return Ok(());
+        }
+        self.answers.push_front(answer);
+        self.answers.truncate(self.max_answers);
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.answers)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(question: &str) -> Answer {
+        Answer {
+            question: question.to_string(),
+            answer: String::new(),
+            model: "llama3.1".to_string(),
+            used_tool: None,
+            answered_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn keeps_the_newest_answers_first() {
+        let path = std::env::temp_dir().join(format!("shark-last-{}.json", std::process::id()));
+        let mut last_answers = LastAnswers::load(&path, 2).unwrap();
+        for question in ["one", "two", "three"] {
+            last_answers.push(answer(question)).unwrap();
+        }
+
+        let reloaded = LastAnswers::load(&path, 2).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded.get(1).unwrap().question, "three");
+        assert_eq!(reloaded.get(2).unwrap().question, "two");
+        assert!(reloaded.get(0).is_none());
+    }
+}
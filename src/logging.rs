@@ -0,0 +1,30 @@
+use termcolor::ColorChoice;
+use tracing_subscriber::{
+    fmt::{format::FmtSpan, time},
+    EnvFilter,
+};
+
+use crate::output;
+
+/// Sends logs to stderr so they never mix with the answer on stdout.
+///
+/// Spans report how long they took when they close.
+/// `RUST_LOG` wins over `verbosity`, which only shows warnings by default,
+/// shark's debug logs with `-v` and everything it traces with `-vv`.
+pub fn init(verbosity: u8) {
+    let default = match verbosity {
+        0 => "warn",
+        1 => "warn,shark=debug",
+        _ => "warn,shark=trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default));
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_timer(time::uptime())
+        .with_target(false)
+        .with_ansi(output::stderr_choice() != ColorChoice::Never)
+        .init();
+}
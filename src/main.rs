@@ -1,83 +1,1090 @@
-use std::io::Write;
+use std::{
+    io::{IsTerminal, Read},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
 
+use backend::{Backend, MockBackend, OllamaClient};
+use cache::Cache;
 use clap::Parser;
-use ollama_rs::{IntoUrlSealed, Ollama};
-use serde::Deserialize;
-use shark::Shark;
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use config::{
+    merge, missing_config_message, parse_config, resolve_config_path, BackendKind, Config, Mode,
+    ModelOptions, Overrides, Preset, Render, SummaryLength,
+};
+use error::ErrorKind;
+use futures::future::join_all;
+use history::History;
+use last::LastAnswers;
+use ollama_rs::{
+    generation::chat::{ChatMessage, ChatMessageResponseStream},
+    IntoUrlSealed,
+};
+use openai::OpenAiBackend;
+use output::{ColorWhen, Printer, ResetGuard, Theme};
+use serde_json::json;
+use shark::{Confirmation, Shark, Templates, Timeout, ToolCall};
+use spinner::Spinner;
+use stats::Stats;
+use termcolor::Color;
+use timing::Timings;
+use tokio::sync::Semaphore;
 use tokio_stream::StreamExt;
-use toml;
+use tools::ToolRegistry;
 
+pub mod backend;
+pub mod cache;
+pub mod config;
+pub mod context;
+pub mod error;
+pub mod export;
+pub mod history;
+pub mod image;
+pub mod last;
+pub mod logging;
+pub mod markdown;
+pub mod models;
+pub mod openai;
+pub mod output;
+pub mod pager;
+pub mod repl;
 pub mod shark;
+pub mod spinner;
+pub mod stats;
+pub mod timing;
 pub mod tools;
+pub mod utf8;
+pub mod wrap;
+
+/// Exit code after Ctrl-C, following the shell's 128 + SIGINT convention.
+const EXIT_INTERRUPTED: u8 = 130;
+
+/// How long a second Ctrl-C forces shark to exit while it is still cleaning up.
+const FORCE_EXIT_WINDOW: Duration = Duration::from_secs(2);
+
+/// Temperature for `--n` when none is configured, or when a zero temperature would make
+/// every sample the same.
+const SAMPLE_TEMPERATURE: f32 = 0.8;
+
+/// Samples generated at the same time, so a large `--n` doesn't swamp the server.
+const MAX_CONCURRENT_SAMPLES: usize = 3;
+
+const USAGE: &str = "Usage: shark <prompt>...\n       <command> | shark [prompt]...\n       shark --repl";
 
 #[derive(Debug, Parser)]
+#[command(version, disable_version_flag = true)]
 struct Args {
     prompt: Vec<String>,
-}
 
-#[derive(Debug, Deserialize)]
-struct Config {
-    addr: String,
-    model: String,
-    color: String,
-    functions: Vec<String>,
+    /// Keep asking questions in an interactive session
+    #[arg(long)]
+    repl: bool,
+
+    /// Continue the conversation of the previous run instead of starting a new one
+    #[arg(short = 'c', long = "continue")]
+    continue_session: bool,
+
+    /// Profile from the config file's `[profiles.<name>]` tables to use
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Model to use instead of the configured one
+    #[arg(long)]
+    model: Option<String>,
+
+    /// Ollama server address instead of the configured one
+    #[arg(long)]
+    addr: Option<String>,
+
+    /// Answer color instead of the configured one
+    #[arg(long, value_name = "COLOR")]
+    answer_color: Option<String>,
+
+    /// When to color the output
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t)]
+    color: ColorWhen,
+
+    /// System prompt for this run instead of the configured one, an empty one clears it
+    #[arg(long, value_name = "TEXT")]
+    system: Option<String>,
+
+    /// Sampling temperature instead of the configured one
+    #[arg(long)]
+    temperature: Option<f32>,
+
+    /// Nucleus sampling probability instead of the configured one
+    #[arg(long)]
+    top_p: Option<f32>,
+
+    /// Top-k sampling instead of the configured one
+    #[arg(long)]
+    top_k: Option<u32>,
+
+    /// Maximum number of tokens to generate instead of the configured one
+    #[arg(long)]
+    num_predict: Option<i32>,
+
+    /// Stop the answer after this many streamed tokens instead of the configured limit,
+    /// even when the server ignores --num-predict
+    #[arg(long, value_name = "TOKENS", value_parser = clap::value_parser!(u64).range(1..))]
+    max_output: Option<u64>,
+
+    /// Seed for sampling instead of the configured one, to get the same answer again
+    #[arg(long, conflicts_with = "count")]
+    seed: Option<i32>,
+
+    /// Sample more freely: temperature 1.2 and top_p 0.95
+    #[arg(long, conflicts_with = "precise")]
+    creative: bool,
+
+    /// Sample conservatively: temperature 0.1 and top_p 0.5
+    #[arg(long)]
+    precise: bool,
+
+    /// Log what shark is doing to stderr, -vv for more detail. RUST_LOG takes precedence
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Render the answer as markdown with highlighted code blocks
+    #[arg(long)]
+    markdown: bool,
+
+    /// Print token counts and throughput to stderr after the answer
+    #[arg(long)]
+    stats: bool,
+
+    /// Print how long each stage took to stderr after the answer: reading the config,
+    /// the tool call, waiting for the stream, the first token and the rest of the answer
+    #[arg(long, conflicts_with_all = ["repl", "count"])]
+    profile_perf: bool,
+
+    /// Print the output of the tool that ran instead of the model's summary of it
+    #[arg(long)]
+    no_summarize: bool,
+
+    /// How long the model's summary of tool output is instead of the configured length
+    #[arg(long, value_enum, value_name = "LENGTH")]
+    summary_length: Option<SummaryLength>,
+
+    /// Send the question as the whole prompt, without the generation template or tools
+    #[arg(long)]
+    raw: bool,
+
+    /// Ask the model even if the answer is cached
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Also write the answer to this file
+    #[arg(long, short, value_name = "FILE", conflicts_with = "repl")]
+    output: Option<PathBuf>,
+
+    /// Append the question and answer to this markdown transcript
+    #[arg(long, value_name = "FILE", conflicts_with = "repl")]
+    export: Option<PathBuf>,
+
+    /// Attach an image to the question, for vision models. Can be repeated
+    #[arg(long, value_name = "PATH", conflicts_with = "repl")]
+    image: Vec<PathBuf>,
+
+    /// Add a file's contents to the prompt as reference material. Can be repeated
+    #[arg(long, visible_alias = "append-context", value_name = "PATH")]
+    context: Vec<PathBuf>,
+
+    /// Run destructive tools without asking, even when require_confirmation is set
+    #[arg(long, short)]
+    yes: bool,
+
+    /// Don't print the answer, only write it to the --output file
+    #[arg(long, short, requires = "output")]
+    quiet: bool,
+
+    /// Stream the answer into $PAGER, or `less -R`, when stdout is a terminal
+    #[arg(long, conflicts_with_all = ["repl", "json"])]
+    pager: bool,
+
+    /// Print the prompts that would be sent to the model and exit without contacting it
+    #[arg(long, conflicts_with = "repl")]
+    dry_run: bool,
+
+    /// Generate this many answers to compare, printed one after another
+    #[arg(
+        long = "n",
+        visible_alias = "count",
+        value_name = "COUNT",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with_all = ["repl", "pager", "output", "export", "continue_session"]
+    )]
+    count: Option<u32>,
+
+    /// Print the whole answer as a single JSON object, for scripting
+    #[arg(long, conflicts_with = "repl")]
+    json: bool,
+
+    /// List the models pulled on the Ollama server and exit
+    #[arg(long)]
+    list_models: bool,
+
+    /// Print the most recent answer, or the Nth most recent, without asking the model
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+    last: Option<usize>,
+
+    /// Print the enabled tools with their descriptions and parameters and exit
+    #[arg(long)]
+    explain_tools: bool,
+
+    /// Pull a model onto the Ollama server and exit
+    #[arg(long, value_name = "MODEL")]
+    pull: Option<String>,
+
+    /// Answer with canned text instead of asking the Ollama server, to try shark offline
+    #[arg(long, conflicts_with_all = ["list_models", "pull"])]
+    mock: bool,
+
+    /// Print the config file path that would be used and exit
+    #[arg(long)]
+    print_config_path: bool,
+
+    /// Print the version and exit. With --verbose, also the config file, model, server
+    /// and tools in use, for bug reports
+    #[arg(short = 'V', long)]
+    version: bool,
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config_path = std::env::var("CONFIG").expect("Missing CONFIG");
-    let config = parse_config(&config_path);
-
+async fn main() -> ExitCode {
     let args = Args::parse();
-    let question = args.prompt.join(" ");
+    output::set_color_when(args.color);
+    let _reset = ResetGuard::stdout();
+    match run(args).await {
+        Ok(code) => code,
+        Err(e) => {
+            let _ = Printer::new(Color::Red).failure(e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: Args) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    logging::init(args.verbose);
+    let config_path = resolve_config_path();
+
+    if args.version {
+        println!("shark {}", env!("CARGO_PKG_VERSION"));
+        if args.verbose > 0 {
+            print_build_info(config_path.as_deref(), &args);
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if args.print_config_path {
+        match &config_path {
+            Some(path) => println!("{}", path.display()),
+            None => println!("{}", missing_config_message()),
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let question = if args.repl
+        || args.list_models
+        || args.explain_tools
+        || args.pull.is_some()
+        || args.last.is_some()
+    {
+        None
+    } else {
+        match read_question(&args.prompt)? {
+            Some(question) => Some(question),
+            None => {
+                println!("{USAGE}");
+                return Ok(ExitCode::SUCCESS);
+            }
+        }
+    };
+
+    let Some(config_path) = config_path else {
+        eprintln!("{}", missing_config_message());
+        return Ok(ErrorKind::Config.exit_code());
+    };
+
+    let images = match args.image.iter().map(|path| image::load(path)).collect() {
+        Ok(images) => images,
+        Err(e) => {
+            Printer::new(Color::Red).failure(e)?;
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    let context = match context::load(&args.context) {
+        Ok(context) => context,
+        Err(e) => {
+            Printer::new(Color::Red).failure(e)?;
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    let preset = if args.creative {
+        Some(Preset::Creative)
+    } else if args.precise {
+        Some(Preset::Precise)
+    } else {
+        None
+    };
+    let options = ModelOptions {
+        temperature: args.temperature,
+        top_p: args.top_p,
+        top_k: args.top_k,
+        num_predict: args.num_predict,
+        seed: args.seed,
+    };
+    let overrides = Overrides {
+        model: args.model,
+        addr: args.addr,
+        color: args.answer_color,
+        options: match preset {
+            Some(preset) => options.or(preset.options()),
+            None => options,
+        },
+    };
+    let timings = if args.profile_perf {
+        Timings::enabled()
+    } else {
+        Timings::default()
+    };
+    let started = Instant::now();
+    let config = parse_config(&config_path, args.profile.as_deref());
+    timings.record("config parse", started);
+    let config = match config {
+        Ok(config) => merge(config, overrides),
+        Err(e) => {
+            Printer::new(Color::Red).failure(e)?;
+            return Ok(ErrorKind::Config.exit_code());
+        }
+    };
+
+    let theme = match config.theme.resolve(&config.color) {
+        Ok(theme) => theme,
+        Err(e) => {
+            Printer::new(Color::Red).failure(e)?;
+            return Ok(ErrorKind::Config.exit_code());
+        }
+    };
+
+    let last_answers_path = config_path.with_file_name(last::FILE_NAME);
+    if let Some(n) = args.last {
+        return show_last_answer(&last_answers_path, &config, theme, n, args.json);
+    }
+
+    let url = match config.addr.as_str().into_url() {
+        Ok(url) => url,
+        Err(e) => {
+            Printer::new(Color::Red)
+                .failure(format!("invalid server address {}: {e}", config.addr))?;
+            return Ok(ErrorKind::Config.exit_code());
+        }
+    };
+    let ollama = match OllamaClient::new(url.clone(), config.api_token.as_deref()) {
+        Ok(ollama) => ollama,
+        Err(e) => {
+            Printer::new(Color::Red).failure(e)?;
+            return Ok(ErrorKind::Config.exit_code());
+        }
+    };
+
+    let openai = config.backend == BackendKind::OpenAi;
+    if openai && (args.pull.is_some() || args.list_models) {
+        Printer::new(Color::Red)
+            .failure("--pull and --list-models need an Ollama server, not backend = \"openai\"")?;
+        return Ok(ErrorKind::Config.exit_code());
+    }
+    if let Some(model) = &args.pull {
+        if let Err(e) = models::pull(&ollama, model).await {
+            Printer::new(Color::Red).failure(&e)?;
+            return Ok(ErrorKind::of(&*e).unwrap_or(ErrorKind::Model).exit_code());
+        }
+        let mut printer = Printer::new(Color::Green);
+        printer.answer(&format!("pulled {model}"))?;
+        printer.finish()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if args.list_models {
+        if let Err(e) = models::list_models(&ollama, &config.model).await {
+            Printer::new(Color::Red).failure(&e)?;
+            return Ok(ErrorKind::of(&*e).unwrap_or(ErrorKind::Other).exit_code());
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+    if config.check_models && !openai && !args.mock && !args.explain_tools {
+        let mut models = vec![config.model.as_str()];
+        models.extend(config.summary_model.as_deref());
+        if let Err(e) = models::check_pulled(&ollama, &models).await {
+            Printer::new(Color::Red).failure(&e)?;
+            return Ok(ErrorKind::of(&*e).unwrap_or(ErrorKind::Model).exit_code());
+        }
+    }
 
-    let url = config.addr.into_url().unwrap();
-    let ollama = Ollama::from_url(url);
-    let shark = Shark::new(ollama, config.model, config.functions);
+    let templates = match Templates::load(
+        config.generation_template.as_deref(),
+        config.summary_template.as_deref(),
+    ) {
+        Ok(templates) => templates,
+        Err(e) => {
+            Printer::new(Color::Red).failure(e)?;
+            return Ok(ErrorKind::Config.exit_code());
+        }
+    };
+
+    let backend: Box<dyn Backend> = if args.mock {
+        Box::new(MockBackend::default())
+    } else if openai {
+        match OpenAiBackend::new(url, config.api_key.as_deref()) {
+            Ok(backend) => Box::new(backend),
+            Err(e) => {
+                Printer::new(Color::Red).failure(e)?;
+                return Ok(ErrorKind::Config.exit_code());
+            }
+        }
+    } else {
+        Box::new(ollama)
+    };
+    let mut options = config.options.clone();
+    if args.count.is_some() && options.temperature.is_none_or(|t| t == 0.0) {
+        options.temperature = Some(SAMPLE_TEMPERATURE);
+    }
+    // A shared seed would make every sample the same answer.
+    if args.count.is_some() {
+        options.seed = None;
+    }
+    let shark = Shark::new(
+        backend,
+        config.model,
+        config.functions,
+        &ToolRegistry::with_builtin_tools(&config.tools),
+        &templates,
+        config.strict_tools,
+    );
+    let mut shark = match shark {
+        Ok(shark) => shark,
+        Err(e) => {
+            Printer::new(Color::Red).failure(e)?;
+            return Ok(ErrorKind::Config.exit_code());
+        }
+    }
+    .with_options(options.generation_options())
+    .with_retry(config.retry.clone())
+    .with_timeout(config.timeout_secs.map(Duration::from_secs))
+    .with_keep_alive(config.keep_alive.map(|keep_alive| keep_alive.0))
+    .with_system_prompt(config.system_prompt)
+    .with_system_override(args.system)
+    .with_context(context)
+    .with_summary_model(config.summary_model)
+    .with_auto_pull(config.auto_pull)
+    .with_summarize(config.summarize && !args.no_summarize)
+    .with_summary_length(args.summary_length.unwrap_or(config.summary_length))
+    .with_raw(args.raw)
+    .with_mode(if args.raw {
+        Mode::Generate
+    } else {
+        config.mode
+    })
+    .with_images(images)
+    .with_timings(timings.clone())
+    .with_max_tool_iterations(config.max_tool_iterations)
+    .with_max_tool_output_tokens(config.max_tool_output_tokens)
+    .with_max_output_tokens(
+        args.max_output
+            .map(|tokens| tokens as usize)
+            .or(config.max_output_tokens),
+    )
+    .with_confirmation(match (config.require_confirmation, args.yes) {
+        (false, _) => Confirmation::Off,
+        (true, false) => Confirmation::Ask,
+        (true, true) => Confirmation::AssumeYes,
+    })
+    .with_cache(
+        (config.cache && !args.no_cache && !args.mock && args.count.is_none()).then(|| {
+            let dir = config.cache_dir.clone().unwrap_or_else(Cache::default_dir);
+            Cache::new(dir, Duration::from_secs(config.cache_ttl_secs))
+        }),
+    );
+    if args.explain_tools {
+        explain_tools(&shark, args.json);
+        return Ok(ExitCode::SUCCESS);
+    }
+    match LastAnswers::load(&last_answers_path, config.last_answers) {
+        Ok(last_answers) => shark = shark.with_last_answers(last_answers),
+        Err(e) => output::warning(&format!("failed to load the last answers: {e}")),
+    }
+    // Single questions start a new session unless continuing, the repl picks up the
+    // configured history as before.
+    let history_path = config
+        .history_path
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config_path.with_file_name(history::FILE_NAME));
+    let with_history =
+        args.continue_session || (question.is_none() && config.history_path.is_some());
+    let history = if args.count.is_some() {
+        History::default()
+    } else if with_history {
+        History::load(&history_path, config.history_max_messages)?
+    } else {
+        History::fresh(&history_path, config.history_max_messages)
+    };
+    shark = shark.with_history(history);
+
+    let markdown = args.markdown || config.render == Render::Markdown;
+    let mut printer = Printer::new(theme.answer)
+        .with_theme(theme)
+        .with_markdown(markdown)
+        .with_wrap(config.wrap, config.wrap_width)
+        .with_stats(args.stats)
+        .with_quiet(args.quiet)
+        .with_pager((args.pager || config.pager) && question.is_some() && !args.json);
+
+    if let Some(question) = question.as_deref().filter(|_| args.dry_run) {
+        for (title, prompt) in shark.dry_run(question, with_history).await? {
+            printer.info(&format!("--- {title} ---\n"))?;
+            printer.answer(&format!("{}\n", prompt.trim()))?;
+        }
+        printer.finish()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let (Some(question), Some(count)) = (&question, args.count) {
+        return tokio::select! {
+            code = ask_samples(&shark, question, count as usize, args.json, &mut printer) => code,
+            _ = interrupted() => {
+                printer.finish()?;
+                Ok(ExitCode::from(EXIT_INTERRUPTED))
+            }
+        };
+    }
+
+    match question {
+        Some(question) if args.json => {
+            let result = tokio::select! {
+                result = ask_json(&shark, &question, with_history, args.stats) => result,
+                _ = interrupted() => return Ok(ExitCode::from(EXIT_INTERRUPTED)),
+            };
+            timings.print();
+            match result {
+                Ok(answered) => {
+                    if !save_output(args.output.as_deref(), &answered.answer)?
+                        || !export_turn(args.export.as_deref(), &shark, &question, &answered)?
+                    {
+                        return Ok(ExitCode::FAILURE);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{e}");
+                    return Ok(ErrorKind::of(&*e).unwrap_or(ErrorKind::Other).exit_code());
+                }
+            }
+        }
+        Some(question) => {
+            let result = tokio::select! {
+                result = ask(&shark, &question, with_history, &mut printer) => result,
+                _ = interrupted() => {
+                    printer.finish()?;
+                    return Ok(ExitCode::from(EXIT_INTERRUPTED));
+                }
+            };
+            printer.finish()?;
+            printer.close_pager();
+            match result {
+                Ok(answered) => {
+                    answered.print_cut_off();
+                    answered.print_stats(&printer);
+                    timings.print();
+                    if !save_output(args.output.as_deref(), &answered.answer)?
+                        || !export_turn(args.export.as_deref(), &shark, &question, &answered)?
+                    {
+                        return Ok(ExitCode::FAILURE);
+                    }
+                }
+                Err(e) => return Ok(printer.error(&*e)?.exit_code()),
+            }
+        }
+        None => repl::run(&mut shark, &mut printer).await?,
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// What `--version --verbose` adds to the version: the config file and the model, server
+/// and tools it resolves to, with the flags and environment variables applied.
+fn print_build_info(config_path: Option<&Path>, args: &Args) {
+    println!(
+        "target: {}-{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS
+    );
+    let Some(config_path) = config_path else {
+        println!("config: {}", missing_config_message());
+        return;
+    };
+    println!("config: {}", config_path.display());
+    let overrides = Overrides {
+        model: args.model.clone(),
+        addr: args.addr.clone(),
+        ..Overrides::default()
+    };
+    let config = match parse_config(config_path, args.profile.as_deref()) {
+        Ok(config) => merge(config, overrides),
+        Err(e) => {
+            println!("config error: {e}");
+            return;
+        }
+    };
+    let registry = ToolRegistry::with_builtin_tools(&config.tools);
+    let enabled = config
+        .functions
+        .iter()
+        .filter(|function| function.enabled)
+        .count();
+    println!("model: {}", config.model);
+    println!("server: {}", config.addr);
+    println!(
+        "tools: {enabled} enabled, {} available",
+        registry.names().len()
+    );
+}
+
+/// Prints the tools the model is offered, or a JSON array of them with `--json`.
+fn explain_tools(shark: &Shark<'_>, json: bool) {
+    let tools = shark.tools();
+    if json {
+        let tools: Vec<_> = tools
+            .iter()
+            .map(|(tool, destructive)| {
+                json!({
+                    "name": tool.name(),
+                    "description": tool.description(),
+                    "destructive": destructive,
+                    "parameters": tool.parameters(),
+                })
+            })
+            .collect();
+        println!("{}", json!(tools));
+        return;
+    }
+    if tools.is_empty() {
+        println!("no tools are enabled, list them under `functions` in the config");
+        return;
+    }
+    let explained: Vec<String> = tools
+        .iter()
+        .map(|(tool, destructive)| tools::explain(tool.as_ref(), *destructive))
+        .collect();
+    print!("{}", explained.join("\n"));
+}
+
+/// Writes the answer to the `--output` file, if any, and tells whether that worked.
+fn save_output(path: Option<&Path>, answer: &str) -> std::io::Result<bool> {
+    let Some(path) = path else {
+        return Ok(true);
+    };
+    match write_atomic(path, answer) {
+        Ok(()) => Ok(true),
+        Err(e) => {
+            Printer::new(Color::Red).failure(format!("failed to write {}: {e}", path.display()))?;
+            Ok(false)
+        }
+    }
+}
+
+/// Appends the answered question to the `--export` transcript.
+fn export_turn(
+    path: Option<&Path>,
+    shark: &Shark<'_>,
+    question: &str,
+    answered: &Answered,
+) -> std::io::Result<bool> {
+    let Some(path) = path else {
+        return Ok(true);
+    };
+    let turn = [
+        ChatMessage::user(question.to_string()),
+        ChatMessage::assistant(answered.answer.clone()),
+    ];
+    match export::export(path, &turn, shark.model(), true) {
+        Ok(_) => Ok(true),
+        Err(e) => {
+            Printer::new(Color::Red)
+                .failure(format!("failed to export to {}: {e}", path.display()))?;
+            Ok(false)
+        }
+    }
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it over `path`,
+/// so an interrupted run never leaves a truncated file behind.
+fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()));
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
+/// Prints the `n`th most recent answer, as stored for `--last`.
+fn show_last_answer(
+    path: &Path,
+    config: &Config,
+    theme: Theme,
+    n: usize,
+    json: bool,
+) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let last_answers = LastAnswers::load(path, config.last_answers)?;
+    let Some(answer) = last_answers.get(n) else {
+        let message = match last_answers.len() {
+            0 => "no answers stored yet".to_string(),
+            len => format!("only {len} answers are stored"),
+        };
+        Printer::new(theme.answer)
+            .with_theme(theme)
+            .failure(message)?;
+        return Ok(ExitCode::FAILURE);
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(answer)?);
+        return Ok(ExitCode::SUCCESS);
+    }
+    let mut printer = Printer::new(theme.answer).with_theme(theme);
+    printer.info(&format!("{} ({})\n", answer.question, answer.answered_at))?;
+    printer.answer(&answer.answer)?;
+    printer.finish()?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Resolves on the first Ctrl-C, so the caller can stop the answer and restore the terminal.
+/// A second Ctrl-C within [`FORCE_EXIT_WINDOW`] exits right away in case that hangs.
+async fn interrupted() {
+    let _ = tokio::signal::ctrl_c().await;
+    tokio::spawn(async {
+        let second = tokio::time::timeout(FORCE_EXIT_WINDOW, tokio::signal::ctrl_c()).await;
+        if second.is_ok() {
+            std::process::exit(EXIT_INTERRUPTED.into());
+        }
+    });
+}
+
+/// Streams the answer to `question` to the printer. Errors are returned for the caller to
+/// report with [`Printer::error`], so it can decide whether to keep going.
+pub async fn ask(
+    shark: &Shark<'_>,
+    question: &str,
+    with_history: bool,
+    printer: &mut Printer,
+) -> Result<Answered, Box<dyn std::error::Error>> {
+    let result = if printer.renders_markdown() {
+        let mut answer = String::new();
+        let spinner = spinner(printer);
+        let result = stream_answer(shark, question, with_history, ToolChoice::Pick, |chunk| {
+            answer.push_str(chunk);
+            Ok(())
+        })
+        .await;
+        drop(spinner);
+        markdown::render(printer, &answer)?;
+        result
+    } else {
+        let mut spinner = spinner(printer);
+        stream_answer(shark, question, with_history, ToolChoice::Pick, |chunk| {
+            spinner.stop();
+            printer.answer(chunk)
+        })
+        .await
+    };
 
-    let mut stdout = StandardStream::stdout(ColorChoice::Always);
-    let mut color_spec = ColorSpec::new();
+    result
+}
 
-    stdout.set_color(color_spec.set_fg(Some(Color::Cyan)))?;
-    let stream = shark.generate_stream(question).await;
-    if let Err(e) = stream {
-        stdout.set_color(color_spec.set_fg(Some(Color::Red)))?;
-        let err =
-            format!("Sorry I can't answer your question right now, please try again later.😭\n{e}");
-        stdout.write(err.as_bytes())?;
-        stdout.flush()?;
-        return Ok(());
+/// The spinner shown until the answer starts, hidden while paging so it can't draw over
+/// the pager.
+fn spinner(printer: &Printer) -> Spinner {
+    if printer.pages() {
+        Spinner::hidden()
+    } else {
+        Spinner::start(printer.spinner_color())
     }
+}
+
+/// Buffers the whole answer and prints it as a single JSON object for scripts.
+async fn ask_json(
+    shark: &Shark<'_>,
+    question: &str,
+    with_history: bool,
+    show_stats: bool,
+) -> Result<Answered, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let answered =
+        stream_answer(shark, question, with_history, ToolChoice::Pick, |_| Ok(())).await?;
+
+    let output = json!({
+        "question": question,
+        "answer": answered.answer,
+        "model": shark.model(),
+        "used_tool": answered.used_tool,
+        "truncated": answered.cut_off.is_some(),
+        "elapsed_ms": started.elapsed().as_millis() as u64,
+    });
+    println!("{output}");
+    answered.print_cut_off();
+    if let Some(stats) = answered.stats.filter(|_| show_stats) {
+        stats.print();
+    }
+    Ok(answered)
+}
 
-    let mut stream = stream.unwrap();
+/// Generates `count` answers to `question`, a few at a time, and prints them numbered once
+/// they are all done, or as a single JSON object with `--json`. The tool answering the
+/// question runs only once, its output shared by all the answers.
+async fn ask_samples(
+    shark: &Shark<'_>,
+    question: &str,
+    count: usize,
+    json: bool,
+    printer: &mut Printer,
+) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    // The tool runs once, before the spinner shows, since it may ask for confirmation.
+    let call = shark.select_tool(question).await?;
+    let semaphore = Semaphore::new(MAX_CONCURRENT_SAMPLES);
+    let spinner = (!json).then(|| spinner(printer));
+    let samples = join_all((0..count).map(|_| async {
+        let _permit = semaphore.acquire().await?;
+        let tool = ToolChoice::Ran(call.clone());
+        stream_answer(shark, question, false, tool, |_| Ok(())).await
+    }))
+    .await;
+    drop(spinner);
 
-    let color = parse_color(&config.color);
-    stdout.set_color(color_spec.set_fg(Some(color)))?;
-    while let Some(Ok(responses)) = stream.next().await {
-        for resp in responses {
-            stdout.write(resp.response.as_bytes())?;
-            stdout.flush()?;
+    let exit_code = samples
+        .iter()
+        .find_map(|sample| sample.as_ref().err())
+        .map_or(ExitCode::SUCCESS, |e| {
+            ErrorKind::of(&**e).unwrap_or(ErrorKind::Other).exit_code()
+        });
+    if json {
+        let answers: Vec<_> = samples
+            .iter()
+            .map(|sample| match sample {
+                Ok(answered) => json!({
+                    "answer": answered.answer,
+                    "used_tool": answered.used_tool,
+                    "truncated": answered.cut_off.is_some(),
+                }),
+                Err(e) => json!({ "error": e.to_string() }),
+            })
+            .collect();
+        let output = json!({
+            "question": question,
+            "answers": answers,
+            "model": shark.model(),
+            "elapsed_ms": started.elapsed().as_millis() as u64,
+        });
+        println!("{output}");
+        return Ok(exit_code);
+    }
+
+    for (i, sample) in samples.iter().enumerate() {
+        printer.info(&format!("--- answer {}/{count} ---\n", i + 1))?;
+        match sample {
+            Ok(answered) if printer.renders_markdown() => {
+                markdown::render(printer, &answered.answer)?
+            }
+            Ok(answered) => printer.answer(&answered.answer)?,
+            Err(e) => {
+                printer.error(&**e)?;
+            }
+        }
+        printer.finish()?;
+        if let Ok(answered) = sample {
+            answered.print_cut_off();
         }
     }
+    Ok(exit_code)
+}
+
+/// What's known about an answer once it has been streamed.
+pub struct Answered {
+    pub answer: String,
+    pub used_tool: Option<String>,
+    pub stats: Option<Stats>,
+    /// The `max_output_tokens` limit, when the answer was stopped at it.
+    pub cut_off: Option<usize>,
+}
 
-    stdout.write(b"\n")?;
+impl Answered {
+    /// Tells on stderr that the answer was stopped early. Call it after the printer has
+    /// finished, so the answer's color is reset first.
+    pub fn print_cut_off(&self) {
+        if let Some(max) = self.cut_off {
+            output::warning(&format!(
+                "stopped the answer after {max} tokens, raise max_output_tokens or --max-output \
+                 for longer answers"
+            ));
+        }
+    }
 
-    Ok(())
+    /// Prints the stats to stderr, when the printer shows them.
+    pub fn print_stats(&self, printer: &Printer) {
+        if let Some(stats) = self.stats.filter(|_| printer.shows_stats()) {
+            stats.print();
+        }
+    }
 }
 
-fn parse_config(path: &str) -> Config {
-    let file = std::fs::read_to_string(path).unwrap();
-    let config: Config = toml::from_str(&file).unwrap();
-    config
+/// Whether the tool answering the question is still to be picked, or already ran once for
+/// all the answers of `--n`.
+enum ToolChoice {
+    Pick,
+    Ran(Option<ToolCall>),
 }
 
-fn parse_color(color: &str) -> Color {
-    let color = color.trim().to_lowercase();
-    match color.as_str() {
-        "purple" => Color::Rgb(202, 158, 230),
-        "red" => Color::Rgb(231, 130, 132),
-        "green" => Color::Rgb(166, 209, 137),
-        _ => Color::Green,
+/// Feeds the answer to `on_chunk` as it streams in and returns it along with the tool used
+/// and the stats. Complete answers are remembered for `--last`.
+/// The stream is dropped, cancelling the request, once the shark's timeout elapses or
+/// `max_output_tokens` were streamed.
+async fn stream_answer(
+    shark: &Shark<'_>,
+    question: &str,
+    with_history: bool,
+    tool: ToolChoice,
+    mut on_chunk: impl FnMut(&str) -> std::io::Result<()>,
+) -> Result<Answered, Box<dyn std::error::Error>> {
+    let mut first_token = true;
+    let on_chunk = |chunk: &str| {
+        if first_token && !chunk.is_empty() {
+            first_token = false;
+            shark.timings().mark("first token");
+        }
+        on_chunk(chunk)
+    };
+    let answer = consume_answer(shark, question, with_history, tool, on_chunk);
+    let answered = match shark.timeout() {
+        Some(limit) => tokio::time::timeout(limit, answer)
+            .await
+            .map_err(|_| Timeout(limit))??,
+        None => answer.await?,
+    };
+    shark.timings().mark("stream end");
+    shark.remember(question, &answered.answer, answered.used_tool.as_deref());
+    Ok(answered)
+}
+
+async fn consume_answer(
+    shark: &Shark<'_>,
+    question: &str,
+    with_history: bool,
+    tool: ToolChoice,
+    mut on_chunk: impl FnMut(&str) -> std::io::Result<()>,
+) -> Result<Answered, Box<dyn std::error::Error>> {
+    if shark.mode() == Mode::Chat {
+        let (stream, tool_call) = match tool {
+            ToolChoice::Pick => shark.chat_stream(question).await?,
+            ToolChoice::Ran(call) => shark.chat_stream_with_tool(question, call).await?,
+        };
+        shark.timings().mark("stream start");
+        let used_tool = tool_call.map(|call| call.name);
+        return consume_chat(stream, used_tool, shark.max_output_tokens(), on_chunk).await;
+    }
+    if with_history {
+        let stream = shark.generate_stream_with_history(question).await?;
+        shark.timings().mark("stream start");
+        return consume_chat(stream, None, shark.max_output_tokens(), on_chunk).await;
+    }
+
+    let mut answer = String::new();
+    let mut stats = None;
+    let mut tokens = 0;
+    let mut cut_off = None;
+    let (mut stream, tool_call) = match tool {
+        ToolChoice::Pick => shark.generate_stream(question).await?,
+        ToolChoice::Ran(call) => shark.generate_stream_with_tool(question, call).await?,
+    };
+    shark.timings().mark("stream start");
+    'stream: while let Some(responses) = stream.next().await {
+        // A stream failing partway is an error, so `-c` can't continue from half an answer.
+        for resp in responses? {
+            on_chunk(&resp.response)?;
+            answer.push_str(&resp.response);
+            stats = stats.or(Stats::from_generation(&resp));
+            tokens += 1;
+            if !resp.done && reached(shark.max_output_tokens(), tokens) {
+                cut_off = shark.max_output_tokens();
+                break 'stream;
+            }
+        }
+    }
+    shark.record_turn(question, &answer);
+    Ok(Answered {
+        answer,
+        used_tool: tool_call.map(|call| call.name),
+        stats,
+        cut_off,
+    })
+}
+
+async fn consume_chat(
+    mut stream: ChatMessageResponseStream,
+    used_tool: Option<String>,
+    max_tokens: Option<usize>,
+    mut on_chunk: impl FnMut(&str) -> std::io::Result<()>,
+) -> Result<Answered, Box<dyn std::error::Error>> {
+    let mut answer = String::new();
+    let mut stats = None;
+    let mut tokens = 0;
+    let mut cut_off = None;
+    while let Some(Ok(resp)) = stream.next().await {
+        if let Some(message) = resp.message {
+            on_chunk(&message.content)?;
+            answer.push_str(&message.content);
+        }
+        if let Some(data) = &resp.final_data {
+            stats = Some(Stats::from_chat(data));
+        }
+        tokens += 1;
+        if !resp.done && reached(max_tokens, tokens) {
+            cut_off = max_tokens;
+            break;
+        }
+    }
+    Ok(Answered {
+        answer,
+        used_tool,
+        stats,
+        cut_off,
+    })
+}
+
+/// Whether `tokens` streamed responses hit the limit. Backends stream a token per response.
+fn reached(max_tokens: Option<usize>, tokens: usize) -> bool {
+    max_tokens.is_some_and(|max| tokens >= max)
+}
+
+/// Builds the question from positional args and, when piped, stdin.
+/// The positional prompt acts as the instruction and stdin as its context.
+fn read_question(prompt: &[String]) -> std::io::Result<Option<String>> {
+    let prompt = prompt.join(" ");
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Ok((!prompt.is_empty()).then_some(prompt));
     }
+
+    let mut piped = String::new();
+    stdin.lock().read_to_string(&mut piped)?;
+    let piped = piped.trim();
+
+    let question = match (prompt.is_empty(), piped.is_empty()) {
+        (true, true) => return Ok(None),
+        (false, true) => prompt,
+        (true, false) => piped.to_string(),
+        (false, false) => format!("{prompt}\n\n{piped}"),
+    };
+    Ok(Some(question))
 }
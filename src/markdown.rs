@@ -0,0 +1,197 @@
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Style, ThemeSet},
+    parsing::SyntaxSet,
+    util::LinesWithEndings,
+};
+use termcolor::{Color, ColorSpec};
+
+use crate::output::Printer;
+
+const THEME: &str = "base16-ocean.dark";
+
+/// Renders a complete markdown answer: headings, emphasis, lists and
+/// fenced code blocks highlighted according to their language tag.
+pub fn render(printer: &mut Printer, text: &str) -> std::io::Result<()> {
+    let syntaxes = SyntaxSet::load_defaults_newlines();
+    let themes = ThemeSet::load_defaults();
+    let theme = &themes.themes[THEME];
+
+    let mut code: Option<HighlightLines> = None;
+    for line in LinesWithEndings::from(text) {
+        if let Some(lang) = line.trim_start().strip_prefix("```") {
+            code = match code {
+                Some(_) => None,
+                None => {
+                    let syntax = syntaxes
+                        .find_syntax_by_token(lang.trim())
+                        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+                    Some(HighlightLines::new(syntax, theme))
+                }
+            };
+            continue;
+        }
+
+        match code.as_mut() {
+            Some(highlighter) => {
+                let ranges = highlighter
+                    .highlight_line(line, &syntaxes)
+                    .unwrap_or_else(|_| vec![(Style::default(), line)]);
+                for (style, piece) in ranges {
+                    let fg = style.foreground;
+                    let mut spec = ColorSpec::new();
+                    spec.set_fg(Some(Color::Rgb(fg.r, fg.g, fg.b)));
+                    printer.styled(&spec, piece)?;
+                }
+            }
+            None => render_line(printer, line)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn render_line(printer: &mut Printer, line: &str) -> std::io::Result<()> {
+    let trimmed = line.trim_start();
+
+    if trimmed.starts_with('#') {
+        let heading = trimmed.trim_start_matches('#').trim_start();
+        let mut spec = ColorSpec::new();
+        spec.set_fg(Some(Color::Cyan)).set_bold(true);
+        return printer.styled(&spec, heading);
+    }
+
+    let indent = &line[..line.len() - trimmed.len()];
+    let content = match list_marker(trimmed) {
+        Some((marker, rest)) => {
+            printer.answer(indent)?;
+            let mut spec = ColorSpec::new();
+            spec.set_fg(Some(Color::Yellow));
+            printer.styled(&spec, marker)?;
+            rest
+        }
+        None => line,
+    };
+
+    render_inline(printer, content)
+}
+
+/// Splits `- item`, `* item` and `1. item` into the marker and the item text.
+fn list_marker(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with("- ") || line.starts_with("* ") {
+        return Some(line.split_at(2));
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && line[digits..].starts_with(". ") {
+        return Some(line.split_at(digits + 2));
+    }
+    None
+}
+
+#[derive(Debug, PartialEq)]
+enum Inline<'a> {
+    Plain(&'a str),
+    Bold(&'a str),
+    Code(&'a str),
+}
+
+/// The first `**` or `` ` `` in `text` and where it starts.
+fn next_delimiter(text: &str) -> Option<(usize, &'static str)> {
+    match (text.find("**"), text.find('`')) {
+        (Some(b), Some(c)) if b < c => Some((b, "**")),
+        (Some(b), None) => Some((b, "**")),
+        (_, Some(c)) => Some((c, "`")),
+        (None, None) => None,
+    }
+}
+
+/// Splits `text` into `**bold**`, `` `code` `` and plain spans. A delimiter that is never
+/// closed stays plain text, and the spans after it are still found.
+fn inline_spans(mut text: &str) -> Vec<Inline<'_>> {
+    let mut spans = Vec::new();
+    let mut searched = 0;
+    while let Some((start, delimiter)) = next_delimiter(&text[searched..]) {
+        let start = searched + start;
+        let after = &text[start + delimiter.len()..];
+        let Some(end) = after.find(delimiter) else {
+            searched = start + delimiter.len();
+            continue;
+        };
+
+        if start > 0 {
+            spans.push(Inline::Plain(&text[..start]));
+        }
+        let span = &after[..end];
+        spans.push(match delimiter {
+            "**" => Inline::Bold(span),
+            _ => Inline::Code(span),
+        });
+        text = &after[end + delimiter.len()..];
+        searched = 0;
+    }
+    if !text.is_empty() {
+        spans.push(Inline::Plain(text));
+    }
+    spans
+}
+
+/// Styles `**bold**` and `` `code` `` spans, printing everything else as a plain answer.
+fn render_inline(printer: &mut Printer, text: &str) -> std::io::Result<()> {
+    for span in inline_spans(text) {
+        let mut spec = ColorSpec::new();
+        match span {
+            Inline::Plain(text) => printer.answer(text)?,
+            Inline::Bold(text) => {
+                spec.set_fg(Some(printer.color())).set_bold(true);
+                printer.styled(&spec, text)?;
+            }
+            Inline::Code(text) => {
+                spec.set_fg(Some(Color::Yellow));
+                printer.styled(&spec, text)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_markers_are_split_from_the_item() {
+        assert_eq!(list_marker("- milk"), Some(("- ", "milk")));
+        assert_eq!(list_marker("* eggs"), Some(("* ", "eggs")));
+        assert_eq!(list_marker("12. flour"), Some(("12. ", "flour")));
+        assert_eq!(list_marker("-dash"), None);
+        assert_eq!(list_marker("1.5 cups"), None);
+        assert_eq!(list_marker("**bold**"), None);
+    }
+
+    #[test]
+    fn bold_and_code_spans_are_found() {
+        use Inline::*;
+        assert_eq!(
+            inline_spans("run **cargo** with `--release`\n"),
+            [
+                Plain("run "),
+                Bold("cargo"),
+                Plain(" with "),
+                Code("--release"),
+                Plain("\n")
+            ]
+        );
+        assert_eq!(inline_spans("`a**b`"), [Code("a**b")]);
+        assert_eq!(inline_spans("plain"), [Plain("plain")]);
+    }
+
+    #[test]
+    fn unclosed_delimiters_stay_plain() {
+        use Inline::*;
+        assert_eq!(
+            inline_spans("2 ** 3 is `8`"),
+            [Plain("2 ** 3 is "), Code("8")]
+        );
+        assert_eq!(inline_spans("a ` b ** c"), [Plain("a ` b ** c")]);
+    }
+}
@@ -0,0 +1,210 @@
+use std::io::{IsTerminal, Write};
+
+use ollama_rs::models::{pull::PullModelStatus, LocalModel};
+use serde_json::{json, Value};
+use termcolor::Color;
+use tokio_stream::StreamExt;
+
+use crate::{
+    backend::{post_stream, OllamaClient},
+    output::Printer,
+};
+
+type Error = Box<dyn std::error::Error + 'static>;
+
+/// Prints the models pulled on the server and warns when `configured` isn't one of them.
+pub async fn list_models(ollama: &OllamaClient, configured: &str) -> Result<(), Error> {
+    let models = ollama.list_local_models().await?;
+
+    let width = models
+        .iter()
+        .map(|m| m.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!("{:width$}  {:>9}  MODIFIED", "NAME", "SIZE");
+    for model in &models {
+        println!(
+            "{:width$}  {:>9}  {}",
+            model.name,
+            format_size(model.size),
+            format_modified(&model.modified_at)
+        );
+    }
+
+    if !is_pulled(&models, configured) {
+        let mut warning = Printer::new(Color::Yellow);
+        warning.answer(&format!(
+            "configured model '{configured}' is not pulled, run `ollama pull {configured}`"
+        ))?;
+        warning.finish()?;
+    }
+
+    Ok(())
+}
+
+/// The context window `model` runs with, in tokens: `num_ctx` from its Modelfile, otherwise
+/// the context length it was trained with. `None` when the server doesn't tell.
+pub async fn context_length(ollama: &OllamaClient, model: &str) -> Result<Option<usize>, Error> {
+    let show = ollama.show(model).await?;
+    Ok(context_length_from(&show))
+}
+
+/// Reads the context length out of an `/api/show` response.
+fn context_length_from(show: &Value) -> Option<usize> {
+    let num_ctx = show["parameters"].as_str().and_then(|parameters| {
+        parameters.lines().find_map(
+            |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                ["num_ctx", value] => value.parse().ok(),
+                _ => None,
+            },
+        )
+    });
+    num_ctx.or_else(|| {
+        show["model_info"]
+            .as_object()?
+            .iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+            .map(|length| length as usize)
+    })
+}
+
+/// Fails unless every model in `names` is pulled on the server.
+pub async fn check_pulled(ollama: &OllamaClient, names: &[&str]) -> Result<(), Error> {
+    let models = ollama.list_local_models().await?;
+    let missing: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|name| !is_pulled(&models, name))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let pulls: Vec<String> = missing
+        .iter()
+        .map(|m| format!("`ollama pull {m}`"))
+        .collect();
+    Err(format!(
+        "model {} is not pulled, run {}",
+        missing.join(", "),
+        pulls.join(" and ")
+    )
+    .into())
+}
+
+/// Pulls `name` onto the server, drawing the download progress on stderr.
+pub async fn pull(ollama: &OllamaClient, name: &str) -> Result<(), Error> {
+    let mut stream = post_stream::<Value>(ollama, "api/pull", json!({ "model": name }))
+        .await
+        .map_err(|e| format!("failed to pull {name}: {e}"))?;
+    let mut stderr = std::io::stderr();
+    let tty = stderr.is_terminal();
+    let mut last_status = String::new();
+    let mut succeeded = false;
+
+    while let Some(lines) = stream.next().await {
+        let lines = lines.map_err(|e| format!("failed to pull {name}: {e}"))?;
+        for line in lines {
+            let status = pull_status(line).map_err(|e| format!("failed to pull {name}: {e}"))?;
+            let changed = status.message != last_status;
+            if changed && !last_status.is_empty() && tty {
+                writeln!(stderr)?;
+            }
+            match (status.completed, status.total) {
+                (Some(completed), Some(total)) if tty && total > 0 => write!(
+                    stderr,
+                    "\r{} {} {:>3}% {} / {}",
+                    status.message,
+                    progress_bar(completed, total, 30),
+                    completed * 100 / total,
+                    format_size(completed),
+                    format_size(total)
+                )?,
+                _ if tty => write!(stderr, "\r{}", status.message)?,
+                _ if changed => writeln!(stderr, "{}", status.message)?,
+                _ => {}
+            }
+            stderr.flush()?;
+            succeeded = status.message == "success";
+            last_status = status.message;
+        }
+    }
+    if tty {
+        writeln!(stderr)?;
+    }
+
+    if !succeeded {
+        return Err(format!("pulling {name} stopped before it completed").into());
+    }
+    Ok(())
+}
+
+/// A line of the pull progress, which reports failures as `{"error": "..."}`.
+fn pull_status(line: Value) -> Result<PullModelStatus, String> {
+    if let Some(error) = line["error"].as_str() {
+        return Err(error.to_string());
+    }
+    serde_json::from_value(line).map_err(|e| e.to_string())
+}
+
+/// `[#####-----]` filled in proportion to `completed / total`.
+fn progress_bar(completed: u64, total: u64, width: usize) -> String {
+    let filled = (completed.min(total) as f64 / total as f64 * width as f64) as usize;
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(width - filled))
+}
+
+/// Model names without a tag refer to `:latest`.
+pub fn is_pulled(models: &[LocalModel], name: &str) -> bool {
+    let name = if name.contains(':') {
+        name.to_string()
+    } else {
+        format!("{name}:latest")
+    };
+    models.iter().any(|model| model.name == name)
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// Trims `2024-08-04T08:52:19.385406455-07:00` down to `2024-08-04 08:52`.
+fn format_modified(modified_at: &str) -> String {
+    modified_at
+        .get(..16)
+        .unwrap_or(modified_at)
+        .replace('T', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_length_prefers_num_ctx() {
+        let show = json!({
+            "parameters": "stop \"<|eot_id|>\"\nnum_ctx                        8192",
+            "model_info": {"general.architecture": "llama", "llama.context_length": 131072},
+        });
+        assert_eq!(context_length_from(&show), Some(8192));
+        let show = json!({"parameters": "", "model_info": {"llama.context_length": 131072}});
+        assert_eq!(context_length_from(&show), Some(131072));
+        assert_eq!(context_length_from(&json!({})), None);
+    }
+
+    #[test]
+    fn progress_bar_fills_in_proportion() {
+        assert_eq!(progress_bar(0, 100, 10), "[----------]");
+        assert_eq!(progress_bar(45, 100, 10), "[####------]");
+        assert_eq!(progress_bar(100, 100, 10), "[##########]");
+        assert_eq!(progress_bar(150, 100, 4), "[####]");
+    }
+}
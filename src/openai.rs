@@ -0,0 +1,383 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use ollama_rs::{
+    error::OllamaError,
+    generation::{
+        chat::{
+            request::ChatMessageRequest, ChatMessage, ChatMessageResponse,
+            ChatMessageResponseStream,
+        },
+        completion::{request::GenerationRequest, GenerationResponse, GenerationResponseStream},
+        functions::tools::Tool,
+        options::GenerationOptions,
+    },
+};
+use reqwest::{RequestBuilder, Url};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio_stream::{Stream, StreamExt};
+
+use crate::{
+    backend::{self, Backend, JsonLines},
+    image,
+};
+
+type Error = Box<dyn std::error::Error + 'static>;
+
+/// Ollama's sampling options that OpenAI's API knows, and their names there.
+const OPTIONS: [(&str, &str); 5] = [
+    ("temperature", "temperature"),
+    ("top_p", "top_p"),
+    ("seed", "seed"),
+    ("stop", "stop"),
+    ("num_predict", "max_tokens"),
+];
+
+/// A server speaking OpenAI's chat completions API, like OpenAI itself, vLLM or llama.cpp.
+/// Shark's Ollama requests are translated for it and its answers back, so the rest of shark
+/// works the same with either.
+#[derive(Debug, Clone)]
+pub struct OpenAiBackend {
+    completions: Url,
+    http: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct Completion {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    content: Option<String>,
+    tool_calls: Option<Vec<ToolCall>>,
+}
+
+#[derive(Deserialize)]
+struct ToolCall {
+    function: FunctionCall,
+}
+
+#[derive(Deserialize)]
+struct FunctionCall {
+    name: String,
+    /// The arguments as a JSON string.
+    arguments: String,
+}
+
+#[derive(Deserialize)]
+struct Chunk {
+    choices: Vec<ChunkChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: Delta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct Delta {
+    content: Option<String>,
+}
+
+impl Chunk {
+    fn text(&self) -> &str {
+        self.choices
+            .first()
+            .and_then(|choice| choice.delta.content.as_deref())
+            .unwrap_or_default()
+    }
+
+    fn done(&self) -> bool {
+        self.choices
+            .first()
+            .is_some_and(|choice| choice.finish_reason.is_some())
+    }
+}
+
+impl Message {
+    /// The content, or the first requested tool the way llama 3.1 asks for it, which is
+    /// what shark looks for in tool selection replies.
+    fn into_content(self) -> String {
+        let Some(call) = self.tool_calls.into_iter().flatten().next() else {
+            return self.content.unwrap_or_default();
+        };
+        // Compact, since shark reads the call from a single line.
+        let arguments = serde_json::from_str::<Value>(&call.function.arguments)
+            .map(|arguments| arguments.to_string())
+            .unwrap_or(call.function.arguments);
+        format!("<function={}>{arguments}</function>", call.function.name)
+    }
+}
+
+impl OpenAiBackend {
+    /// Talks to the server at `url`, e.g. `https://api.openai.com`, sending `api_key` as a
+    /// bearer token.
+    pub fn new(url: Url, api_key: Option<&str>) -> Result<Self, String> {
+        let completions = url
+            .join("v1/chat/completions")
+            .map_err(|e| format!("invalid server address {url}: {e}"))?;
+        let http = backend::http_client(api_key)?;
+        Ok(Self { completions, http })
+    }
+
+    fn post(&self, body: &Value) -> RequestBuilder {
+        self.http
+            .post(self.completions.clone())
+            .body(body.to_string())
+    }
+
+    async fn stream(
+        &self,
+        body: Value,
+    ) -> Result<impl Stream<Item = Result<Vec<Chunk>, OllamaError>> + Send, OllamaError> {
+        backend::read_stream(self.post(&body), JsonLines::server_sent_events()).await
+    }
+
+    async fn complete(
+        &self,
+        body: Value,
+        model: String,
+    ) -> Result<ChatMessageResponse, OllamaError> {
+        let completion: Completion = backend::send(self.post(&body)).await?;
+        let message = completion
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| ChatMessage::assistant(choice.message.into_content()));
+        Ok(ChatMessageResponse {
+            model,
+            created_at: String::new(),
+            message,
+            done: true,
+            final_data: None,
+        })
+    }
+}
+
+/// A chat message in OpenAI's format, with images as `data:` URLs next to the text.
+fn message(message: &ChatMessage) -> Value {
+    let images = message.images.as_deref().unwrap_or_default();
+    if images.is_empty() {
+        return json!({
+            "role": message.role,
+            "content": message.content,
+        });
+    }
+    let mut content = vec![json!({"type": "text", "text": message.content})];
+    content.extend(images.iter().map(|image| {
+        json!({
+            "type": "image_url",
+            "image_url": {"url": image::data_url(image)},
+        })
+    }));
+    json!({
+        "role": message.role,
+        "content": content,
+    })
+}
+
+/// The chat completions request for `messages`, with the `options` OpenAI knows.
+fn request_body(
+    model: &str,
+    messages: &[ChatMessage],
+    options: Option<&GenerationOptions>,
+    stream: bool,
+) -> Value {
+    let mut body = json!({
+        "model": model,
+        "messages": messages.iter().map(message).collect::<Vec<_>>(),
+        "stream": stream,
+    });
+    // Through the JSON text, since `json!` widens the f32 options, 0.3 becoming 0.30000001.
+    let options: Value = options
+        .and_then(|options| serde_json::to_string(options).ok())
+        .and_then(|options| serde_json::from_str(&options).ok())
+        .unwrap_or_default();
+    for (ollama, openai) in OPTIONS {
+        match options.get(ollama) {
+            None | Some(Value::Null) => {}
+            // Ollama takes negative lengths as unlimited.
+            Some(value) if openai == "max_tokens" && value.as_i64() < Some(1) => {}
+            Some(value) => body[openai] = value.clone(),
+        }
+    }
+    body
+}
+
+/// A tool in OpenAI's function calling format.
+fn function(tool: &Arc<dyn Tool>) -> Value {
+    json!({
+        "type": "function",
+        "function": {
+            "name": tool.name(),
+            "description": tool.description(),
+            "parameters": tool.parameters(),
+        },
+    })
+}
+
+#[async_trait]
+impl Backend for OpenAiBackend {
+    async fn generate_stream(
+        &self,
+        request: GenerationRequest,
+    ) -> Result<GenerationResponseStream, OllamaError> {
+        let mut messages = Vec::new();
+        messages.extend(request.system.map(ChatMessage::system));
+        let prompt = ChatMessage::user(request.prompt);
+        if request.images.is_empty() {
+            messages.push(prompt);
+        } else {
+            messages.push(prompt.with_images(request.images));
+        }
+        let body = request_body(
+            &request.model_name,
+            &messages,
+            request.options.as_ref(),
+            true,
+        );
+
+        let model = request.model_name;
+        let stream = self.stream(body).await?;
+        Ok(Box::pin(stream.filter_map(move |chunks| {
+            match chunks {
+                Ok(chunks) if chunks.is_empty() => None,
+                Ok(chunks) => Some(Ok(chunks
+                    .iter()
+                    .map(|chunk| GenerationResponse {
+                        model: model.clone(),
+                        created_at: String::new(),
+                        response: chunk.text().to_string(),
+                        done: chunk.done(),
+                        context: None,
+                        total_duration: None,
+                        prompt_eval_count: None,
+                        prompt_eval_duration: None,
+                        eval_count: None,
+                        eval_duration: None,
+                    })
+                    .collect())),
+                Err(e) => Some(Err(e)),
+            }
+        })))
+    }
+
+    async fn chat(&self, request: ChatMessageRequest) -> Result<ChatMessageResponse, OllamaError> {
+        self.chat_with_tools(request, &[]).await
+    }
+
+    fn native_tools(&self) -> bool {
+        true
+    }
+
+    async fn chat_with_tools(
+        &self,
+        request: ChatMessageRequest,
+        tools: &[Arc<dyn Tool>],
+    ) -> Result<ChatMessageResponse, OllamaError> {
+        let mut body = request_body(
+            &request.model_name,
+            &request.messages,
+            request.options.as_ref(),
+            false,
+        );
+        if !tools.is_empty() {
+            body["tools"] = tools.iter().map(function).collect();
+        }
+        self.complete(body, request.model_name).await
+    }
+
+    async fn chat_stream(
+        &self,
+        request: ChatMessageRequest,
+    ) -> Result<ChatMessageResponseStream, OllamaError> {
+        let body = request_body(
+            &request.model_name,
+            &request.messages,
+            request.options.as_ref(),
+            true,
+        );
+
+        let model = request.model_name;
+        let stream = self.stream(body).await?;
+        let responses = stream.map(move |chunks| {
+            chunks.map(|chunks| {
+                chunks
+                    .iter()
+                    .map(|chunk| ChatMessageResponse {
+                        model: model.clone(),
+                        created_at: String::new(),
+                        message: Some(ChatMessage::assistant(chunk.text().to_string())),
+                        done: chunk.done(),
+                        final_data: None,
+                    })
+                    .collect()
+            })
+        });
+        Ok(Box::pin(backend::flatten_chat_responses(responses)))
+    }
+
+    async fn pull(&self, model: &str) -> Result<(), Error> {
+        Err(format!("model '{model}' can't be pulled from an OpenAI-compatible server").into())
+    }
+
+    async fn context_length(&self, _model: &str) -> Option<usize> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ollama_options_are_translated() {
+        let options = GenerationOptions::default()
+            .temperature(0.2)
+            .seed(42)
+            .num_predict(-1)
+            .top_k(40);
+        let body = request_body(
+            "gpt-4o-mini",
+            &[ChatMessage::user("Hi".to_string())],
+            Some(&options),
+            true,
+        );
+        assert_eq!(
+            body,
+            json!({
+                "model": "gpt-4o-mini",
+                "messages": [{"role": "user", "content": "Hi"}],
+                "stream": true,
+                "temperature": 0.2,
+                "seed": 42,
+            })
+        );
+    }
+
+    #[test]
+    fn tool_calls_are_written_the_llama_way() {
+        let message: Message = serde_json::from_value(json!({
+            "content": null,
+            "tool_calls": [{
+                "id": "call_1",
+                "type": "function",
+                "function": {"name": "calculator", "arguments": "{\n  \"expression\": \"2+2\"\n}"}
+            }]
+        }))
+        .unwrap();
+        assert_eq!(
+            message.into_content(),
+            r#"<function=calculator>{"expression":"2+2"}</function>"#
+        );
+    }
+}
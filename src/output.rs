@@ -0,0 +1,387 @@
+use std::{
+    io::{IsTerminal, Write},
+    sync::OnceLock,
+};
+
+use termcolor::{Ansi, Color, ColorChoice, ColorSpec, NoColor, StandardStream, WriteColor};
+use terminal_size::{terminal_size, Width};
+
+use crate::{error::ErrorKind, pager::Pager, spinner, wrap::Wrapper};
+
+static COLOR_WHEN: OnceLock<ColorWhen> = OnceLock::new();
+
+/// When output is colored, from `--color`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorWhen {
+    /// Only on terminals, and only without `NO_COLOR`.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Sets when output is colored for the rest of the process, [`ColorWhen::Auto`] otherwise.
+pub fn set_color_when(when: ColorWhen) {
+    let _ = COLOR_WHEN.set(when);
+}
+
+pub fn stdout_choice() -> ColorChoice {
+    let when = COLOR_WHEN.get().copied().unwrap_or_default();
+    color_choice(when, std::io::stdout().is_terminal(), no_color())
+}
+
+pub fn stderr_choice() -> ColorChoice {
+    let when = COLOR_WHEN.get().copied().unwrap_or_default();
+    color_choice(when, std::io::stderr().is_terminal(), no_color())
+}
+
+/// A non-empty `NO_COLOR` turns colors off, see <https://no-color.org>.
+fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// `--color always` and `never` win over `NO_COLOR` and the kind of stream.
+fn color_choice(when: ColorWhen, is_terminal: bool, no_color: bool) -> ColorChoice {
+    match when {
+        ColorWhen::Always => ColorChoice::Always,
+        ColorWhen::Never => ColorChoice::Never,
+        ColorWhen::Auto if no_color || !is_terminal => ColorChoice::Never,
+        ColorWhen::Auto => ColorChoice::Auto,
+    }
+}
+
+/// Colors of the answer, of errors and of the spinner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub answer: Color,
+    pub error: Color,
+    /// The terminal's default color when `None`.
+    pub spinner: Option<Color>,
+}
+
+/// Colored terminal writer shared by single-shot and REPL runs.
+pub struct Printer {
+    stdout: Box<dyn WriteColor>,
+    paging: bool,
+    color_spec: ColorSpec,
+    color: Color,
+    error_color: Color,
+    spinner_color: Option<Color>,
+    markdown: bool,
+    stats: bool,
+    quiet: bool,
+    wrapper: Option<Wrapper>,
+}
+
+impl Printer {
+    pub fn new(color: Color) -> Self {
+        Self {
+            stdout: Box::new(StandardStream::stdout(stdout_choice())),
+            paging: false,
+            color_spec: ColorSpec::new(),
+            color,
+            error_color: Color::Red,
+            spinner_color: None,
+            markdown: false,
+            stats: false,
+            quiet: false,
+            wrapper: None,
+        }
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.color = theme.answer;
+        self.error_color = theme.error;
+        self.spinner_color = theme.spinner;
+        self
+    }
+
+    pub fn spinner_color(&self) -> Option<Color> {
+        self.spinner_color
+    }
+
+    /// Buffers answers and renders them as markdown, only when stdout is a terminal
+    /// so piped output stays plain.
+    pub fn with_markdown(mut self, markdown: bool) -> Self {
+        self.markdown = markdown && std::io::stdout().is_terminal();
+        self
+    }
+
+    pub fn renders_markdown(&self) -> bool {
+        self.markdown && !self.quiet
+    }
+
+    /// Word-wraps streamed answers at `width` columns, or the terminal's width when `None`.
+    /// Only applies when stdout is a terminal and answers aren't rendered as markdown.
+    pub fn with_wrap(mut self, wrap: bool, width: Option<usize>) -> Self {
+        let width = width.or_else(|| terminal_size().map(|(Width(w), _)| w as usize));
+        self.wrapper = width
+            .filter(|_| wrap && !self.markdown && std::io::stdout().is_terminal())
+            .map(Wrapper::new);
+        self
+    }
+
+    /// Streams the output into `$PAGER` as it arrives, only when stdout is a terminal and
+    /// a pager can be started.
+    pub fn with_pager(mut self, pager: bool) -> Self {
+        if !pager || self.quiet || !std::io::stdout().is_terminal() {
+            return self;
+        }
+        if let Some(pager) = Pager::start() {
+            self.stdout = match stdout_choice() {
+                ColorChoice::Never => Box::new(NoColor::new(pager)),
+                _ => Box::new(Ansi::new(pager)),
+            };
+            self.paging = true;
+        }
+        self
+    }
+
+    pub fn pages(&self) -> bool {
+        self.paging
+    }
+
+    /// Waits for the user to quit the pager, then prints to stdout again.
+    pub fn close_pager(&mut self) {
+        if self.paging {
+            self.stdout = Box::new(StandardStream::stdout(stdout_choice()));
+            self.paging = false;
+        }
+    }
+
+    /// Swallows answers, for when they are only written to a file.
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Prints token counts and throughput to stderr after each answer.
+    pub fn with_stats(mut self, stats: bool) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    pub fn shows_stats(&self) -> bool {
+        self.stats
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    pub fn answer(&mut self, text: &str) -> std::io::Result<()> {
+        if self.quiet {
+            return Ok(());
+        }
+        match &mut self.wrapper {
+            Some(wrapper) => {
+                let text = wrapper.push(text);
+                self.write_colored(Some(self.color), &text)
+            }
+            None => self.write_colored(Some(self.color), text),
+        }
+    }
+
+    pub fn styled(&mut self, spec: &ColorSpec, text: &str) -> std::io::Result<()> {
+        self.stdout.set_color(spec)?;
+        self.stdout.write_all(text.as_bytes())?;
+        self.stdout.flush()
+    }
+
+    pub fn info(&mut self, text: &str) -> std::io::Result<()> {
+        self.write_colored(Some(Color::Cyan), text)
+    }
+
+    /// Prints why the question couldn't be answered, with a headline for its category.
+    pub fn error(&mut self, e: &(dyn std::error::Error + 'static)) -> std::io::Result<ErrorKind> {
+        let kind = ErrorKind::of(e).unwrap_or(ErrorKind::Other);
+        let err = format!("{}\n{e}\n", kind.headline());
+        self.write_colored(Some(self.error_color), &err)?;
+        Ok(kind)
+    }
+
+    /// Prints a problem the user can fix themselves, like a broken config file.
+    pub fn failure(&mut self, message: impl std::fmt::Display) -> std::io::Result<()> {
+        self.write_colored(Some(self.error_color), &format!("{message}\n"))?;
+        self.stdout.reset()
+    }
+
+    /// Ends the current answer and restores the terminal's default color.
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        if let Some(wrapper) = &mut self.wrapper {
+            let rest = wrapper.finish();
+            self.write_colored(Some(self.color), &rest)?;
+        }
+        self.stdout.reset()?;
+        if !self.quiet {
+            self.stdout.write_all(b"\n")?;
+        }
+        self.stdout.flush()
+    }
+
+    fn write_colored(&mut self, color: Option<Color>, text: &str) -> std::io::Result<()> {
+        self.stdout.set_color(self.color_spec.set_fg(color))?;
+        self.stdout.write_all(text.as_bytes())?;
+        self.stdout.flush()
+    }
+}
+
+/// Restores the terminal's default color when dropped, so no exit path leaves it tinted.
+pub struct ResetGuard<W: WriteColor> {
+    out: W,
+}
+
+impl ResetGuard<StandardStream> {
+    /// Guards stdout, unless it isn't colored and there's nothing to restore.
+    pub fn stdout() -> Option<Self> {
+        let choice = stdout_choice();
+        (choice != ColorChoice::Never).then(|| Self::new(StandardStream::stdout(choice)))
+    }
+}
+
+impl<W: WriteColor> ResetGuard<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: WriteColor> Drop for ResetGuard<W> {
+    fn drop(&mut self) {
+        let _ = self.out.reset();
+        let _ = self.out.flush();
+    }
+}
+
+/// Tells the user a request is being retried, dimmed on stderr so it never mixes with answers.
+pub fn retrying(attempt: u32, max_retries: u32) {
+    let mut stderr = StandardStream::stderr(stderr_choice());
+    let _ = stderr.set_color(ColorSpec::new().set_dimmed(true));
+    let _ = writeln!(stderr, "retrying ({attempt}/{max_retries})…");
+    let _ = stderr.reset();
+}
+
+/// Reports a problem that doesn't stop shark, in yellow on stderr.
+pub fn warning(message: &str) {
+    let mut stderr = StandardStream::stderr(stderr_choice());
+    let _ = stderr.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)));
+    let _ = writeln!(stderr, "warning: {message}");
+    let _ = stderr.reset();
+}
+
+/// Whether the user can be asked a question, which needs both stdin and stderr on a terminal.
+pub fn can_prompt() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Asks a yes/no `question` on stderr, only a `y` or `yes` answer counts as yes.
+pub fn confirm(question: &str) -> std::io::Result<bool> {
+    let _paused = spinner::pause();
+    let mut stderr = StandardStream::stderr(stderr_choice());
+    stderr.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+    write!(stderr, "{question} [y/N] ")?;
+    stderr.reset()?;
+    stderr.flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Parses one of the 16 ANSI color names, `#rrggbb` or `rgb(r, g, b)`.
+/// `purple`, `red` and `green` are shark's softer shades rather than the ANSI ones.
+pub fn parse_color(color: &str) -> Result<Color, String> {
+    let color = color.trim().to_lowercase();
+    let parsed = match color.replace(['-', ' '], "_").as_str() {
+        "purple" => Color::Rgb(202, 158, 230),
+        "red" => Color::Rgb(231, 130, 132),
+        "green" => Color::Rgb(166, 209, 137),
+        "black" => Color::Black,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        "bright_black" | "gray" | "grey" => Color::Ansi256(8),
+        "bright_red" => Color::Ansi256(9),
+        "bright_green" => Color::Ansi256(10),
+        "bright_yellow" => Color::Ansi256(11),
+        "bright_blue" => Color::Ansi256(12),
+        "bright_magenta" => Color::Ansi256(13),
+        "bright_cyan" => Color::Ansi256(14),
+        "bright_white" => Color::Ansi256(15),
+        _ => {
+            return parse_rgb(&color).ok_or_else(|| {
+                format!("invalid color '{color}', expected a color name, #rrggbb or rgb(r, g, b)")
+            })
+        }
+    };
+    Ok(parsed)
+}
+
+fn parse_rgb(color: &str) -> Option<Color> {
+    if let Some(hex) = color.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+
+    let channels = color.strip_prefix("rgb(")?.strip_suffix(')')?;
+    let channels: Vec<u8> = channels
+        .split(',')
+        .map(|c| c.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match channels[..] {
+        [r, g, b] => Some(Color::Rgb(r, g, b)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use termcolor::Ansi;
+
+    use super::*;
+
+    #[test]
+    fn colors_parse_from_names_hex_and_rgb() {
+        assert_eq!(parse_color("Cyan"), Ok(Color::Cyan));
+        assert_eq!(parse_color("bright-red"), Ok(Color::Ansi256(9)));
+        assert_eq!(parse_color("purple"), Ok(Color::Rgb(202, 158, 230)));
+        assert_eq!(parse_color("#FF8000"), Ok(Color::Rgb(255, 128, 0)));
+        assert_eq!(parse_color("rgb(1, 2, 3)"), Ok(Color::Rgb(1, 2, 3)));
+
+        for invalid in ["pink", "#ff80", "#gg0000", "rgb(1, 2)", "rgb(1, 2, 300)"] {
+            assert!(parse_color(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn color_flag_wins_over_no_color_and_pipes() {
+        use ColorWhen::*;
+
+        assert_eq!(color_choice(Auto, true, false), ColorChoice::Auto);
+        assert_eq!(color_choice(Auto, false, false), ColorChoice::Never);
+        assert_eq!(color_choice(Auto, true, true), ColorChoice::Never);
+        assert_eq!(color_choice(Always, false, true), ColorChoice::Always);
+        assert_eq!(color_choice(Never, true, false), ColorChoice::Never);
+    }
+
+    #[test]
+    fn reset_guard_restores_the_default_color() {
+        let mut written = Vec::new();
+        {
+            let mut out = Ansi::new(&mut written);
+            out.set_color(ColorSpec::new().set_fg(Some(Color::Cyan)))
+                .unwrap();
+            out.write_all(b"answer").unwrap();
+            let _reset = ResetGuard::new(out);
+        }
+        assert!(written.ends_with(b"answer\x1b[0m"));
+    }
+}
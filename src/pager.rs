@@ -0,0 +1,111 @@
+use std::{
+    io::{self, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+};
+
+/// Pager used when `$PAGER` isn't set, passing color codes through.
+const DEFAULT_PAGER: &str = "less -R";
+
+/// The pager command from `$PAGER`, or [`DEFAULT_PAGER`]. An empty `$PAGER` disables paging.
+fn command_line(pager: Option<&str>) -> Option<Vec<&str>> {
+    let words: Vec<&str> = pager.unwrap_or(DEFAULT_PAGER).split_whitespace().collect();
+    (!words.is_empty()).then_some(words)
+}
+
+/// A pager process that answers are streamed into as they arrive.
+/// Dropping it closes the pipe and waits for the user to quit the pager.
+pub struct Pager {
+    child: Child,
+    input: Option<ChildStdin>,
+}
+
+impl Pager {
+    /// Starts the pager, `None` when there is none or it can't be started.
+    pub fn start() -> Option<Self> {
+        let pager = std::env::var("PAGER").ok();
+        let [program, args @ ..] = &command_line(pager.as_deref())?[..] else {
+            return None;
+        };
+        let mut command = Command::new(program);
+        command.args(args).stdin(Stdio::piped());
+        // Like git: let less quit on answers that fit the screen and keep colors.
+        if std::env::var_os("LESS").is_none() {
+            command.env("LESS", "FRX");
+        }
+        match command.spawn() {
+            Ok(mut child) => Some(Self {
+                input: child.stdin.take(),
+                child,
+            }),
+            Err(e) => {
+                tracing::debug!("failed to start the pager {program}: {e}");
+                None
+            }
+        }
+    }
+}
+
+impl Write for Pager {
+    /// Once the user quits the pager, the rest of the answer is dropped instead of failing.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(input) = &mut self.input else {
+            return Ok(buf.len());
+        };
+        match input.write(buf) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                self.input = None;
+                Ok(buf.len())
+            }
+            result => result,
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.input.as_mut().map(Write::flush) {
+            Some(Err(e)) if e.kind() == io::ErrorKind::BrokenPipe => {
+                self.input = None;
+                Ok(())
+            }
+            Some(result) => result,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        self.input = None;
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pager_comes_from_the_environment() {
+        assert_eq!(command_line(None), Some(vec!["less", "-R"]));
+        assert_eq!(command_line(Some("most")), Some(vec!["most"]));
+        assert_eq!(
+            command_line(Some("bat --paging always")),
+            Some(vec!["bat", "--paging", "always"])
+        );
+        assert_eq!(command_line(Some("  ")), None);
+    }
+
+    #[test]
+    fn writes_after_the_pager_quits_are_dropped() {
+        let Ok(mut child) = Command::new("true").stdin(Stdio::piped()).spawn() else {
+            return;
+        };
+        let input = child.stdin.take();
+        child.wait().unwrap();
+        let mut pager = Pager { child, input };
+        for _ in 0..100 {
+            pager.write_all(&[b'x'; 4096]).unwrap();
+        }
+        pager.flush().unwrap();
+        assert!(pager.input.is_none());
+    }
+}
@@ -0,0 +1,80 @@
+use std::path::Path;
+
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::{export, output::Printer, shark::Shark};
+
+type Error = Box<dyn std::error::Error + 'static>;
+
+const PROMPT: &str = "🦈> ";
+
+const HELP: &str = "/reset          forget the conversation so far
+/model <name>   switch to another model
+/export <path>  save the conversation as markdown
+/quit           leave the repl";
+
+/// Reads questions line by line, reusing the same `Shark` and its history.
+/// Ctrl-C cancels the answer in flight, Ctrl-D or `/quit` exits.
+pub async fn run(shark: &mut Shark<'_>, printer: &mut Printer) -> Result<(), Error> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+
+    loop {
+        printer.info(PROMPT)?;
+        let line = tokio::select! {
+            line = lines.next_line() => line?,
+            _ = tokio::signal::ctrl_c() => None,
+        };
+        let Some(line) = line else {
+            printer.finish()?;
+            return Ok(());
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(command) = line.strip_prefix('/') {
+            let mut parts = command.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("quit" | "exit"), _) => return Ok(()),
+                (Some("reset"), _) => {
+                    shark.reset_history();
+                    printer.info("history cleared\n")?;
+                }
+                (Some("model"), Some(model)) => {
+                    shark.set_model(model);
+                    printer.info(&format!("switched to {model}\n"))?;
+                }
+                (Some("export"), Some(path)) => {
+                    match export::export(Path::new(path), &shark.history(), shark.model(), false) {
+                        Ok(true) => printer.info(&format!("exported to {path}\n"))?,
+                        Ok(false) => printer.info("nothing to export yet\n")?,
+                        Err(e) => printer.failure(format!("failed to export to {path}: {e}"))?,
+                    }
+                }
+                _ => printer.info(&format!("{HELP}\n"))?,
+            }
+            continue;
+        }
+
+        let result = tokio::select! {
+            result = crate::ask(shark, line, true, printer) => result,
+            _ = tokio::signal::ctrl_c() => {
+                printer.info("\n(cancelled)")?;
+                printer.finish()?;
+                continue;
+            }
+        };
+        printer.finish()?;
+        match result {
+            Ok(answered) => {
+                answered.print_cut_off();
+                answered.print_stats(printer);
+            }
+            Err(e) => {
+                printer.error(&*e)?;
+            }
+        }
+    }
+}
@@ -1,137 +1,1501 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    future::Future,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use minijinja::{context, Environment};
 use ollama_rs::{
+    error::OllamaError,
     generation::{
-        chat::{ChatMessage, ChatMessageResponse},
-        completion::{request::GenerationRequest, GenerationResponseStream},
-        functions::{tools::Tool, DDGSearcher, FunctionCallRequest, LlamaFunctionCall},
+        chat::{
+            request::ChatMessageRequest, ChatMessage, ChatMessageResponse,
+            ChatMessageResponseStream,
+        },
+        completion::{request::GenerationRequest, GenerationResponse, GenerationResponseStream},
+        functions::{pipelines::RequestParserBase, tools::Tool, LlamaFunctionCall},
+        images::Image,
+        options::GenerationOptions,
+        parameters::KeepAlive,
     },
-    Ollama,
 };
+use regex::Regex;
+use serde_json::{json, Value};
+use tokio_stream::StreamExt;
+use tracing::{debug, trace, warn};
+
+use crate::{
+    backend::Backend,
+    cache::Cache,
+    config::{Mode, RetryConfig, SummaryLength},
+    error::is_connection_error,
+    history::History,
+    last::{Answer, LastAnswers},
+    output,
+    timing::Timings,
+    tools::{
+        args::{self, ArgError},
+        DescribedTool, FunctionConfig, ToolRegistry,
+    },
+};
+
+const DEFAULT_SYSTEM_PROMPT: &str = "You are a helpful assistant called shark🦈.";
 
-use crate::tools::rust_toolchain_switcher::RustToolchainSwitcher;
+/// Rough size of a token, to turn token budgets into characters without a tokenizer.
+const CHARS_PER_TOKEN: usize = 4;
 
-const SHARK_GENERATATION_PROMPT_TEMPLATE: &'static str = r#"
-You are a helpful assistant called shark🦈, answer the question given by user: {{question}}
+/// Ollama's default context window, assumed when the server doesn't report the model's.
+const DEFAULT_CONTEXT_LENGTH: usize = 2048;
+
+/// Longest wait between retries, however many attempts were configured.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+const SHARK_GENERATATION_PROMPT_TEMPLATE: &str = r#"
+{{system_prompt}}
+{% if context %}Use these files provided by the user as context:
+{{context}}
+{% endif %}Answer the question given by user: {{question}}
 "#;
 
-const SHARK_SUMMARIZING_PROMPT_TEMPLATE: &'static str = r#"
-You are a helpful assistant called shark🦈, given user's question: {{question}} and the answer of the question: {{answer}}, try to give a short summary.
+const SHARK_SUMMARIZING_PROMPT_TEMPLATE: &str = r#"
+{{system_prompt}}
+Given user's question: {{question}} and the answer of the question: {{answer}}, try to give {{length}}.
 Just response your summary content.
 "#;
 
 type Error = Box<dyn std::error::Error + 'static>;
 
+/// The model didn't finish within the configured `timeout_secs`.
+#[derive(Debug)]
+pub struct Timeout(pub Duration);
+
+impl std::fmt::Display for Timeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no answer within {}s, giving up", self.0.as_secs())
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+/// The server doesn't have the model and `auto_pull` is off.
+#[derive(Debug)]
+pub struct ModelNotPulled(pub String);
+
+impl std::fmt::Display for ModelNotPulled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Model '{0}' is not available. Run: ollama pull {0}",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ModelNotPulled {}
+
+/// A destructive tool didn't get the user's confirmation, told to the model as the tool's error.
+#[derive(Debug)]
+pub struct Declined(pub String);
+
+impl std::fmt::Display for Declined {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Declined {}
+
+/// Whether destructive tools ask before they run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Confirmation {
+    /// Run them like any other tool.
+    #[default]
+    Off,
+    /// Ask on the terminal, refusing to run them when there is none.
+    Ask,
+    /// Run them without asking, as with `--yes`.
+    AssumeYes,
+}
+
+/// Sources of the `generation` and `summary` prompt templates.
+pub struct Templates {
+    pub generation: String,
+    pub summary: String,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Self {
+            generation: SHARK_GENERATATION_PROMPT_TEMPLATE.to_string(),
+            summary: SHARK_SUMMARIZING_PROMPT_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl Templates {
+    /// Reads templates from the given files, keeping the embedded ones for missing paths.
+    /// Every template is test-rendered so a broken one fails at startup instead of mid-request.
+    pub fn load(generation: Option<&Path>, summary: Option<&Path>) -> Result<Self, Error> {
+        let mut templates = Self::default();
+        if let Some(path) = generation {
+            templates.generation = Self::read(path)?;
+        }
+        if let Some(path) = summary {
+            templates.summary = Self::read(path)?;
+        }
+
+        let dummy = context! {
+            system_prompt => DEFAULT_SYSTEM_PROMPT,
+            context => "context",
+            question => "question",
+            answer => "answer",
+            length => SummaryLength::default().instruction(),
+        };
+        for (name, source, path) in [
+            ("generation", &templates.generation, generation),
+            ("summary", &templates.summary, summary),
+        ] {
+            let origin = path.map_or("embedded".to_string(), |p| p.display().to_string());
+            Environment::new()
+                .render_str(source, &dummy)
+                .map_err(|e| format!("invalid {name} template ({origin}): {e}"))?;
+        }
+
+        Ok(templates)
+    }
+
+    fn read(path: &Path) -> Result<String, Error> {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read template {}: {e}", path.display()).into())
+    }
+}
+
+/// A tool the model chose to run, along with what it returned.
+#[derive(Clone)]
+pub struct ToolCall {
+    pub name: String,
+    pub arguments: Value,
+    pub output: String,
+}
+
 pub struct Shark<'a> {
-    core: Ollama,
+    core: Box<dyn Backend>,
     model: String,
     functions: HashMap<String, Arc<dyn Tool>>,
+    destructive: HashSet<String>,
+    confirmation: Confirmation,
+    max_tool_iterations: u32,
+    max_tool_output_tokens: Option<usize>,
+    max_output_tokens: Option<usize>,
     template_env: Environment<'a>,
+    history: Arc<Mutex<History>>,
+    options: GenerationOptions,
+    retry: RetryConfig,
+    timeout: Option<Duration>,
+    keep_alive: Option<KeepAlive>,
+    system_prompt: Option<String>,
+    context: Option<String>,
+    summary_model: Option<String>,
+    auto_pull: bool,
+    summarize: bool,
+    summary_length: SummaryLength,
+    raw: bool,
+    mode: Mode,
+    images: Vec<Image>,
+    cache: Option<Cache>,
+    last_answers: Option<Mutex<LastAnswers>>,
+    timings: Timings,
 }
 
 impl<'a> Shark<'a> {
-    pub fn new(core: Ollama, model: impl ToString, functions: Vec<String>) -> Self {
+    pub fn new(
+        core: Box<dyn Backend>,
+        model: impl ToString,
+        functions: Vec<FunctionConfig>,
+        registry: &ToolRegistry,
+        templates: &'a Templates,
+        strict_tools: bool,
+    ) -> Result<Self, Error> {
+        let destructive = Self::destructive_functions(&functions, registry);
+        let (functions, unknown) = Self::parse_functions(functions, registry);
+        if !unknown.is_empty() {
+            let message = format!(
+                "unknown tools: {}, available tools are: {}",
+                unknown.join(", "),
+                registry.names().join(", ")
+            );
+            if strict_tools {
+                return Err(message.into());
+            }
+            output::warning(&message);
+        }
+
         let mut template_env = Environment::new();
 
         template_env
-            .add_template("generation", SHARK_GENERATATION_PROMPT_TEMPLATE)
-            .unwrap();
+            .add_template("generation", &templates.generation)
+            .map_err(|e| format!("invalid generation template: {e}"))?;
 
         template_env
-            .add_template("summary", SHARK_SUMMARIZING_PROMPT_TEMPLATE)
-            .unwrap();
+            .add_template("summary", &templates.summary)
+            .map_err(|e| format!("invalid summary template: {e}"))?;
 
-        Self {
+        Ok(Self {
             core,
             model: model.to_string(),
-            functions: Self::parse_functions(functions),
+            functions,
+            destructive,
+            confirmation: Confirmation::default(),
+            max_tool_iterations: 1,
+            max_tool_output_tokens: None,
+            max_output_tokens: None,
             template_env,
+            history: Arc::new(Mutex::new(History::default())),
+            options: GenerationOptions::default(),
+            retry: RetryConfig::default(),
+            timeout: None,
+            keep_alive: None,
+            system_prompt: None,
+            context: None,
+            summary_model: None,
+            auto_pull: false,
+            summarize: true,
+            summary_length: SummaryLength::default(),
+            raw: false,
+            mode: Mode::default(),
+            images: Vec::new(),
+            cache: None,
+            last_answers: None,
+            timings: Timings::default(),
+        })
+    }
+
+    /// Whether tool output is summarized by the model or returned as it is.
+    pub fn with_summarize(mut self, summarize: bool) -> Self {
+        self.summarize = summarize;
+        self
+    }
+
+    pub fn with_summary_length(mut self, summary_length: SummaryLength) -> Self {
+        self.summary_length = summary_length;
+        self
+    }
+
+    /// Send questions as the whole prompt, without the generation template or tools.
+    pub fn with_raw(mut self, raw: bool) -> Self {
+        self.raw = raw;
+        self
+    }
+
+    /// Answer through the chat endpoint instead of the generation templates.
+    /// The cache only applies to [`Mode::Generate`].
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Whether destructive tools need the user's confirmation before they run.
+    pub fn with_confirmation(mut self, confirmation: Confirmation) -> Self {
+        self.confirmation = confirmation;
+        self
+    }
+
+    /// How many times the model may call tools for one question, retrying after errors.
+    pub fn with_max_tool_iterations(mut self, max_tool_iterations: u32) -> Self {
+        self.max_tool_iterations = max_tool_iterations;
+        self
+    }
+
+    /// How much tool output is passed to the summary, by default half the summary model's
+    /// context window. Longer output keeps its head and tail.
+    pub fn with_max_tool_output_tokens(mut self, max_tool_output_tokens: Option<usize>) -> Self {
+        self.max_tool_output_tokens = max_tool_output_tokens;
+        self
+    }
+
+    /// Tokens streamed at most before answers are cut off on the client side, which works
+    /// even when the server ignores `num_predict`.
+    pub fn with_max_output_tokens(mut self, max_output_tokens: Option<usize>) -> Self {
+        self.max_output_tokens = max_output_tokens;
+        self
+    }
+
+    pub fn max_output_tokens(&self) -> Option<usize> {
+        self.max_output_tokens
+    }
+
+    /// Images attached to every question, for vision models.
+    /// Answers about images are never cached and the images aren't kept in the history.
+    pub fn with_images(mut self, images: Vec<Image>) -> Self {
+        self.images = images;
+        self
+    }
+
+    /// Pulls a model the server doesn't have instead of failing.
+    pub fn with_auto_pull(mut self, auto_pull: bool) -> Self {
+        self.auto_pull = auto_pull;
+        self
+    }
+
+    /// Keeps complete answers so `--last` can show them again.
+    pub fn with_last_answers(mut self, last_answers: LastAnswers) -> Self {
+        self.last_answers = Some(Mutex::new(last_answers));
+        self
+    }
+
+    /// Stores a complete answer for `--last`.
+    pub fn remember(&self, question: &str, answer: &str, used_tool: Option<&str>) {
+        let Some(last_answers) = &self.last_answers else {
+            return;
+        };
+        let answer = Answer {
+            question: question.to_string(),
+            answer: answer.to_string(),
+            model: self.model.to_owned(),
+            used_tool: used_tool.map(str::to_string),
+            answered_at: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+        };
+        if let Err(e) = last_answers.lock().unwrap().push(answer) {
+            output::warning(&format!("failed to save the answer for --last: {e}"));
+        }
+    }
+
+    /// Model summarizing tool output, the main model when `None`.
+    pub fn with_summary_model(mut self, summary_model: Option<String>) -> Self {
+        self.summary_model = summary_model;
+        self
+    }
+
+    pub fn summary_model(&self) -> &str {
+        self.summary_model.as_deref().unwrap_or(&self.model)
+    }
+
+    /// Stores final answers on disk and replays them for identical prompts.
+    pub fn with_cache(mut self, cache: Option<Cache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Persona used in the prompt templates and as the system message in chats.
+    /// Empty prompts keep the default shark persona.
+    pub fn with_system_prompt(mut self, system_prompt: Option<String>) -> Self {
+        self.system_prompt = system_prompt.filter(|prompt| !prompt.trim().is_empty());
+        self
+    }
+
+    /// One-off system prompt from `--system`, replacing the configured one. An empty prompt
+    /// leaves out the persona altogether, the default one included.
+    pub fn with_system_override(mut self, system_prompt: Option<String>) -> Self {
+        if let Some(system_prompt) = system_prompt {
+            self.system_prompt = Some(system_prompt.trim().to_string());
         }
+        self
+    }
+
+    /// Reference text from `--context` files, rendered as `context` in the generation template
+    /// and sent as a system message in chats.
+    pub fn with_context(mut self, context: Option<String>) -> Self {
+        self.context = context;
+        self
+    }
+
+    pub fn system_prompt(&self) -> &str {
+        self.system_prompt
+            .as_deref()
+            .unwrap_or(DEFAULT_SYSTEM_PROMPT)
+    }
+
+    /// Upper bound for a single answer, so a stalled generation can't hang forever.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// How long the server keeps the model loaded after a generation.
+    /// ollama-rs can't send it with chat requests yet, so [`Mode::Chat`] and history ignore it.
+    pub fn with_keep_alive(mut self, keep_alive: Option<KeepAlive>) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// How often requests are retried when the Ollama server can't be reached.
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
     }
 
+    /// Sampling options applied to every request sent to the model.
+    pub fn with_options(mut self, options: GenerationOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Records how long tool calls take in `timings`, for `--profile-perf`.
+    pub fn with_timings(mut self, timings: Timings) -> Self {
+        self.timings = timings;
+        self
+    }
+
+    pub fn timings(&self) -> &Timings {
+        &self.timings
+    }
+
+    pub fn with_history(mut self, history: History) -> Self {
+        self.history = Arc::new(Mutex::new(history));
+        self
+    }
+
+    /// Streams the answer to `question`, along with the tool whose output it summarizes, if any.
     pub async fn generate_stream(
         &self,
         question: impl ToString,
-    ) -> Result<GenerationResponseStream, Error> {
+    ) -> Result<(GenerationResponseStream, Option<ToolCall>), Error> {
+        let question = question.to_string();
+        let call = self.select_tool(&question).await?;
+        self.generate_stream_with_tool(question, call).await
+    }
+
+    /// Asks the model which tool answers `question` and runs it, the first step of
+    /// [`Shark::generate_stream`] and [`Shark::chat_stream`]. Answering the same question
+    /// several times, the call is made once and passed to each answer.
+    pub async fn select_tool(&self, question: &str) -> Result<Option<ToolCall>, Error> {
+        if self.raw && self.mode == Mode::Generate {
+            return Ok(None);
+        }
+        let started = Instant::now();
+        let call = self.call_function(question).await?;
+        self.timings.record("tool call", started);
+        Ok(call)
+    }
+
+    /// Streams the answer to `question` from the output of `call`, the tool that already ran.
+    #[tracing::instrument(skip_all, fields(model = %self.model))]
+    pub async fn generate_stream_with_tool(
+        &self,
+        question: impl ToString,
+        call: Option<ToolCall>,
+    ) -> Result<(GenerationResponseStream, Option<ToolCall>), Error> {
+        let question = question.to_string();
+        if self.raw {
+            debug!("sending the question as the prompt");
+            let stream = self.generate(&self.model, question, &self.images).await?;
+            return Ok((stream, None));
+        }
+        match call {
+            Some(call) if !self.summarize => {
+                debug!(tool = %call.name, "returning the raw tool output");
+                let stream = single_response(&self.model, format_tool_output(&call.output));
+                Ok((stream, Some(call)))
+            }
+            Some(call) => {
+                debug!(tool = %call.name, "summarizing the tool output");
+                let budget = self.tool_output_budget().await;
+                let output = truncate_middle(&call.output, budget);
+                let stream = self.summarize_stream(question, output).await?;
+                Ok((stream, Some(call)))
+            }
+            None => {
+                debug!("no tool applies, falling back to plain generation");
+                let prompt = self.generation_prompt(&question)?;
+                let stream = self.generate(&self.model, prompt, &self.images).await?;
+                Ok((stream, None))
+            }
+        }
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// The enabled tools sorted by name, with whether each counts as destructive.
+    pub fn tools(&self) -> Vec<(&Arc<dyn Tool>, bool)> {
+        let mut tools: Vec<(&String, &Arc<dyn Tool>)> = self.functions.iter().collect();
+        tools.sort_unstable_by_key(|(name, _)| *name);
+        tools
+            .into_iter()
+            .map(|(name, tool)| (tool, self.destructive.contains(name)))
+            .collect()
+    }
+
+    pub fn set_model(&mut self, model: impl ToString) {
+        self.model = model.to_string();
+    }
+
+    /// The turns recorded so far, oldest first.
+    pub fn history(&self) -> Vec<ChatMessage> {
+        self.history.lock().unwrap().messages().to_vec()
+    }
+
+    pub fn reset_history(&self) {
+        let mut history = self.history.lock().unwrap();
+        history.clear();
+        if let Err(e) = history.save() {
+            eprintln!("failed to save history: {e}");
+        }
+    }
+
+    /// Records a turn answered without the chat endpoint, which records its own turns,
+    /// so `--continue` can pick the conversation up.
+    pub fn record_turn(&self, question: &str, answer: &str) {
+        let question = ChatMessage::user(question.to_string());
+        let mut history = self.history.lock().unwrap();
+        if let Err(e) = history.record(question, answer.to_string()) {
+            output::warning(&format!("failed to save history: {e}"));
+        }
+    }
+
+    /// Sends the question along with the previous turns through the chat endpoint.
+    /// Both the question and the answer are recorded once the stream completes.
+    pub async fn generate_stream_with_history(
+        &self,
+        question: impl ToString,
+    ) -> Result<ChatMessageResponseStream, Error> {
+        self.chat(self.question_message(question), None).await
+    }
+
+    /// Streams the answer to `question` in [`Mode::Chat`]: the output of the tool that ran,
+    /// if any, is passed to the model as a message next to the system prompt and history.
+    pub async fn chat_stream(
+        &self,
+        question: impl ToString,
+    ) -> Result<(ChatMessageResponseStream, Option<ToolCall>), Error> {
         let question = question.to_string();
-        match self.call_function(&question).await {
-            Ok(resp) => {
-                let response = resp.message.unwrap().content;
-                let stream = self.summarize_stream(question, response).await?;
-                Ok(stream)
+        let call = self.select_tool(&question).await?;
+        self.chat_stream_with_tool(question, call).await
+    }
+
+    /// Streams the answer to `question` in [`Mode::Chat`] from the output of `call`, the tool
+    /// that already ran.
+    #[tracing::instrument(skip_all, fields(model = %self.model))]
+    pub async fn chat_stream_with_tool(
+        &self,
+        question: impl ToString,
+        call: Option<ToolCall>,
+    ) -> Result<(ChatMessageResponseStream, Option<ToolCall>), Error> {
+        let question = question.to_string();
+        match call {
+            Some(call) if !self.summarize => {
+                debug!(tool = %call.name, "returning the raw tool output");
+                let stream = single_chat_response(&self.model, format_tool_output(&call.output));
+                Ok((stream, Some(call)))
             }
-            Err(_) => {
-                let template = self.template_env.get_template("generation").unwrap();
-                let prompt = template.render(context! {question => question})?;
+            call => {
                 let stream = self
-                    .core
-                    .generate_stream(GenerationRequest::new(self.model.to_owned(), prompt))
+                    .chat(self.question_message(question), call.as_ref())
                     .await?;
-                Ok(stream)
+                Ok((stream, call))
             }
         }
     }
 
-    async fn call_function(&self, question: impl ToString) -> Result<ChatMessageResponse, Error> {
-        let functions: Vec<Arc<dyn Tool>> = self
-            .functions
-            .iter()
-            .map(|(_, func)| func.clone())
-            .collect();
-
-        let user_message = ChatMessage::user(question.to_string());
-        let parser = Arc::new(LlamaFunctionCall {});
-
-        let response = self
-            .core
-            .send_function_call(
-                FunctionCallRequest::new(self.model.to_owned(), functions, vec![user_message]),
-                parser,
-            )
+    /// The user message asking `question`, with the attached images.
+    fn question_message(&self, question: impl ToString) -> ChatMessage {
+        let message = ChatMessage::user(question.to_string());
+        if self.images.is_empty() {
+            return message;
+        }
+        message.with_images(self.images.clone())
+    }
+
+    async fn chat(
+        &self,
+        question: ChatMessage,
+        tool_call: Option<&ToolCall>,
+    ) -> Result<ChatMessageResponseStream, Error> {
+        let messages = self.chat_messages(question.clone(), tool_call);
+        let question = ChatMessage {
+            images: None,
+            ..question
+        };
+        let stream = self
+            .retry(|| self.core.chat_stream(self.chat_request(messages.clone())))
             .await?;
 
-        Ok(response)
+        let history = self.history.clone();
+        let mut answer = String::new();
+        let stream = stream.map(move |resp| {
+            if let Ok(resp) = &resp {
+                if let Some(message) = &resp.message {
+                    answer.push_str(&message.content);
+                }
+                if resp.done {
+                    let answer = std::mem::take(&mut answer);
+                    if let Err(e) = history.lock().unwrap().record(question.clone(), answer) {
+                        eprintln!("failed to save history: {e}");
+                    }
+                }
+            }
+            resp
+        });
+
+        Ok(Box::pin(stream))
+    }
+
+    /// Asks the model whether one of the configured tools answers the question and runs it.
+    /// A tool reporting an error is shown to the model, which may call it again with fixed
+    /// arguments up to `max_tool_iterations` times in total. The last failed call is returned
+    /// when it never succeeds.
+    ///
+    /// Returns `Ok(None)` when no tool applies, so callers can fall back to plain generation,
+    /// while errors talking to the Ollama server are propagated.
+    #[tracing::instrument(skip_all, fields(tools = self.functions.len()))]
+    async fn call_function(&self, question: impl ToString) -> Result<Option<ToolCall>, Error> {
+        if self.functions.is_empty() {
+            return Ok(None);
+        }
+
+        let tools: Vec<Arc<dyn Tool>> = self.functions.values().cloned().collect();
+        let mut messages = self.tool_messages(question).await;
+        let mut failed = None;
+        for iteration in 1..=self.max_tool_iterations.max(1) {
+            let request = self.retry(|| {
+                let request = self.chat_request(messages.clone());
+                self.core.chat_with_tools(request, &tools)
+            });
+            let response = match self.timeout {
+                Some(limit) => tokio::time::timeout(limit, request)
+                    .await
+                    .map_err(|_| Timeout(limit))??,
+                None => request.await?,
+            };
+            let Some((name, arguments)) = requested_tool(&response) else {
+                break;
+            };
+            let Some(tool) = self.functions.get(&name) else {
+                debug!(tool = %name, "model asked for an unknown tool");
+                break;
+            };
+
+            let output = match self.run_tool(&name, tool, &arguments).await {
+                Ok(output) => output,
+                // The user said no, asking the model again won't change that.
+                Err(e) if e.is::<Declined>() => {
+                    let output = json!({"error": e.to_string()}).to_string();
+                    return Ok(Some(ToolCall {
+                        name,
+                        arguments,
+                        output,
+                    }));
+                }
+                Err(e) => json!({"error": e.to_string()}).to_string(),
+            };
+            let Some(error) = tool_error(&output) else {
+                return Ok(Some(ToolCall {
+                    name,
+                    arguments,
+                    output,
+                }));
+            };
+
+            debug!(tool = %name, iteration, "tool failed, letting the model retry: {error}");
+            messages.extend(response.message);
+            messages.push(ChatMessage::user(format!(
+                "The {name} tool returned an error: {error}\n\
+                 Call it again with corrected arguments."
+            )));
+            failed = Some(ToolCall {
+                name,
+                arguments,
+                output,
+            });
+        }
+        Ok(failed)
     }
 
+    /// Validates the arguments, asks for confirmation if needed and runs the tool.
+    async fn run_tool(
+        &self,
+        name: &str,
+        tool: &Arc<dyn Tool>,
+        arguments: &Value,
+    ) -> Result<String, Error> {
+        debug!(tool = %name, %arguments, "calling tool");
+        let started = Instant::now();
+        let result = match args::validate(&tool.parameters(), arguments) {
+            Ok(()) => match self.confirm(name, arguments).await {
+                Ok(()) => tool.run(arguments.clone()).await,
+                Err(e) => Err(e.into()),
+            },
+            Err(e) => Err(e.into()),
+        };
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        match &result {
+            Ok(output) => {
+                debug!(tool = %name, elapsed_ms, output_len = output.len(), "tool finished");
+                trace!(tool = %name, %output, "tool output");
+            }
+            Err(e) if e.is::<ArgError>() || e.is::<Declined>() => {
+                debug!(tool = %name, "tool didn't run: {e}");
+            }
+            Err(e) => warn!(tool = %name, elapsed_ms, "tool failed: {e}"),
+        }
+        result
+    }
+
+    /// Asks the user whether the destructive tool `name` may run with `arguments`.
+    async fn confirm(&self, name: &str, arguments: &Value) -> Result<(), Declined> {
+        if !self.destructive.contains(name) || self.confirmation != Confirmation::Ask {
+            return Ok(());
+        }
+        if !output::can_prompt() {
+            output::warning(&format!(
+                "not running {name} without confirmation, pass --yes to allow it"
+            ));
+            return Err(Declined(format!(
+                "{name} needs the user's confirmation, which can't be asked for here"
+            )));
+        }
+
+        let question = format!("Run {name} with {arguments}?");
+        let confirmed = tokio::task::spawn_blocking(move || output::confirm(&question)).await;
+        match confirmed {
+            Ok(Ok(true)) => Ok(()),
+            _ => Err(Declined(format!("the user declined to run {name}"))),
+        }
+    }
+
+    /// Characters of tool output the summary prompt can hold next to the question and answer.
+    async fn tool_output_budget(&self) -> usize {
+        let tokens = match self.max_tool_output_tokens {
+            Some(tokens) => tokens,
+            None => {
+                let context = self.core.context_length(self.summary_model()).await;
+                context.unwrap_or(DEFAULT_CONTEXT_LENGTH) / 2
+            }
+        };
+        tokens * CHARS_PER_TOKEN
+    }
+
+    #[tracing::instrument(skip_all)]
     async fn summarize_stream(
         &self,
         question: impl ToString,
         answer: impl ToString,
     ) -> Result<GenerationResponseStream, Error> {
-        let (question, answer) = (question.to_string(), answer.to_string());
+        let prompt = self.summary_prompt(&question.to_string(), &answer.to_string())?;
+        self.generate(self.summary_model(), prompt, &[]).await
+    }
+
+    /// Renders what would be sent to the model for `question`, without contacting the server.
+    /// Returns a title and the text of every prompt, in the order they would be sent.
+    pub async fn dry_run(
+        &self,
+        question: &str,
+        with_history: bool,
+    ) -> Result<Vec<(String, String)>, Error> {
+        let mut prompts = Vec::new();
+        let chat = with_history || self.mode == Mode::Chat;
+        if self.raw && !chat {
+            prompts.push(("prompt".to_string(), question.to_string()));
+            return Ok(prompts);
+        }
+        if !self.functions.is_empty() && (self.mode == Mode::Chat || !with_history) {
+            for message in self.tool_messages(question).await {
+                let title = format!("tool selection, {:?} message", message.role);
+                prompts.push((title.to_lowercase(), message.content));
+            }
+        }
+        if chat {
+            let messages = self.chat_messages(ChatMessage::user(question.to_string()), None);
+            let chat = messages
+                .iter()
+                .map(|m| format!("{:?}: {}", m.role, m.content))
+                .collect::<Vec<_>>()
+                .join("\n");
+            prompts.push(("chat messages".to_string(), chat));
+            return Ok(prompts);
+        }
+
+        if !self.functions.is_empty() {
+            let summary = self.summary_prompt(question, "<output of the chosen tool>")?;
+            prompts.push(("summary prompt, if a tool runs".to_string(), summary));
+        }
+        let generation = self.generation_prompt(question)?;
+        prompts.push(("generation prompt".to_string(), generation));
+        Ok(prompts)
+    }
+
+    fn generation_prompt(&self, question: &str) -> Result<String, Error> {
+        let template = self.template_env.get_template("generation").unwrap();
+        Ok(template.render(context! {
+            system_prompt => self.system_prompt(),
+            context => self.context,
+            question => question,
+        })?)
+    }
+
+    fn summary_prompt(&self, question: &str, answer: &str) -> Result<String, Error> {
         let template = self.template_env.get_template("summary").unwrap();
-        let prompt = template.render(context! {question => question, answer => answer})?;
-        let stream = self
-            .core
-            .generate_stream(GenerationRequest::new(self.model.to_owned(), prompt))
-            .await?;
-        Ok(stream)
+        Ok(template.render(context! {
+            system_prompt => self.system_prompt(),
+            question => question,
+            answer => answer,
+            length => self.summary_length.instruction(),
+        })?)
     }
 
-    fn parse_functions(functions: Vec<String>) -> HashMap<String, Arc<dyn Tool>> {
-        let mut function_set: HashMap<String, Arc<dyn Tool>> = HashMap::new();
-        for f in functions {
-            let function_name = f.trim().to_lowercase();
-            match function_name.as_str() {
-                "ddg_searcher" => {
-                    function_set.insert("ddg_searcher".to_string(), Arc::new(DDGSearcher::new()));
+    /// The system message describing the configured tools, followed by the question.
+    /// Servers given the tools natively only get the question.
+    async fn tool_messages(&self, question: impl ToString) -> Vec<ChatMessage> {
+        let question = ChatMessage::user(question.to_string());
+        if self.core.native_tools() {
+            return vec![question];
+        }
+        let functions: Vec<Arc<dyn Tool>> = self.functions.values().cloned().collect();
+        let parser = LlamaFunctionCall {};
+        vec![parser.get_system_message(&functions).await, question]
+    }
+
+    /// The system prompt and the previous turns, followed by what `tool_call` returned
+    /// and `question`.
+    fn chat_messages(
+        &self,
+        question: ChatMessage,
+        tool_call: Option<&ToolCall>,
+    ) -> Vec<ChatMessage> {
+        let mut messages = Vec::new();
+        if let Some(system_prompt) = self.system_prompt.as_ref().filter(|p| !p.is_empty()) {
+            messages.push(ChatMessage::system(system_prompt.to_owned()));
+        }
+        if let Some(context) = &self.context {
+            messages.push(ChatMessage::system(format!(
+                "Use these files provided by the user as context:\n{context}"
+            )));
+        }
+        messages.extend_from_slice(self.history.lock().unwrap().messages());
+        if let Some(call) = tool_call {
+            messages.push(ChatMessage::system(format!(
+                "The {} tool ran for the next question and returned: {}",
+                call.name, call.output
+            )));
+        }
+        messages.push(question);
+        messages
+    }
+
+    /// Streams `model`'s completion of `prompt` about `images`, replaying it from the cache
+    /// when possible.
+    async fn generate(
+        &self,
+        model: &str,
+        prompt: String,
+        images: &[Image],
+    ) -> Result<GenerationResponseStream, Error> {
+        let request = || {
+            let request = self.generation_request(model, prompt.clone());
+            self.core.generate_stream(request.images(images.to_vec()))
+        };
+        let Some(cache) = self.cache.as_ref().filter(|_| images.is_empty()) else {
+            return self.retry(request).await;
+        };
+
+        let key = Cache::key(model, &prompt, &self.options);
+        if let Some(answer) = cache.get(&key) {
+            debug!("answering from cache");
+            return Ok(single_response(model, answer));
+        }
+
+        let stream = self.retry(request).await?;
+        let cache = cache.clone();
+        let mut answer = String::new();
+        let stream = stream.map(move |chunk| {
+            if let Ok(responses) = &chunk {
+                for resp in responses {
+                    answer.push_str(&resp.response);
+                    if resp.done {
+                        if let Err(e) = cache.put(&key, &answer) {
+                            eprintln!("failed to write cache: {e}");
+                        }
+                    }
+                }
+            }
+            chunk
+        });
+        Ok(Box::pin(stream))
+    }
+
+    /// Retries `request` with exponential backoff while the server is unreachable.
+    /// A model that isn't pulled is pulled once with `auto_pull`, and reported as
+    /// [`ModelNotPulled`] otherwise. Other errors reported by the server are returned right away.
+    async fn retry<T, F, Fut>(&self, mut request: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, OllamaError>>,
+    {
+        let mut attempt = 0;
+        let mut pulled = false;
+        loop {
+            match request().await {
+                Err(e) if missing_model(&e).is_some() => {
+                    let model = missing_model(&e).unwrap();
+                    if !self.auto_pull || pulled {
+                        return Err(Box::new(ModelNotPulled(model)));
+                    }
+                    output::warning(&format!("model '{model}' is not pulled, pulling it now"));
+                    self.core.pull(&model).await?;
+                    pulled = true;
                 }
-                "rust_toolchain_switcher" => {
-                    function_set.insert(
-                        "rust_toolchain_switcher".to_string(),
-                        Arc::new(RustToolchainSwitcher::default()),
-                    );
+                Err(e) if attempt < self.retry.max_retries && is_transient(&e) => {
+                    attempt += 1;
+                    debug!("transient error: {e}");
+                    output::retrying(attempt, self.retry.max_retries);
+                    tokio::time::sleep(retry_delay(self.retry.base_delay_ms, attempt)).await;
                 }
-                other => println!("unknown tool: {other}"),
+                result => return Ok(result?),
             }
         }
+    }
+
+    fn generation_request(&self, model: &str, prompt: String) -> GenerationRequest {
+        let request =
+            GenerationRequest::new(model.to_owned(), prompt).options(self.options.clone());
+        match &self.keep_alive {
+            Some(keep_alive) => request.keep_alive(keep_alive.clone()),
+            None => request,
+        }
+    }
+
+    fn chat_request(&self, messages: Vec<ChatMessage>) -> ChatMessageRequest {
+        ChatMessageRequest::new(self.model.to_owned(), messages).options(self.options.clone())
+    }
 
-        function_set
+    /// Names of the enabled tools that are destructive, by their config or the registry's default.
+    fn destructive_functions(
+        functions: &[FunctionConfig],
+        registry: &ToolRegistry,
+    ) -> HashSet<String> {
+        functions
+            .iter()
+            .filter(|f| f.enabled)
+            .map(|f| (f.name.trim().to_lowercase(), f.destructive))
+            .filter(|(name, destructive)| {
+                destructive.unwrap_or_else(|| registry.is_destructive(name))
+            })
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Builds the enabled tools, returning the names the registry doesn't know separately.
+    fn parse_functions(
+        functions: Vec<FunctionConfig>,
+        registry: &ToolRegistry,
+    ) -> (HashMap<String, Arc<dyn Tool>>, Vec<String>) {
+        let mut function_set: HashMap<String, Arc<dyn Tool>> = HashMap::new();
+        let mut unknown = Vec::new();
+        for f in functions.into_iter().filter(|f| f.enabled) {
+            let function_name = f.name.trim().to_lowercase();
+            match registry.build(&function_name) {
+                Some(tool) => {
+                    let tool = match f.description_override {
+                        Some(description) => Arc::new(DescribedTool::new(tool, description)),
+                        None => tool,
+                    };
+                    function_set.insert(function_name, tool);
+                }
+                None => unknown.push(function_name),
+            }
+        }
+
+        (function_set, unknown)
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct FucntionResponse {
-    function: Option<String>,
+/// A stream holding all of `text` in a single, final response.
+fn single_response(model: &str, text: String) -> GenerationResponseStream {
+    let response = GenerationResponse {
+        model: model.to_owned(),
+        created_at: String::new(),
+        response: text,
+        done: true,
+        context: None,
+        total_duration: None,
+        prompt_eval_count: None,
+        prompt_eval_duration: None,
+        eval_count: None,
+        eval_duration: None,
+    };
+    Box::pin(tokio_stream::once(Ok(vec![response])))
+}
+
+fn single_chat_response(model: &str, text: String) -> ChatMessageResponseStream {
+    let response = ChatMessageResponse {
+        model: model.to_owned(),
+        created_at: String::new(),
+        message: Some(ChatMessage::assistant(text)),
+        done: true,
+        final_data: None,
+    };
+    Box::pin(tokio_stream::once(Ok(response)))
+}
+
+/// Cuts `text` down to about `max_chars` characters, keeping its head and tail and noting
+/// how much was dropped in between.
+fn truncate_middle(text: &str, max_chars: usize) -> Cow<'_, str> {
+    let chars = text.chars().count();
+    if chars <= max_chars {
+        return Cow::Borrowed(text);
+    }
+    let dropped = chars - max_chars;
+    debug!(chars, dropped, "truncating the tool output");
+    let byte = |n: usize| text.char_indices().nth(n).map_or(text.len(), |(i, _)| i);
+    let head = &text[..byte(max_chars / 2)];
+    let tail = &text[byte(chars - (max_chars - max_chars / 2))..];
+    Cow::Owned(format!(
+        "{head}\n[… {dropped} characters omitted …]\n{tail}"
+    ))
+}
+
+/// Lays a tool's JSON output out for reading: one `key: value` line per field,
+/// with multi-line strings like file contents printed as they are.
+fn format_tool_output(output: &str) -> String {
+    let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(output) else {
+        return output.to_string();
+    };
+
+    let mut formatted = String::new();
+    for (key, value) in fields {
+        let value = match value {
+            Value::String(text) if text.contains('\n') => format!("\n{}", text.trim_end()),
+            Value::String(text) => format!(" {text}"),
+            value @ (Value::Array(_) | Value::Object(_)) => {
+                let pretty = serde_json::to_string_pretty(&value).unwrap_or_default();
+                format!("\n{pretty}")
+            }
+            value => format!(" {value}"),
+        };
+        formatted.push_str(&format!("{key}:{value}\n"));
+    }
+    formatted
+}
+
+/// Name of the model the server reported as not pulled, from errors like
+/// `model "llama3:70b" not found, try pulling it first`.
+fn missing_model(e: &OllamaError) -> Option<String> {
+    let pattern = Regex::new(r#"model [\\"']*([^\\"'\s]+)[\\"']* not found"#).unwrap();
+    let message = e.to_string();
+    pattern
+        .captures(&message)
+        .map(|captures| captures[1].to_string())
+}
+
+/// Transport failures surface from ollama-rs as reqwest's error message,
+/// while errors answered by the server carry its JSON error body.
+fn is_transient(e: &OllamaError) -> bool {
+    is_connection_error(&e.to_string())
+}
+
+/// The wait before retry number `attempt`, starting at `base_delay_ms` and doubling
+/// until it reaches [`MAX_RETRY_DELAY`].
+fn retry_delay(base_delay_ms: u64, attempt: u32) -> Duration {
+    let factor = 2u64
+        .checked_pow(attempt.saturating_sub(1))
+        .unwrap_or(u64::MAX);
+    let delay = base_delay_ms.saturating_mul(factor);
+    Duration::from_millis(delay).min(MAX_RETRY_DELAY)
+}
+
+/// The tool and arguments the model asked for in its tool selection reply.
+/// Replies without a message, which some models send, request no tool.
+fn requested_tool(response: &ChatMessageResponse) -> Option<(String, Value)> {
+    let Some(message) = &response.message else {
+        debug!("tool selection reply has no message");
+        return None;
+    };
+    trace!(reply = %message.content, "tool selection reply");
+    parse_function_call(&message.content)
+}
+
+/// The message of a tool output like `{"error": "..."}`, the way tools report failures.
+fn tool_error(output: &str) -> Option<String> {
+    let output: Value = serde_json::from_str(output).ok()?;
+    match output.get("error")? {
+        Value::String(error) => Some(error.to_owned()),
+        error => Some(error.to_string()),
+    }
+}
+
+/// Extracts `<function=name>{...}</function>` from a llama function-calling response.
+fn parse_function_call(response: &str) -> Option<(String, Value)> {
+    let response = response
+        .trim()
+        .trim_start_matches("```json")
+        .trim_end_matches("```")
+        .replace("{{", "{")
+        .replace("}}", "}");
+    let function_regex = Regex::new(r"<function=(\w+)>(.*?)</function>").unwrap();
+    let captures = function_regex.captures(&response)?;
+    let arguments = serde_json::from_str(&captures[2]).ok()?;
+    Some((captures[1].to_string(), arguments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{backend::MockBackend, tools::ToolsConfig};
+
+    fn shark(templates: &Templates) -> Result<Shark<'_>, Error> {
+        Shark::new(
+            Box::new(MockBackend::default()),
+            "llama3.1",
+            Vec::new(),
+            &ToolRegistry::default(),
+            templates,
+            false,
+        )
+    }
+
+    #[test]
+    fn new_accepts_embedded_templates() {
+        assert!(shark(&Templates::default()).is_ok());
+    }
+
+    #[test]
+    fn new_rejects_invalid_template() {
+        let templates = Templates {
+            generation: "{{ question ".to_string(),
+            ..Default::default()
+        };
+        let err = shark(&templates).err().unwrap();
+        assert!(err.to_string().contains("generation template"));
+
+        let templates = Templates {
+            summary: "{% if answer %}".to_string(),
+            ..Default::default()
+        };
+        let err = shark(&templates).err().unwrap();
+        assert!(err.to_string().contains("summary template"));
+    }
+
+    #[test]
+    fn new_rejects_unknown_tools_in_strict_mode() {
+        let templates = Templates::default();
+        let registry = ToolRegistry::with_builtin_tools(&ToolsConfig::default());
+        let functions = vec!["calculator".into(), "ddg_search".into()];
+
+        let err = Shark::new(
+            Box::new(MockBackend::default()),
+            "llama3.1",
+            functions.clone(),
+            &registry,
+            &templates,
+            true,
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("ddg_search"));
+
+        let shark = Shark::new(
+            Box::new(MockBackend::default()),
+            "llama3.1",
+            functions,
+            &registry,
+            &templates,
+            false,
+        )
+        .unwrap();
+        assert_eq!(shark.functions.len(), 1);
+    }
+
+    #[test]
+    fn replies_without_a_message_request_no_tool() {
+        let response: ChatMessageResponse = serde_json::from_value(serde_json::json!({
+            "model": "llama3.1",
+            "created_at": "2024-08-04T08:52:19.385406455-07:00",
+            "done": true,
+        }))
+        .unwrap();
+        assert!(response.message.is_none());
+        assert!(requested_tool(&response).is_none());
+
+        let response: ChatMessageResponse = serde_json::from_value(serde_json::json!({
+            "model": "llama3.1",
+            "created_at": "2024-08-04T08:52:19.385406455-07:00",
+            "message": {
+                "role": "assistant",
+                "content": "<function=calculator>{\"expression\": \"1+1\"}</function>",
+            },
+            "done": true,
+        }))
+        .unwrap();
+        let (name, arguments) = requested_tool(&response).unwrap();
+        assert_eq!(name, "calculator");
+        assert_eq!(arguments["expression"], "1+1");
+    }
+
+    #[test]
+    fn chat_messages_pass_the_tool_output_before_the_question() {
+        let templates = Templates::default();
+        let shark = shark(&templates)
+            .unwrap()
+            .with_system_prompt(Some("be brief".to_string()));
+        let call = ToolCall {
+            name: "calculator".to_string(),
+            arguments: Value::Null,
+            output: r#"{"result":8.0}"#.to_string(),
+        };
+
+        let messages = shark.chat_messages(ChatMessage::user("2+2*3?".to_string()), Some(&call));
+        let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            [
+                "be brief",
+                r#"The calculator tool ran for the next question and returned: {"result":8.0}"#,
+                "2+2*3?",
+            ]
+        );
+    }
+
+    #[test]
+    fn system_flag_replaces_or_clears_the_system_prompt() {
+        let templates = Templates::default();
+        let shark = shark(&templates)
+            .unwrap()
+            .with_system_prompt(Some("be brief".to_string()))
+            .with_system_override(Some("answer in French".to_string()));
+        let prompt = shark.generation_prompt("hello").unwrap();
+        assert!(prompt.starts_with("\nanswer in French\n"), "{prompt}");
+
+        let shark = shark.with_system_override(Some(String::new()));
+        let prompt = shark.generation_prompt("hello").unwrap();
+        assert!(!prompt.contains(DEFAULT_SYSTEM_PROMPT), "{prompt}");
+        let messages = shark.chat_messages(ChatMessage::user("hello".to_string()), None);
+        assert_eq!(messages.len(), 1);
+    }
+
+    #[test]
+    fn summary_length_is_rendered_into_the_summary_prompt() {
+        let templates = Templates::default();
+        let shark = shark(&templates).unwrap();
+        let prompt = shark.summary_prompt("question", "answer").unwrap();
+        assert!(prompt.contains("try to give a short summary of one or two sentences."));
+
+        let shark = shark.with_summary_length(SummaryLength::Detailed);
+        let prompt = shark.summary_prompt("question", "answer").unwrap();
+        assert!(prompt.contains("try to give a detailed summary of several paragraphs"));
+    }
+
+    #[test]
+    fn disabled_tools_are_skipped_and_descriptions_overridden() {
+        let registry = ToolRegistry::with_builtin_tools(&ToolsConfig::default());
+        let functions = vec![
+            FunctionConfig {
+                enabled: false,
+                .."weather".into()
+            },
+            FunctionConfig {
+                description_override: Some("Do arithmetic".to_string()),
+                .."calculator".into()
+            },
+        ];
+
+        let (functions, unknown) = Shark::parse_functions(functions, &registry);
+        assert!(unknown.is_empty());
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions["calculator"].description(), "Do arithmetic");
+    }
+
+    #[test]
+    fn destructive_tools_follow_the_registry_unless_configured() {
+        let registry = ToolRegistry::with_builtin_tools(&ToolsConfig::default());
+        let functions = vec![
+            "shell_command".into(),
+            "calculator".into(),
+            FunctionConfig {
+                destructive: Some(false),
+                .."cargo_add".into()
+            },
+            FunctionConfig {
+                destructive: Some(true),
+                .."cargo_runner".into()
+            },
+            FunctionConfig {
+                enabled: false,
+                .."rust_format".into()
+            },
+        ];
+
+        let destructive = Shark::destructive_functions(&functions, &registry);
+        let mut destructive: Vec<&str> = destructive.iter().map(String::as_str).collect();
+        destructive.sort_unstable();
+        assert_eq!(destructive, ["cargo_runner", "shell_command"]);
+    }
+
+    #[tokio::test]
+    async fn tool_output_is_summarized() {
+        let templates = Templates::default();
+        let registry = ToolRegistry::with_builtin_tools(&ToolsConfig::default());
+        let backend = MockBackend::new("It is 8.")
+            .with_tool_call("calculator", json!({"expression": "2+2*3"}));
+        let shark = Shark::new(
+            Box::new(backend.clone()),
+            "llama3.1",
+            vec!["calculator".into()],
+            &registry,
+            &templates,
+            false,
+        )
+        .unwrap();
+
+        let (mut stream, call) = shark.generate_stream("2+2*3?").await.unwrap();
+        let call = call.unwrap();
+        assert_eq!(call.name, "calculator");
+        assert!(call.output.contains('8'), "{}", call.output);
+
+        let mut answer = String::new();
+        while let Some(Ok(responses)) = stream.next().await {
+            answer.extend(responses.into_iter().map(|resp| resp.response));
+        }
+        assert_eq!(answer, "It is 8.");
+
+        let requests = backend.requests();
+        assert_eq!(requests.len(), 2);
+        let summary = requests[1]["prompt"].as_str().unwrap();
+        assert!(summary.contains(&call.output), "{summary}");
+    }
+
+    #[tokio::test]
+    async fn a_selected_tool_is_shared_by_several_answers() {
+        let templates = Templates::default();
+        let registry = ToolRegistry::with_builtin_tools(&ToolsConfig::default());
+        let backend = MockBackend::new("It is 8.")
+            .with_tool_call("calculator", json!({"expression": "2+2*3"}));
+        let shark = Shark::new(
+            Box::new(backend.clone()),
+            "llama3.1",
+            vec!["calculator".into()],
+            &registry,
+            &templates,
+            false,
+        )
+        .unwrap();
+
+        let call = shark.select_tool("2+2*3?").await.unwrap();
+        for _ in 0..3 {
+            let (_, used) = shark
+                .generate_stream_with_tool("2+2*3?", call.clone())
+                .await
+                .unwrap();
+            assert_eq!(used.unwrap().name, "calculator");
+        }
+
+        let requests = backend.requests();
+        assert_eq!(requests.len(), 4);
+        assert!(requests[1..]
+            .iter()
+            .all(|request| request.get("prompt").is_some()));
+    }
+
+    #[test]
+    fn long_tool_output_keeps_head_and_tail() {
+        assert_eq!(truncate_middle("short", 5), "short");
+        assert_eq!(
+            truncate_middle("abcdefghij", 4),
+            "ab\n[… 6 characters omitted …]\nij"
+        );
+        assert_eq!(
+            truncate_middle("ééééé🦈", 3),
+            "é\n[… 3 characters omitted …]\né🦈"
+        );
+    }
+
+    #[tokio::test]
+    async fn tool_output_is_truncated_before_the_summary() {
+        let templates = Templates::default();
+        let registry = ToolRegistry::with_builtin_tools(&ToolsConfig::default());
+        let backend = MockBackend::new("It is 8.")
+            .with_tool_call("calculator", json!({"expression": "2+2*3"}));
+        let shark = Shark::new(
+            Box::new(backend.clone()),
+            "llama3.1",
+            vec!["calculator".into()],
+            &registry,
+            &templates,
+            false,
+        )
+        .unwrap()
+        .with_max_tool_output_tokens(Some(2));
+
+        let (_, call) = shark.generate_stream("2+2*3?").await.unwrap();
+        let output = call.unwrap().output;
+        let summary = backend.requests()[1]["prompt"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(!summary.contains(&output), "{summary}");
+        assert!(summary.contains(&truncate_middle(&output, 8).into_owned()));
+    }
+
+    #[tokio::test]
+    async fn chat_answers_are_recorded_in_the_history() {
+        let templates = Templates::default();
+        let backend = MockBackend::new("Hi there!");
+        let shark = Shark::new(
+            Box::new(backend.clone()),
+            "llama3.1",
+            Vec::new(),
+            &ToolRegistry::default(),
+            &templates,
+            false,
+        )
+        .unwrap();
+
+        for question in ["hello", "again"] {
+            let mut stream = shark.generate_stream_with_history(question).await.unwrap();
+            while stream.next().await.is_some() {}
+        }
+
+        let history: Vec<String> = shark.history().into_iter().map(|m| m.content).collect();
+        assert_eq!(history, ["hello", "Hi there!", "again", "Hi there!"]);
+        let last = &backend.requests()[1]["messages"];
+        assert_eq!(last.as_array().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn tool_errors_are_shown_to_the_model_until_the_cap() {
+        let templates = Templates::default();
+        let registry = ToolRegistry::with_builtin_tools(&ToolsConfig::default());
+        let backend = MockBackend::default().with_tool_call("calculator", json!({"expression": 5}));
+        let shark = Shark::new(
+            Box::new(backend.clone()),
+            "llama3.1",
+            vec!["calculator".into()],
+            &registry,
+            &templates,
+            false,
+        )
+        .unwrap()
+        .with_max_tool_iterations(3);
+
+        let call = shark.call_function("2+2?").await.unwrap().unwrap();
+        let error = tool_error(&call.output).unwrap();
+        assert!(error.contains("'expression' must be a string"), "{error}");
+
+        let requests = backend.requests();
+        assert_eq!(requests.len(), 3);
+        let messages = requests[2]["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 6);
+        let feedback = messages[5]["content"].as_str().unwrap();
+        assert!(feedback.starts_with("The calculator tool returned an error"));
+    }
+
+    #[test]
+    fn retry_delays_double_up_to_the_cap() {
+        assert_eq!(retry_delay(500, 1), Duration::from_millis(500));
+        assert_eq!(retry_delay(500, 3), Duration::from_secs(2));
+        assert_eq!(retry_delay(500, 64), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(u64::MAX, 2), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(0, 100), Duration::ZERO);
+    }
 }
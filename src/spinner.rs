@@ -0,0 +1,84 @@
+use std::{
+    io::{IsTerminal, Write},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use termcolor::{Color, ColorSpec, StandardStream, WriteColor};
+use tokio::task::JoinHandle;
+
+use crate::output;
+
+const FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Set while the user is being asked something, so spinners don't draw over the question.
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Animated braille spinner on stderr, shown only when stderr is a terminal.
+pub struct Spinner {
+    task: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    pub fn start(color: Option<Color>) -> Self {
+        if !std::io::stderr().is_terminal() {
+            return Self::hidden();
+        }
+
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(80));
+            for frame in FRAMES.iter().cycle() {
+                interval.tick().await;
+                if PAUSED.load(Ordering::Relaxed) {
+                    continue;
+                }
+                let mut stderr = StandardStream::stderr(output::stderr_choice());
+                let _ = stderr.set_color(ColorSpec::new().set_fg(color));
+                let _ = write!(stderr, "\r{frame} ");
+                let _ = stderr.reset();
+                let _ = stderr.flush();
+            }
+        });
+        Self { task: Some(task) }
+    }
+
+    /// A spinner that never shows.
+    pub fn hidden() -> Self {
+        Self { task: None }
+    }
+
+    /// Stops the animation and erases it, leaving the cursor where the spinner was.
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+            let mut stderr = std::io::stderr();
+            let _ = write!(stderr, "\r  \r");
+            let _ = stderr.flush();
+        }
+    }
+}
+
+/// Hides running spinners until the returned guard is dropped.
+pub fn pause() -> Paused {
+    PAUSED.store(true, Ordering::Relaxed);
+    if std::io::stderr().is_terminal() {
+        let mut stderr = std::io::stderr();
+        let _ = write!(stderr, "\r  \r");
+        let _ = stderr.flush();
+    }
+    Paused(())
+}
+
+pub struct Paused(());
+
+impl Drop for Paused {
+    fn drop(&mut self) {
+        PAUSED.store(false, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
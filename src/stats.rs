@@ -0,0 +1,100 @@
+use std::{fmt, io::Write, time::Duration};
+
+use ollama_rs::generation::{chat::ChatMessageFinalResponseData, completion::GenerationResponse};
+use termcolor::{ColorSpec, StandardStream, WriteColor};
+
+use crate::output;
+
+/// Throughput figures reported by Ollama in the final chunk of an answer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub eval_count: u16,
+    pub eval_duration: Duration,
+    pub total_duration: Duration,
+    pub load_duration: Duration,
+}
+
+impl Stats {
+    /// Reads the stats off the `done` chunk of a generation stream.
+    /// Cached answers carry no durations and yield `None`.
+    pub fn from_generation(resp: &GenerationResponse) -> Option<Self> {
+        if !resp.done {
+            return None;
+        }
+        Some(Self::new(
+            resp.eval_count?,
+            resp.eval_duration?,
+            resp.total_duration?,
+            resp.prompt_eval_duration.unwrap_or_default(),
+        ))
+    }
+
+    pub fn from_chat(data: &ChatMessageFinalResponseData) -> Self {
+        Self::new(
+            data.eval_count,
+            data.eval_duration,
+            data.total_duration,
+            data.prompt_eval_duration,
+        )
+    }
+
+    /// ollama-rs doesn't expose `load_duration`, so it is taken as the part of the total
+    /// spent neither on the prompt nor on the answer.
+    fn new(eval_count: u16, eval_ns: u64, total_ns: u64, prompt_eval_ns: u64) -> Self {
+        Self {
+            eval_count,
+            eval_duration: Duration::from_nanos(eval_ns),
+            total_duration: Duration::from_nanos(total_ns),
+            load_duration: Duration::from_nanos(
+                total_ns
+                    .saturating_sub(prompt_eval_ns)
+                    .saturating_sub(eval_ns),
+            ),
+        }
+    }
+
+    pub fn tokens_per_sec(&self) -> f64 {
+        let secs = self.eval_duration.as_secs_f64();
+        if secs == 0.0 {
+            return 0.0;
+        }
+        f64::from(self.eval_count) / secs
+    }
+
+    /// Prints the stats dimmed on stderr, so piped answers and `--json` output stay clean.
+    pub fn print(&self) {
+        let mut stderr = StandardStream::stderr(output::stderr_choice());
+        let _ = stderr.set_color(ColorSpec::new().set_dimmed(true));
+        let _ = writeln!(stderr, "{self}");
+        let _ = stderr.reset();
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} tokens, {:.1} tokens/s, total {:.2}s, load {:.2}s",
+            self.eval_count,
+            self.tokens_per_sec(),
+            self.total_duration.as_secs_f64(),
+            self.load_duration.as_secs_f64(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_is_what_remains_of_the_total() {
+        let stats = Stats::new(30, 1_500_000_000, 2_000_000_000, 100_000_000);
+        assert_eq!(stats.load_duration, Duration::from_millis(400));
+        assert_eq!(stats.tokens_per_sec(), 20.0);
+        assert_eq!(
+            stats.to_string(),
+            "30 tokens, 20.0 tokens/s, total 2.00s, load 0.40s"
+        );
+    }
+}
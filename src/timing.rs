@@ -0,0 +1,143 @@
+use std::{
+    fmt,
+    io::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use termcolor::{ColorSpec, StandardStream, WriteColor};
+
+use crate::output;
+
+/// A stage of answering a question, as offsets from the start of the run.
+#[derive(Debug, Clone, PartialEq)]
+struct Stage {
+    name: &'static str,
+    start: Duration,
+    end: Duration,
+}
+
+#[derive(Debug)]
+struct Timeline {
+    origin: Instant,
+    stages: Vec<Stage>,
+}
+
+/// Where the time goes for `--profile-perf`. Clones share the timeline, so the shark can
+/// time its tool calls while `main` times the rest. Does nothing unless enabled.
+#[derive(Debug, Clone, Default)]
+pub struct Timings(Option<Arc<Mutex<Timeline>>>);
+
+impl Timings {
+    /// Starts the clock now.
+    pub fn enabled() -> Self {
+        Self(Some(Arc::new(Mutex::new(Timeline {
+            origin: Instant::now(),
+            stages: Vec::new(),
+        }))))
+    }
+
+    /// Records the stage `name` that began at `started` and ends now.
+    pub fn record(&self, name: &'static str, started: Instant) {
+        let Some(timeline) = &self.0 else {
+            return;
+        };
+        let mut timeline = timeline.lock().unwrap();
+        let start = started.saturating_duration_since(timeline.origin);
+        let end = timeline.origin.elapsed();
+        timeline.stages.push(Stage { name, start, end });
+    }
+
+    /// Records the stage `name` as lasting from the end of the previous one until now.
+    pub fn mark(&self, name: &'static str) {
+        let Some(timeline) = &self.0 else {
+            return;
+        };
+        let mut timeline = timeline.lock().unwrap();
+        let start = timeline.stages.last().map(|stage| stage.end);
+        let end = timeline.origin.elapsed();
+        timeline.stages.push(Stage {
+            name,
+            start: start.unwrap_or_default(),
+            end,
+        });
+    }
+
+    /// Prints the breakdown dimmed on stderr, like the stats.
+    pub fn print(&self) {
+        let Some(timeline) = &self.0 else {
+            return;
+        };
+        let timeline = timeline.lock().unwrap();
+        let mut stderr = StandardStream::stderr(output::stderr_choice());
+        let _ = stderr.set_color(ColorSpec::new().set_dimmed(true));
+        let _ = write!(stderr, "{timeline}");
+        let _ = stderr.reset();
+    }
+}
+
+impl fmt::Display for Timeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |duration: Duration| format!("{:.1}ms", duration.as_secs_f64() * 1000.0);
+        writeln!(f, "{:<14} {:>10} {:>10}", "stage", "at", "took")?;
+        for stage in &self.stages {
+            writeln!(
+                f,
+                "{:<14} {:>10} {:>10}",
+                stage.name,
+                ms(stage.start),
+                ms(stage.end.saturating_sub(stage.start)),
+            )?;
+        }
+        let total = self.stages.last().map(|stage| stage.end);
+        writeln!(
+            f,
+            "{:<14} {:>10} {:>10}",
+            "total",
+            "",
+            ms(total.unwrap_or_default())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages_are_tabulated() {
+        let stage = |name, start, end| Stage {
+            name,
+            start: Duration::from_millis(start),
+            end: Duration::from_millis(end),
+        };
+        let timeline = Timeline {
+            origin: Instant::now(),
+            stages: vec![
+                stage("config parse", 0, 2),
+                stage("tool call", 5, 805),
+                stage("first token", 805, 1200),
+            ],
+        };
+        assert_eq!(
+            timeline.to_string(),
+            "stage                  at       took
+config parse        0.0ms      2.0ms
+tool call           5.0ms    800.0ms
+first token       805.0ms    395.0ms
+total                       1200.0ms
+"
+        );
+    }
+
+    #[test]
+    fn marks_continue_from_the_previous_stage() {
+        let timings = Timings::enabled();
+        timings.record("config parse", Instant::now());
+        timings.mark("stream start");
+        let timeline = timings.0.as_ref().unwrap().lock().unwrap();
+        assert_eq!(timeline.stages[1].start, timeline.stages[0].end);
+
+        assert!(Timings::default().0.is_none());
+    }
+}
@@ -0,0 +1,238 @@
+use serde_json::Value;
+
+/// Arguments the model passed that don't match the tool's schema. Returned from
+/// [`Tool::run`](ollama_rs::generation::functions::tools::Tool::run), it is shown to the
+/// model as the tool's output instead of failing the call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgError(pub String);
+
+impl std::fmt::Display for ArgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ArgError {}
+
+/// Checks `input` against the `parameters()` schema of a tool: the required fields,
+/// the type of every declared property and `enum` values.
+pub fn validate(schema: &Value, input: &Value) -> Result<(), ArgError> {
+    let empty = serde_json::Map::new();
+    let fields = match input {
+        Value::Object(fields) => fields,
+        Value::Null => &empty,
+        other => {
+            return Err(ArgError(format!(
+                "arguments must be an object, got {}",
+                type_name(other)
+            )))
+        }
+    };
+
+    for name in schema["required"].as_array().into_iter().flatten() {
+        let Some(name) = name.as_str() else { continue };
+        if fields.get(name).is_none_or(Value::is_null) {
+            return Err(ArgError(format!("missing '{name}' argument")));
+        }
+    }
+
+    let Some(properties) = schema["properties"].as_object() else {
+        return Ok(());
+    };
+    for (name, value) in fields {
+        if let Some(property) = properties.get(name).filter(|_| !value.is_null()) {
+            check_property(name, property, value)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_property(name: &str, property: &Value, value: &Value) -> Result<(), ArgError> {
+    if let Some(expected) = property["type"].as_str() {
+        if !has_type(value, expected) {
+            return Err(ArgError(format!(
+                "'{name}' must be {}, got {}",
+                article(expected),
+                type_name(value)
+            )));
+        }
+    }
+    if let Some(allowed) = property["enum"].as_array() {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            return Err(ArgError(format!(
+                "'{name}' must be one of {}, got {value}",
+                allowed.join(", ")
+            )));
+        }
+    }
+    if let (Some(items), Some(item_type)) = (value.as_array(), property["items"]["type"].as_str()) {
+        if let Some(item) = items.iter().find(|item| !has_type(item, item_type)) {
+            return Err(ArgError(format!(
+                "'{name}' must only hold {item_type} items, got {}",
+                type_name(item)
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn article(type_name: &str) -> String {
+    match type_name {
+        "integer" | "array" | "object" => format!("an {type_name}"),
+        _ => format!("a {type_name}"),
+    }
+}
+
+/// Typed access to the arguments of a tool call. Missing or mistyped arguments are
+/// reported as an [`ArgError`], so `run` can use `?` on them.
+#[derive(Debug, Clone, Copy)]
+pub struct Args<'a>(&'a Value);
+
+impl<'a> Args<'a> {
+    pub fn new(input: &'a Value) -> Self {
+        Self(input)
+    }
+
+    fn field(&self, name: &str) -> Option<&'a Value> {
+        self.0.get(name).filter(|value| !value.is_null())
+    }
+
+    /// A required string argument, blank strings count as missing.
+    pub fn get_str(&self, name: &str) -> Result<&'a str, ArgError> {
+        self.opt_str(name)?
+            .filter(|value| !value.trim().is_empty())
+            .ok_or_else(|| ArgError(format!("missing '{name}' argument")))
+    }
+
+    pub fn opt_str(&self, name: &str) -> Result<Option<&'a str>, ArgError> {
+        self.typed(name, "string", Value::as_str)
+    }
+
+    pub fn opt_u64(&self, name: &str) -> Result<Option<u64>, ArgError> {
+        self.typed(name, "integer", Value::as_u64)
+    }
+
+    pub fn opt_f64(&self, name: &str) -> Result<Option<f64>, ArgError> {
+        self.typed(name, "number", Value::as_f64)
+    }
+
+    pub fn opt_bool(&self, name: &str) -> Result<Option<bool>, ArgError> {
+        self.typed(name, "boolean", Value::as_bool)
+    }
+
+    pub fn opt_str_list(&self, name: &str) -> Result<Option<Vec<&'a str>>, ArgError> {
+        self.typed(name, "array", |value| {
+            value.as_array()?.iter().map(Value::as_str).collect()
+        })
+    }
+
+    fn typed<T>(
+        &self,
+        name: &str,
+        expected: &str,
+        convert: impl FnOnce(&'a Value) -> Option<T>,
+    ) -> Result<Option<T>, ArgError> {
+        let Some(value) = self.field(name) else {
+            return Ok(None);
+        };
+        match convert(value) {
+            Some(converted) => Ok(Some(converted)),
+            None => Err(ArgError(format!(
+                "'{name}' must be {}, got {value}",
+                article(expected)
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string"},
+                "count": {"type": "integer"},
+                "mode": {"type": "string", "enum": ["fast", "slow"]},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["path"]
+        })
+    }
+
+    #[test]
+    fn validate_reports_missing_and_mistyped_arguments() {
+        let error = |input: Value| validate(&schema(), &input).unwrap_err().to_string();
+
+        assert!(validate(&schema(), &json!({"path": "a", "count": 3, "tags": ["x"]})).is_ok());
+        assert_eq!(error(json!({})), "missing 'path' argument");
+        assert_eq!(error(json!({"path": null})), "missing 'path' argument");
+        assert_eq!(
+            error(json!({"path": 1})),
+            "'path' must be a string, got integer"
+        );
+        assert_eq!(
+            error(json!({"path": "a", "count": "3"})),
+            "'count' must be an integer, got string"
+        );
+        assert_eq!(
+            error(json!({"path": "a", "mode": "medium"})),
+            "'mode' must be one of \"fast\", \"slow\", got \"medium\""
+        );
+        assert_eq!(
+            error(json!({"path": "a", "tags": ["x", 2]})),
+            "'tags' must only hold string items, got integer"
+        );
+        assert_eq!(error(json!("a")), "arguments must be an object, got string");
+    }
+
+    #[test]
+    fn accessors_return_typed_values() {
+        let input = json!({"path": "a", "count": 3, "check": true, "tags": ["x", "y"]});
+        let args = Args::new(&input);
+
+        assert_eq!(args.get_str("path"), Ok("a"));
+        assert_eq!(args.opt_u64("count"), Ok(Some(3)));
+        assert_eq!(args.opt_bool("check"), Ok(Some(true)));
+        assert_eq!(args.opt_str_list("tags"), Ok(Some(vec!["x", "y"])));
+        assert_eq!(args.opt_str("missing"), Ok(None));
+        assert!(Args::new(&json!({"path": " "})).get_str("path").is_err());
+        assert_eq!(
+            args.get_str("missing"),
+            Err(ArgError("missing 'missing' argument".to_string()))
+        );
+        assert_eq!(
+            args.get_str("count"),
+            Err(ArgError("'count' must be a string, got 3".to_string()))
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde_json::{json, Value};
+
+use super::args::Args;
+
+#[derive(Default)]
+pub struct Calculator {}
+
+/// Operands nested deeper than this are refused, before the recursion overflows the stack.
+const MAX_DEPTH: usize = 128;
+
+/// Recursive descent evaluator for arithmetic expressions.
+///
+/// ```text
+/// expr    := term (('+' | '-') term)*
+/// term    := unary (('*' | '/' | '%') unary)*
+/// unary   := ('+' | '-') unary | power
+/// power   := primary ('^' unary)?
+/// primary := number | name | name '(' expr (',' expr)* ')' | '(' expr ')'
+/// ```
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    fn evaluate(mut self) -> Result<f64, String> {
+        let value = self.expr()?;
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(value),
+            Some(c) => Err(format!("unexpected '{c}' at position {}", self.pos)),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err("division by zero".to_string());
+                }
+                value /= divisor;
+            } else if self.eat('%') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err("modulo by zero".to_string());
+                }
+                value %= divisor;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Every nested operand is parsed through here, which is where the depth is limited.
+    fn unary(&mut self) -> Result<f64, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!(
+                "expression nested too deeply at position {}",
+                self.pos
+            ));
+        }
+        self.depth += 1;
+        let value = self.signed();
+        self.depth -= 1;
+        value
+    }
+
+    fn signed(&mut self) -> Result<f64, String> {
+        if self.eat('-') {
+            return Ok(-self.unary()?);
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<f64, String> {
+        let base = self.primary()?;
+        if self.eat('^') {
+            return Ok(base.powf(self.unary()?));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<f64, String> {
+        self.skip_whitespace();
+        if self.eat('(') {
+            let value = self.expr()?;
+            if !self.eat(')') {
+                return Err(format!("missing ')' at position {}", self.pos));
+            }
+            return Ok(value);
+        }
+
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => self.name(),
+            Some(c) => Err(format!("unexpected '{c}' at position {}", self.pos)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        let literal = &self.input[start..self.pos];
+        literal
+            .parse()
+            .map_err(|_| format!("invalid number '{literal}'"))
+    }
+
+    fn name(&mut self) -> Result<f64, String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.pos += 1;
+        }
+        let name = &self.input[start..self.pos];
+
+        if !self.eat('(') {
+            return match name {
+                "pi" => Ok(std::f64::consts::PI),
+                "e" => Ok(std::f64::consts::E),
+                _ => Err(format!("unknown constant '{name}'")),
+            };
+        }
+
+        let mut args = vec![self.expr()?];
+        while self.eat(',') {
+            args.push(self.expr()?);
+        }
+        if !self.eat(')') {
+            return Err(format!("missing ')' after arguments of {name}"));
+        }
+
+        call(name, &args)
+    }
+}
+
+fn call(name: &str, args: &[f64]) -> Result<f64, String> {
+    let value = match (name, args) {
+        ("sqrt", [x]) if *x < 0.0 => return Err("sqrt of a negative number".to_string()),
+        ("sqrt", [x]) => x.sqrt(),
+        ("sin", [x]) => x.sin(),
+        ("cos", [x]) => x.cos(),
+        ("tan", [x]) => x.tan(),
+        ("abs", [x]) => x.abs(),
+        ("exp", [x]) => x.exp(),
+        ("floor", [x]) => x.floor(),
+        ("ceil", [x]) => x.ceil(),
+        ("round", [x]) => x.round(),
+        ("ln", [x]) if *x <= 0.0 => return Err("ln of a non-positive number".to_string()),
+        ("ln", [x]) => x.ln(),
+        ("log", [x]) if *x <= 0.0 => return Err("log of a non-positive number".to_string()),
+        ("log", [x]) => x.log10(),
+        ("log", [x, base]) => x.log(*base),
+        ("pow", [x, y]) => x.powf(*y),
+        ("min", [x, y]) => x.min(*y),
+        ("max", [x, y]) => x.max(*y),
+        _ => {
+            return Err(format!(
+                "unknown function {name} with {} argument(s)",
+                args.len()
+            ))
+        }
+    };
+    Ok(value)
+}
+
+#[async_trait]
+impl Tool for Calculator {
+    fn name(&self) -> String {
+        "calculator".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Evaluate an arithmetic expression exactly instead of guessing the result".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "expression": {
+                    "type": "string",
+                    "description": "Expression using + - * / % ^, parentheses, pi, e and functions like sqrt, sin, cos, tan, log, ln, exp, pow, min, max, abs"
+                }
+            },
+            "required": ["expression"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let expression = Args::new(&input).get_str("expression")?;
+
+        let response = match Parser::new(expression).evaluate() {
+            Ok(result) if result.is_finite() => json!({"expression": expression, "result": result}),
+            Ok(_) => json!({"error": format!("'{expression}' does not have a finite result")}),
+            Err(error) => json!({"error": format!("cannot evaluate '{expression}': {error}")}),
+        };
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expression: &str) -> Result<f64, String> {
+        Parser::new(expression).evaluate()
+    }
+
+    #[test]
+    fn operators_follow_precedence() {
+        assert_eq!(eval("2 + 3 * 4"), Ok(14.0));
+        assert_eq!(eval("(2 + 3) * 4"), Ok(20.0));
+        assert_eq!(eval("2 ^ 3 ^ 2"), Ok(512.0));
+        assert_eq!(eval("-2 ^ 2"), Ok(-4.0));
+        assert_eq!(eval("10 - 4 - 3"), Ok(3.0));
+        assert_eq!(eval("7 % 4 * 2"), Ok(6.0));
+    }
+
+    #[test]
+    fn functions_and_constants_are_evaluated() {
+        assert_eq!(eval("sqrt(16) + abs(-2)"), Ok(6.0));
+        assert_eq!(eval("max(1, pow(2, 3))"), Ok(8.0));
+        assert_eq!(eval("log(100)"), Ok(2.0));
+        assert_eq!(eval("round(pi)"), Ok(3.0));
+        assert!(eval("sqrt(-1)").unwrap_err().contains("negative"));
+        assert!(eval("foo(1)").unwrap_err().contains("unknown function"));
+    }
+
+    #[test]
+    fn division_by_zero_is_an_error() {
+        assert_eq!(eval("1 / 0"), Err("division by zero".to_string()));
+        assert_eq!(eval("5 % (2 - 2)"), Err("modulo by zero".to_string()));
+    }
+
+    #[test]
+    fn malformed_expressions_are_errors() {
+        assert!(eval("1 +").unwrap_err().contains("unexpected end"));
+        assert!(eval("(1 + 2").unwrap_err().contains("missing ')'"));
+        assert!(eval("1.2.3").unwrap_err().contains("invalid number"));
+        assert!(eval("2 $ 3").unwrap_err().contains("unexpected '$'"));
+        assert!(eval("é").is_err());
+    }
+
+    #[test]
+    fn unicode_whitespace_is_skipped() {
+        assert_eq!(eval("1\u{a0}+ 1"), Ok(2.0));
+        assert_eq!(eval("\u{3000}3 *\u{3000}2"), Ok(6.0));
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let signs = "-".repeat(100_000) + "1";
+        let parens = "(".repeat(100_000) + "1" + &")".repeat(100_000);
+        let powers = vec!["2"; 100_000].join("^");
+        for expression in [signs, parens, powers] {
+            let error = eval(&expression).unwrap_err();
+            assert!(error.contains("nested too deeply"), "{error}");
+        }
+
+        let nested = "(".repeat(60) + "-1" + &")".repeat(60);
+        assert_eq!(eval(&nested), Ok(-1.0));
+    }
+}
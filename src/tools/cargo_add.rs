@@ -0,0 +1,132 @@
+use std::{error::Error, path::Path};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use tokio::process::Command;
+
+#[derive(Default)]
+pub struct CargoAdd {}
+
+/// Crate names, versions and features never start with `-`, so none of them can
+/// be smuggled in as an extra cargo flag.
+fn is_valid(value: &str) -> bool {
+    !value.is_empty()
+        && !value.starts_with('-')
+        && value.chars().all(|c| {
+            c.is_ascii_alphanumeric()
+                || matches!(
+                    c,
+                    '-' | '_' | '.' | '+' | '/' | '^' | '~' | '=' | '<' | '>' | '*'
+                )
+        })
+}
+
+/// Finds the `name = ...` line cargo wrote for the crate.
+fn dependency_line(manifest: &str, name: &str) -> Option<String> {
+    manifest
+        .lines()
+        .find(|line| {
+            line.split_once('=')
+                .is_some_and(|(key, _)| key.trim() == name)
+        })
+        .map(|line| line.trim().to_string())
+}
+
+#[async_trait]
+impl Tool for CargoAdd {
+    fn name(&self) -> String {
+        "cargo_add".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Add a dependency to the current Rust project with cargo add".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "crate_name": {
+                    "type": "string",
+                    "description": "Name of the crate to add, e.g. 'tokio'"
+                },
+                "version": {
+                    "type": "string",
+                    "description": "Version requirement, defaults to the latest release"
+                },
+                "features": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Features to enable, e.g. ['full']"
+                }
+            },
+            "required": ["crate_name"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let name = args.get_str("crate_name")?.trim();
+        let version = args
+            .opt_str("version")?
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        let features = args.opt_str_list("features")?.unwrap_or_default();
+
+        if !is_valid(name) || !version.is_none_or(is_valid) || !features.iter().all(|f| is_valid(f))
+        {
+            return Ok(json!({"error": "invalid crate name, version or feature"}).to_string());
+        }
+        if !Path::new("Cargo.toml").is_file() {
+            let error = "no Cargo.toml in the current directory, run shark from a cargo project";
+            return Ok(json!({ "error": error }).to_string());
+        }
+
+        let spec = match version {
+            Some(version) => format!("{name}@{version}"),
+            None => name.to_string(),
+        };
+        let mut command = Command::new("cargo");
+        command.arg("add").arg(&spec);
+        if !features.is_empty() {
+            command.arg("--features").arg(features.join(","));
+        }
+
+        tracing::debug!(spec, ?features, "running cargo add");
+        let output = command.output().await?;
+        let manifest = tokio::fs::read_to_string("Cargo.toml").await?;
+
+        Ok(json!({
+            "success": output.status.success(),
+            "dependency": dependency_line(&manifest, name),
+            "stdout": String::from_utf8_lossy(&output.stdout),
+            "stderr": String::from_utf8_lossy(&output.stderr),
+        })
+        .to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_flags_passed_as_crate_names() {
+        assert!(is_valid("tokio"));
+        assert!(is_valid("^1.42"));
+        assert!(!is_valid("--path"));
+        assert!(!is_valid("serde json"));
+    }
+
+    #[test]
+    fn dependency_line_finds_the_added_crate() {
+        let manifest = "[dependencies]\nserde_json = \"1\"\nserde = { version = \"1\", features = [\"derive\"] }\n";
+        assert_eq!(
+            dependency_line(manifest, "serde").as_deref(),
+            Some("serde = { version = \"1\", features = [\"derive\"] }")
+        );
+    }
+}
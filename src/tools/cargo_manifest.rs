@@ -0,0 +1,282 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde_json::{json, Map, Value};
+use toml::Table;
+
+use super::args::Args;
+use super::file_reader::resolve_within;
+
+const DEPENDENCY_KINDS: [(&str, &str); 3] = [
+    ("dependencies", "normal"),
+    ("dev-dependencies", "dev"),
+    ("build-dependencies", "build"),
+];
+
+#[derive(Default)]
+pub struct CargoManifest {}
+
+/// A TOML value as JSON, e.g. `version.workspace = true` becomes `{"workspace": true}`.
+fn to_json(value: &toml::Value) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// One dependency, either a plain version requirement or a table of details. Only the
+/// details the manifest sets are included.
+fn dependency(name: &str, spec: &toml::Value, kind: &str, target: Option<&str>) -> Value {
+    let mut entry = Map::new();
+    entry.insert("name".to_string(), json!(name));
+    entry.insert("kind".to_string(), json!(kind));
+    if let Some(target) = target {
+        entry.insert("target".to_string(), json!(target));
+    }
+    match spec {
+        toml::Value::String(version) => {
+            entry.insert("version".to_string(), json!(version));
+        }
+        toml::Value::Table(table) => {
+            for (key, field) in [
+                ("version", "version"),
+                ("package", "package"),
+                ("path", "path"),
+                ("git", "git"),
+                ("branch", "branch"),
+                ("tag", "tag"),
+                ("rev", "rev"),
+                ("features", "features"),
+                ("optional", "optional"),
+                ("default-features", "default_features"),
+                ("workspace", "workspace"),
+            ] {
+                if let Some(value) = table.get(key) {
+                    entry.insert(field.to_string(), to_json(value));
+                }
+            }
+        }
+        _ => {}
+    }
+    Value::Object(entry)
+}
+
+/// The dependencies of every kind in `table`, including the target specific ones.
+fn dependencies(table: &Table) -> Vec<Value> {
+    let mut list = Vec::new();
+    let mut add = |table: &Table, target: Option<&str>| {
+        for (section, kind) in DEPENDENCY_KINDS {
+            if let Some(deps) = table.get(section).and_then(toml::Value::as_table) {
+                list.extend(
+                    deps.iter()
+                        .map(|(name, spec)| dependency(name, spec, kind, target)),
+                );
+            }
+        }
+    };
+    add(table, None);
+    if let Some(targets) = table.get("target").and_then(toml::Value::as_table) {
+        for (target, table) in targets {
+            if let Some(table) = table.as_table() {
+                add(table, Some(target));
+            }
+        }
+    }
+    list
+}
+
+fn package(manifest: &Table) -> Option<Value> {
+    let package = manifest.get("package")?.as_table()?;
+    let field = |key| package.get(key).map(to_json);
+    Some(json!({
+        "name": field("name"),
+        "version": field("version"),
+        "edition": field("edition"),
+        "rust_version": field("rust-version"),
+    }))
+}
+
+fn parse(source: &str) -> Result<Table, String> {
+    source
+        .parse()
+        .map_err(|e: toml::de::Error| format!("malformed manifest: {}", e.message()))
+}
+
+/// Member directories of a workspace rooted at `root`. Globs are only expanded at the end
+/// of a pattern, e.g. `crates/*`, which is how workspaces nearly always list them.
+fn member_dirs(root: &Path, members: &[&str], exclude: &[&str]) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for member in members {
+        match member.strip_suffix("/*") {
+            Some(parent) => {
+                let Ok(entries) = std::fs::read_dir(root.join(parent)) else {
+                    continue;
+                };
+                let mut found: Vec<PathBuf> = entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| path.join("Cargo.toml").is_file())
+                    .collect();
+                found.sort();
+                dirs.extend(found);
+            }
+            None => dirs.push(root.join(member)),
+        }
+    }
+    dirs.retain(|dir| !exclude.iter().any(|excluded| *dir == root.join(excluded)));
+    dirs
+}
+
+/// A workspace member's name and version, read from its own manifest.
+fn member(root: &Path, dir: &Path) -> Value {
+    let path = dir.strip_prefix(root).unwrap_or(dir).display().to_string();
+    let manifest = std::fs::read_to_string(dir.join("Cargo.toml"))
+        .map_err(|e| format!("cannot read its Cargo.toml: {e}"))
+        .and_then(|source| parse(&source));
+    match manifest {
+        Ok(manifest) => {
+            let package = package(&manifest).unwrap_or(Value::Null);
+            json!({
+                "path": path,
+                "name": package["name"],
+                "version": package["version"],
+            })
+        }
+        Err(error) => json!({
+            "path": path,
+            "error": error,
+        }),
+    }
+}
+
+fn inspect(manifest_path: &Path, source: &str) -> Result<Value, String> {
+    let manifest = parse(source)?;
+    let mut summary = json!({
+        "package": package(&manifest),
+        "dependencies": dependencies(&manifest),
+    });
+
+    if let Some(workspace) = manifest.get("workspace").and_then(toml::Value::as_table) {
+        let strings = |key| -> Vec<&str> {
+            workspace
+                .get(key)
+                .and_then(toml::Value::as_array)
+                .map(|list| list.iter().filter_map(toml::Value::as_str).collect())
+                .unwrap_or_default()
+        };
+        let root = manifest_path.parent().unwrap_or(Path::new("."));
+        let members: Vec<Value> = member_dirs(root, &strings("members"), &strings("exclude"))
+            .iter()
+            .map(|dir| member(root, dir))
+            .collect();
+        let shared: Vec<Value> = workspace
+            .get("dependencies")
+            .and_then(toml::Value::as_table)
+            .map(|deps| {
+                deps.iter()
+                    .map(|(name, spec)| dependency(name, spec, "workspace", None))
+                    .collect()
+            })
+            .unwrap_or_default();
+        summary["workspace"] = json!({
+            "members": members,
+            "dependencies": shared,
+        });
+    }
+    Ok(summary)
+}
+
+#[async_trait]
+impl Tool for CargoManifest {
+    fn name(&self) -> String {
+        "cargo_manifest".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Read a Rust project's Cargo.toml: its package, edition, dependencies with their versions and features, and workspace members"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the manifest or its directory, ./Cargo.toml by default"
+                }
+            }
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let path = Args::new(&input)
+            .opt_str("path")?
+            .unwrap_or("Cargo.toml")
+            .to_string();
+
+        let response = tokio::task::spawn_blocking(move || {
+            let mut resolved = resolve_within(Path::new("."), &path)?;
+            if resolved.is_dir() {
+                resolved.push("Cargo.toml");
+            }
+            let source = std::fs::read_to_string(&resolved)
+                .map_err(|e| format!("cannot read {}: {e}", resolved.display()))?;
+            inspect(&resolved, &source)
+        })
+        .await?
+        .unwrap_or_else(|error| json!({ "error": error }));
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dependencies_keep_versions_and_features() {
+        let summary = inspect(
+            Path::new("Cargo.toml"),
+            r#"
+            [package]
+            name = "shark"
+            version = "0.1.0"
+            edition = "2021"
+            rust-version = "1.80"
+
+            [dependencies]
+            regex = "1"
+            tokio = { version = "1.42.0", features = ["full"], optional = true }
+
+            [target.'cfg(unix)'.dev-dependencies]
+            nix = { workspace = true }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(summary["package"]["name"], "shark");
+        assert_eq!(summary["package"]["rust_version"], "1.80");
+        assert_eq!(
+            summary["dependencies"],
+            json!([
+                {"name": "regex", "kind": "normal", "version": "1"},
+                {
+                    "name": "tokio",
+                    "kind": "normal",
+                    "version": "1.42.0",
+                    "features": ["full"],
+                    "optional": true
+                },
+                {"name": "nix", "kind": "dev", "target": "cfg(unix)", "workspace": true}
+            ])
+        );
+        assert!(summary.get("workspace").is_none());
+    }
+
+    #[test]
+    fn malformed_manifests_are_an_error() {
+        let error = inspect(Path::new("Cargo.toml"), "[package\nname = 1").unwrap_err();
+        assert!(error.starts_with("malformed manifest"));
+    }
+}
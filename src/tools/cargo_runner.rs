@@ -0,0 +1,201 @@
+use std::{error::Error, time::Duration};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use tokio::process::Command;
+
+const SUBCOMMANDS: [&str; 4] = ["build", "test", "check", "clippy"];
+
+/// Flags that would let the model run arbitrary commands, e.g. through a `runner` set with
+/// `--config`, or point cargo at another project.
+const FORBIDDEN_FLAGS: [&str; 5] = ["--config", "-Z", "--manifest-path", "-C", "--target-dir"];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CargoRunnerConfig {
+    pub timeout_secs: u64,
+}
+
+impl Default for CargoRunnerConfig {
+    fn default() -> Self {
+        Self { timeout_secs: 600 }
+    }
+}
+
+#[derive(Default)]
+pub struct CargoRunner {
+    config: CargoRunnerConfig,
+}
+
+impl CargoRunner {
+    pub fn new(config: CargoRunnerConfig) -> Self {
+        Self { config }
+    }
+}
+
+/// The first forbidden flag in `args`, in any of its spellings like `--config=...` or
+/// `-Zflag`. Arguments after `--` go to the test binaries, not to cargo.
+pub fn forbidden_flag(args: &[&str]) -> Option<String> {
+    args.iter()
+        .take_while(|arg| **arg != "--")
+        .find(|arg| {
+            FORBIDDEN_FLAGS.iter().any(|flag| {
+                let Some(rest) = arg.strip_prefix(flag) else {
+                    return false;
+                };
+                // Short flags take their value attached, long ones after `=`.
+                !flag.starts_with("--") || rest.is_empty() || rest.starts_with('=')
+            })
+        })
+        .map(|arg| arg.to_string())
+}
+
+/// Reads the `test result: ok. 3 passed; 0 failed; ...` summaries of cargo test output,
+/// one per test binary, into their counts.
+fn parse_test_results(output: &str) -> Vec<Value> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("test result: "))
+        .map(|summary| {
+            let (status, counts) = summary.split_once(". ").unwrap_or((summary, ""));
+            let mut result = json!({ "ok": status == "ok" });
+            for part in counts.split("; ") {
+                let Some((count, name)) = part.split_once(' ') else {
+                    continue;
+                };
+                if let Ok(count) = count.parse::<u64>() {
+                    result[name.replace(' ', "_")] = json!(count);
+                }
+            }
+            result
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Tool for CargoRunner {
+    fn name(&self) -> String {
+        "cargo_runner".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Run cargo build, test, check or clippy in the current Rust project".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "subcommand": {
+                    "type": "string",
+                    "enum": SUBCOMMANDS,
+                    "description": "The cargo subcommand to run"
+                },
+                "args": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Extra arguments passed to cargo, e.g. ['--release']"
+                }
+            },
+            "required": ["subcommand"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let subcommand = args.get_str("subcommand")?;
+        if !SUBCOMMANDS.contains(&subcommand) {
+            let error = format!(
+                "unsupported subcommand '{subcommand}', expected one of: {}",
+                SUBCOMMANDS.join(", ")
+            );
+            return Ok(json!({ "error": error }).to_string());
+        }
+
+        let args = args.opt_str_list("args")?.unwrap_or_default();
+        if let Some(flag) = forbidden_flag(&args) {
+            let error = format!("'{flag}' can't be passed to cargo");
+            return Ok(json!({ "error": error }).to_string());
+        }
+
+        tracing::debug!(subcommand, ?args, "running cargo");
+        let child = Command::new("cargo")
+            .arg(subcommand)
+            .args(&args)
+            .kill_on_drop(true)
+            .output();
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let output = match tokio::time::timeout(timeout, child).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => {
+                let error = format!("failed to run cargo {subcommand}: {e}");
+                return Ok(json!({ "error": error }).to_string());
+            }
+            Err(_) => {
+                let error = format!("cargo {subcommand} timed out after {}s", timeout.as_secs());
+                return Ok(json!({ "error": error }).to_string());
+            }
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut response = json!({
+            "stdout": stdout,
+            "stderr": stderr,
+            "success": output.status.success(),
+        });
+        if subcommand == "test" {
+            response["test_results"] = json!(parse_test_results(&stdout));
+        }
+
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_running_other_commands_are_rejected() {
+        assert_eq!(forbidden_flag(&["--release", "--", "--config"]), None);
+        assert_eq!(
+            forbidden_flag(&["--config", "target.x.runner='sh'"]),
+            Some("--config".to_string())
+        );
+        assert_eq!(
+            forbidden_flag(&["--config=build.rustc='sh'"]),
+            Some("--config=build.rustc='sh'".to_string())
+        );
+        assert_eq!(
+            forbidden_flag(&["-Zunstable-options"]),
+            Some("-Zunstable-options".to_string())
+        );
+        assert_eq!(
+            forbidden_flag(&["--manifest-path", "../other/Cargo.toml"]),
+            Some("--manifest-path".to_string())
+        );
+        assert_eq!(forbidden_flag(&["--configure-me"]), None);
+    }
+
+    #[test]
+    fn test_summaries_are_counted() {
+        let output = "\
+running 2 tests
+test result: ok. 2 passed; 0 failed; 1 ignored; 0 measured; 3 filtered out; finished in 0.01s
+
+test result: FAILED. 4 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.20s
+";
+        assert_eq!(
+            parse_test_results(output),
+            vec![
+                json!({"ok": true, "passed": 2, "failed": 0, "ignored": 1, "measured": 0, "filtered_out": 3}),
+                json!({"ok": false, "passed": 4, "failed": 1, "ignored": 0, "measured": 0, "filtered_out": 0}),
+            ]
+        );
+    }
+}
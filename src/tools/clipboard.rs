@@ -0,0 +1,155 @@
+use std::error::Error;
+
+use arboard::Clipboard as SystemClipboard;
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use super::http_fetch::truncate_chars;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ClipboardConfig {
+    /// Text read from the clipboard is cut down to this many characters.
+    pub max_chars: usize,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self { max_chars: 8000 }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Operation {
+    Read,
+    Write(String),
+}
+
+impl Operation {
+    fn parse(args: &Args) -> Result<Self, String> {
+        let operation = args.get_str("operation").map_err(|e| e.to_string())?;
+        let text = args.opt_str("text").map_err(|e| e.to_string())?;
+        match (operation, text) {
+            ("read", _) => Ok(Self::Read),
+            ("write", Some(text)) => Ok(Self::Write(text.to_string())),
+            ("write", None) => Err("writing to the clipboard needs the text to write".to_string()),
+            (operation, _) => Err(format!(
+                "unknown operation '{operation}', use 'read' or 'write'"
+            )),
+        }
+    }
+}
+
+fn describe_error(e: arboard::Error) -> String {
+    match e {
+        arboard::Error::ContentNotAvailable => {
+            "the clipboard is empty or holds no text".to_string()
+        }
+        arboard::Error::ClipboardOccupied => {
+            "the clipboard is in use by another program, try again".to_string()
+        }
+        e => format!("failed to use the clipboard: {e}"),
+    }
+}
+
+/// Runs `operation` on the system clipboard. Blocks, so it goes through `spawn_blocking`.
+fn run_operation(operation: Operation, max_chars: usize) -> Result<Value, String> {
+    // Without a display server, e.g. over ssh or in a container, there is no clipboard.
+    let mut clipboard = SystemClipboard::new()
+        .map_err(|e| format!("no clipboard is available, e.g. because there is no display: {e}"))?;
+    match operation {
+        Operation::Read => {
+            let text = clipboard.get_text().map_err(describe_error)?;
+            let (content, truncated) = truncate_chars(&text, max_chars);
+            Ok(json!({
+                "text": content,
+                "truncated": truncated,
+            }))
+        }
+        Operation::Write(text) => {
+            let chars = text.chars().count();
+            clipboard.set_text(text).map_err(describe_error)?;
+            Ok(json!({
+                "written": true,
+                "chars": chars,
+            }))
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Clipboard {
+    config: ClipboardConfig,
+}
+
+impl Clipboard {
+    pub fn new(config: ClipboardConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Tool for Clipboard {
+    fn name(&self) -> String {
+        "clipboard".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Read the text on the user's clipboard, e.g. a copied error message, or put text on it"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["read", "write"],
+                    "description": "'read' to get the clipboard text, 'write' to replace it"
+                },
+                "text": {
+                    "type": "string",
+                    "description": "The text to put on the clipboard, for 'write'"
+                }
+            },
+            "required": ["operation"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let operation = match Operation::parse(&Args::new(&input)) {
+            Ok(operation) => operation,
+            Err(error) => return Ok(json!({ "error": error }).to_string()),
+        };
+        let max_chars = self.config.max_chars;
+        let response = tokio::task::spawn_blocking(move || run_operation(operation, max_chars))
+            .await?
+            .unwrap_or_else(|error| json!({ "error": error }));
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operations_are_parsed() {
+        let parse = |input: Value| Operation::parse(&Args::new(&input));
+        assert_eq!(parse(json!({"operation": "read"})), Ok(Operation::Read));
+        assert_eq!(
+            parse(json!({"operation": "write", "text": "cargo build"})),
+            Ok(Operation::Write("cargo build".to_string()))
+        );
+        assert!(parse(json!({"operation": "write"}))
+            .unwrap_err()
+            .contains("needs the text"));
+        assert!(parse(json!({"operation": "paste"}))
+            .unwrap_err()
+            .contains("unknown operation"));
+    }
+}
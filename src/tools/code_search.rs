@@ -0,0 +1,206 @@
+use std::{error::Error, path::PathBuf};
+
+use async_trait::async_trait;
+use ignore::{overrides::OverrideBuilder, WalkBuilder};
+use ollama_rs::generation::functions::tools::Tool;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use super::file_reader::resolve_within;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CodeSearchConfig {
+    /// Only files inside this directory are searched.
+    pub root: PathBuf,
+    pub max_results: usize,
+    /// Lines shown before and after every match.
+    pub context_lines: usize,
+    /// Also search files ignored by `.gitignore`.
+    pub include_ignored: bool,
+}
+
+impl Default for CodeSearchConfig {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("."),
+            max_results: 50,
+            context_lines: 2,
+            include_ignored: false,
+        }
+    }
+}
+
+pub struct CodeSearch {
+    config: CodeSearchConfig,
+}
+
+impl CodeSearch {
+    pub fn new(config: CodeSearchConfig) -> Self {
+        Self { config }
+    }
+
+    /// Walks `path` and collects matches of `pattern`, stopping at `max_results`.
+    /// Returns the matches and whether the search was cut short.
+    fn search(
+        &self,
+        pattern: &Regex,
+        path: &str,
+        glob: Option<&str>,
+    ) -> Result<(Vec<Value>, bool), String> {
+        let root = resolve_within(&self.config.root, ".")?;
+        let start = resolve_within(&self.config.root, path)?;
+
+        let mut walker = WalkBuilder::new(&start);
+        walker
+            .git_ignore(!self.config.include_ignored)
+            .require_git(false);
+        if let Some(glob) = glob {
+            let overrides = OverrideBuilder::new(&start)
+                .add(glob)
+                .and_then(|builder| builder.build())
+                .map_err(|e| format!("invalid glob '{glob}': {e}"))?;
+            walker.overrides(overrides);
+        }
+
+        let mut matches = Vec::new();
+        for entry in walker.build().filter_map(Result::ok) {
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            let Ok(bytes) = std::fs::read(entry.path()) else {
+                continue;
+            };
+            // Like git and grep, a NUL byte early on marks a binary file.
+            if bytes.iter().take(8000).any(|&b| b == 0) {
+                continue;
+            }
+            let content = String::from_utf8_lossy(&bytes);
+            let lines: Vec<&str> = content.lines().collect();
+            let file = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+
+            for (index, line) in lines.iter().enumerate() {
+                if !pattern.is_match(line) {
+                    continue;
+                }
+                if matches.len() == self.config.max_results {
+                    return Ok((matches, true));
+                }
+                let context = self.config.context_lines;
+                let first = index.saturating_sub(context);
+                let last = (index + context + 1).min(lines.len());
+                matches.push(json!({
+                    "path": file.display().to_string(),
+                    "line": index + 1,
+                    "text": line,
+                    "context": lines[first..last].join("\n"),
+                }));
+            }
+        }
+        Ok((matches, false))
+    }
+}
+
+#[async_trait]
+impl Tool for CodeSearch {
+    fn name(&self) -> String {
+        "code_search".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Search the files of the current project for lines matching a regex, like grep".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "pattern": {
+                    "type": "string",
+                    "description": "Regular expression to look for, e.g. 'fn parse_config'"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Directory or file to search, defaults to the whole project"
+                },
+                "glob": {
+                    "type": "string",
+                    "description": "Only search files matching this glob, e.g. '*.rs'"
+                }
+            },
+            "required": ["pattern"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let pattern = args.get_str("pattern")?;
+        let pattern = match Regex::new(pattern) {
+            Ok(pattern) => pattern,
+            Err(e) => return Ok(json!({"error": format!("invalid pattern: {e}")}).to_string()),
+        };
+        let path = args.opt_str("path")?.unwrap_or(".").to_string();
+        let glob = args.opt_str("glob")?.map(str::to_string);
+
+        let this = CodeSearch::new(self.config.clone());
+        let result =
+            tokio::task::spawn_blocking(move || this.search(&pattern, &path, glob.as_deref()))
+                .await?;
+
+        match result {
+            Ok((matches, truncated)) => Ok(json!({
+                "matches": matches,
+                "truncated": truncated,
+                "note": truncated.then(|| format!(
+                    "stopped after {} matches, narrow the pattern, path or glob to see the rest",
+                    self.config.max_results
+                )),
+            })
+            .to_string()),
+            Err(error) => Ok(json!({ "error": error }).to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_matches_with_context_and_caps_them() {
+        let root = std::env::temp_dir().join(format!("shark-search-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/a.rs"), "fn one() {}\nfn parse_config() {}\n").unwrap();
+        std::fs::write(root.join("src/b.txt"), "parse_config mentioned\n").unwrap();
+        std::fs::write(root.join("blob.bin"), b"parse_config\0\x01").unwrap();
+
+        let search = CodeSearch::new(CodeSearchConfig {
+            root: root.clone(),
+            context_lines: 1,
+            ..Default::default()
+        });
+        let pattern = Regex::new("parse_config").unwrap();
+
+        let (matches, truncated) = search.search(&pattern, ".", Some("*.rs")).unwrap();
+        assert!(!truncated);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0]["path"], "src/a.rs");
+        assert_eq!(matches[0]["line"], 2);
+        assert_eq!(matches[0]["context"], "fn one() {}\nfn parse_config() {}");
+
+        let (matches, _) = search.search(&pattern, ".", None).unwrap();
+        assert_eq!(matches.len(), 2, "the binary file is skipped");
+
+        let search = CodeSearch::new(CodeSearchConfig {
+            root: root.clone(),
+            max_results: 1,
+            ..Default::default()
+        });
+        let (matches, truncated) = search.search(&pattern, ".", None).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert!(truncated);
+    }
+}
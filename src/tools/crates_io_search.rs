@@ -0,0 +1,124 @@
+use std::{error::Error, time::Duration};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use super::http_fetch::USER_AGENT;
+
+pub const CRATES_API_URL: &str = "https://crates.io/api/v1/crates";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CratesIoSearchConfig {
+    pub max_results: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for CratesIoSearchConfig {
+    fn default() -> Self {
+        Self {
+            max_results: 5,
+            timeout_secs: 15,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    crates: Vec<Crate>,
+}
+
+#[derive(Deserialize)]
+struct Crate {
+    name: String,
+    max_stable_version: Option<String>,
+    max_version: String,
+    downloads: u64,
+    description: Option<String>,
+}
+
+pub struct CratesIoSearch {
+    client: reqwest::Client,
+    config: CratesIoSearchConfig,
+}
+
+impl CratesIoSearch {
+    pub fn new(config: CratesIoSearchConfig) -> Self {
+        let client = crates_io_client(Duration::from_secs(config.timeout_secs));
+        Self { client, config }
+    }
+}
+
+/// HTTP client for the crates.io API, which asks users to identify themselves
+/// with a way to reach the author.
+pub fn crates_io_client(timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent(format!("{USER_AGENT} (https://github.com/ZBcheng/shark)"))
+        .timeout(timeout)
+        .build()
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl Tool for CratesIoSearch {
+    fn name(&self) -> String {
+        "crates_io_search".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Search crates.io for Rust crates (packages, libraries) matching a task or keyword"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "What the crate should do or its name, e.g. 'async sqlite'"
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let query = Args::new(&input).get_str("query")?;
+
+        let per_page = self.config.max_results.to_string();
+        let url = Url::parse_with_params(CRATES_API_URL, [("q", query), ("per_page", &per_page)])?;
+        tracing::debug!(%url, "searching crates.io");
+        let response = match self.client.get(url).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                return Ok(json!({"error": format!("failed to search crates.io: {e}")}).to_string())
+            }
+        };
+        let status = response.status();
+        if !status.is_success() {
+            return Ok(json!({"error": format!("crates.io responded with {status}")}).to_string());
+        }
+
+        let found: SearchResponse = serde_json::from_str(&response.text().await?)?;
+        let crates: Vec<Value> = found
+            .crates
+            .into_iter()
+            .take(self.config.max_results)
+            .map(|krate| {
+                json!({
+                    "name": krate.name,
+                    "version": krate.max_stable_version.unwrap_or(krate.max_version),
+                    "downloads": krate.downloads,
+                    "description": krate.description.map(|d| d.trim().to_string()),
+                })
+            })
+            .collect();
+
+        Ok(json!({ "query": query, "crates": crates }).to_string())
+    }
+}
@@ -0,0 +1,93 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Local, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use ollama_rs::generation::functions::tools::Tool;
+use serde_json::{json, Value};
+
+use super::args::Args;
+
+#[derive(Default)]
+pub struct DateTimeTool {}
+
+/// Describes `now` as seen in `tz`.
+fn describe<T: TimeZone>(now: DateTime<Utc>, tz: &T) -> Value
+where
+    T::Offset: std::fmt::Display,
+{
+    let time = now.with_timezone(tz);
+    json!({
+        "time": time.to_rfc3339_opts(SecondsFormat::Secs, true),
+        "day_of_week": time.format("%A").to_string(),
+        "iso_week": time.iso_week().week(),
+    })
+}
+
+#[async_trait]
+impl Tool for DateTimeTool {
+    fn name(&self) -> String {
+        "datetime".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Get the current date, time, day of week and week number, optionally in a time zone"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "timezone": {
+                    "type": "string",
+                    "description": "IANA time zone to convert to, e.g. 'Europe/Berlin'"
+                }
+            }
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let now = Utc::now();
+        let mut output = json!({
+            "utc": now.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "local": describe(now, &Local),
+        });
+
+        let timezone = Args::new(&input).opt_str("timezone")?;
+        if let Some(name) = timezone.filter(|n| !n.trim().is_empty()) {
+            let Ok(tz) = name.trim().parse::<Tz>() else {
+                let error = format!("unknown time zone '{name}', expected an IANA name");
+                return Ok(json!({ "error": error }).to_string());
+            };
+            let mut converted = describe(now, &tz);
+            converted["timezone"] = json!(tz.name());
+            output["converted"] = converted;
+        }
+
+        Ok(output.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_follows_daylight_saving_time() {
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        let winter = Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap();
+        let summer = Utc.with_ymd_and_hms(2024, 7, 15, 12, 0, 0).unwrap();
+
+        assert_eq!(
+            describe(winter, &berlin)["time"],
+            "2024-01-15T13:00:00+01:00"
+        );
+        assert_eq!(
+            describe(summer, &berlin)["time"],
+            "2024-07-15T14:00:00+02:00"
+        );
+        assert_eq!(describe(summer, &berlin)["day_of_week"], "Monday");
+        assert_eq!(describe(summer, &berlin)["iso_week"], 29);
+    }
+}
@@ -0,0 +1,95 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::{tools::Tool, DDGSearcher};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::args::Args;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DdgSearcherConfig {
+    pub max_results: usize,
+    /// DuckDuckGo region code such as "us-en" or "de-de", unset searches worldwide.
+    pub region: Option<String>,
+}
+
+impl Default for DdgSearcherConfig {
+    fn default() -> Self {
+        Self {
+            max_results: 5,
+            region: None,
+        }
+    }
+}
+
+/// Wraps ollama-rs' `DDGSearcher`, which takes neither a result limit nor a region.
+pub struct DdgSearcher {
+    searcher: DDGSearcher,
+    config: DdgSearcherConfig,
+}
+
+impl DdgSearcher {
+    pub fn new(config: DdgSearcherConfig) -> Self {
+        Self {
+            searcher: DDGSearcher::new(),
+            config,
+        }
+    }
+
+    /// `DDGSearcher::search` pastes the query right after `?q=`, so the query is encoded
+    /// here and the region rides along as an extra parameter.
+    fn query_string(&self, query: &str) -> String {
+        let mut url = Url::parse("https://duckduckgo.com/html/").unwrap();
+        url.query_pairs_mut().append_pair("q", query);
+        if let Some(region) = &self.config.region {
+            url.query_pairs_mut().append_pair("kl", region);
+        }
+        url.query().unwrap_or_default()["q=".len()..].to_string()
+    }
+}
+
+#[async_trait]
+impl Tool for DdgSearcher {
+    fn name(&self) -> String {
+        self.searcher.name()
+    }
+
+    fn description(&self) -> String {
+        self.searcher.description()
+    }
+
+    fn parameters(&self) -> Value {
+        self.searcher.parameters()
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let query = Args::new(&input).get_str("query")?;
+
+        let results = self.searcher.search(&self.query_string(query)).await?;
+        let results: Vec<_> = results.into_iter().take(self.config.max_results).collect();
+        Ok(serde_json::to_string(&results)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_string_encodes_the_query_and_adds_the_region() {
+        let searcher = DdgSearcher::new(DdgSearcherConfig {
+            region: Some("de-de".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            searcher.query_string("rust & tokio"),
+            "rust+%26+tokio&kl=de-de"
+        );
+
+        let searcher = DdgSearcher::new(DdgSearcherConfig::default());
+        assert_eq!(searcher.query_string("rust"), "rust");
+    }
+}
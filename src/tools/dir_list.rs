@@ -0,0 +1,216 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use ollama_rs::generation::functions::tools::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use super::file_reader::resolve_within;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DirListConfig {
+    /// Only directories inside this one can be listed.
+    pub root: PathBuf,
+    /// How many levels below the listed directory a recursive listing descends.
+    pub max_depth: usize,
+    /// Entries returned at most, across all levels.
+    pub max_entries: usize,
+}
+
+impl Default for DirListConfig {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("."),
+            max_depth: 3,
+            max_entries: 500,
+        }
+    }
+}
+
+pub struct DirList {
+    config: DirListConfig,
+}
+
+impl DirList {
+    pub fn new(config: DirListConfig) -> Self {
+        Self { config }
+    }
+}
+
+/// Walks a directory, counting entries against the configured limit.
+struct Listing {
+    max_depth: usize,
+    remaining: usize,
+    truncated: bool,
+}
+
+impl Listing {
+    /// The entries of `dir`, directories first and then files, each group sorted by name.
+    /// Symlinks are reported but never followed.
+    fn list(&mut self, dir: &Path, depth: usize) -> std::io::Result<Vec<Value>> {
+        let mut children = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            children.push((entry.file_name().to_string_lossy().into_owned(), entry));
+        }
+        children.sort_by_key(|(name, entry)| {
+            let is_dir = entry.file_type().is_ok_and(|kind| kind.is_dir());
+            (!is_dir, name.clone())
+        });
+
+        let mut entries = Vec::new();
+        for (name, entry) in children {
+            if self.remaining == 0 {
+                self.truncated = true;
+                break;
+            }
+            self.remaining -= 1;
+
+            let metadata = entry.metadata()?;
+            let kind = if metadata.is_symlink() {
+                "symlink"
+            } else if metadata.is_dir() {
+                "dir"
+            } else {
+                "file"
+            };
+            let modified = metadata
+                .modified()
+                .ok()
+                .map(|time| DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true));
+            let mut value = json!({
+                "name": name,
+                "type": kind,
+                "size": metadata.len(),
+                "modified": modified,
+            });
+            if kind == "dir" && depth < self.max_depth {
+                value["entries"] = json!(self.list(&entry.path(), depth + 1)?);
+            }
+            entries.push(value);
+        }
+        Ok(entries)
+    }
+}
+
+#[async_trait]
+impl Tool for DirList {
+    fn name(&self) -> String {
+        "dir_list".to_string()
+    }
+
+    fn description(&self) -> String {
+        "List the files and directories in a local directory with their type, size and modification time"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Directory to list, relative to the working directory. Defaults to '.'"
+                },
+                "recursive": {
+                    "type": "boolean",
+                    "description": "Also list the contents of subdirectories"
+                }
+            }
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let path = args
+            .opt_str("path")?
+            .filter(|path| !path.trim().is_empty())
+            .unwrap_or(".")
+            .to_string();
+        let recursive = args.opt_bool("recursive")?.unwrap_or(false);
+
+        let resolved = match resolve_within(&self.config.root, &path) {
+            Ok(resolved) if resolved.is_dir() => resolved,
+            Ok(_) => {
+                let error = format!("'{path}' is not a directory");
+                return Ok(json!({ "error": error }).to_string());
+            }
+            Err(error) => return Ok(json!({ "error": error }).to_string()),
+        };
+
+        let mut listing = Listing {
+            max_depth: if recursive { self.config.max_depth } else { 0 },
+            remaining: self.config.max_entries,
+            truncated: false,
+        };
+        let result = tokio::task::spawn_blocking(move || {
+            let entries = listing.list(&resolved, 0);
+            (entries, listing.truncated)
+        })
+        .await?;
+
+        let response = match result {
+            (Ok(entries), truncated) => json!({
+                "path": path,
+                "entries": entries,
+                "truncated": truncated,
+            }),
+            (Err(e), _) => json!({"error": format!("cannot list '{path}': {e}")}),
+        };
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(entries: &[Value]) -> Vec<&str> {
+        entries
+            .iter()
+            .map(|entry| entry["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn directories_come_first_and_depth_is_capped() {
+        let dir = std::env::temp_dir().join(format!("shark-dir-list-{}", std::process::id()));
+        fs::create_dir_all(dir.join("b_dir/nested")).unwrap();
+        fs::create_dir_all(dir.join("z_dir")).unwrap();
+        fs::write(dir.join("a.txt"), "hello").unwrap();
+        fs::write(dir.join("b_dir/inner.txt"), "").unwrap();
+
+        let mut listing = Listing {
+            max_depth: 1,
+            remaining: 100,
+            truncated: false,
+        };
+        let entries = listing.list(&dir, 0).unwrap();
+        assert_eq!(names(&entries), ["b_dir", "z_dir", "a.txt"]);
+        assert_eq!(entries[2]["type"], "file");
+        assert_eq!(entries[2]["size"], 5);
+
+        let inner = entries[0]["entries"].as_array().unwrap();
+        assert_eq!(names(inner), ["nested", "inner.txt"]);
+        assert!(inner[0].get("entries").is_none());
+
+        let mut listing = Listing {
+            max_depth: 0,
+            remaining: 2,
+            truncated: false,
+        };
+        let entries = listing.list(&dir, 0).unwrap();
+        assert_eq!(names(&entries), ["b_dir", "z_dir"]);
+        assert!(entries[0].get("entries").is_none());
+        assert!(listing.truncated);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
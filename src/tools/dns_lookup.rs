@@ -0,0 +1,190 @@
+use std::{error::Error, time::Duration};
+
+use async_trait::async_trait;
+use hickory_resolver::{
+    net::NetError,
+    proto::rr::{RData, RecordType},
+    TokioResolver,
+};
+use ollama_rs::generation::functions::tools::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DnsLookupConfig {
+    /// Seconds to wait for each name server before giving up on it.
+    pub timeout_secs: u64,
+}
+
+impl Default for DnsLookupConfig {
+    fn default() -> Self {
+        Self { timeout_secs: 5 }
+    }
+}
+
+const RECORD_TYPES: [&str; 5] = ["A", "AAAA", "MX", "TXT", "CNAME"];
+
+fn record_type(name: &str) -> Option<RecordType> {
+    match name.to_uppercase().as_str() {
+        "A" => Some(RecordType::A),
+        "AAAA" => Some(RecordType::AAAA),
+        "MX" => Some(RecordType::MX),
+        "TXT" => Some(RecordType::TXT),
+        "CNAME" => Some(RecordType::CNAME),
+        _ => None,
+    }
+}
+
+/// Record data as JSON: MX records keep their preference, TXT records are joined into
+/// one string and everything else is shown the way zone files write it.
+fn record_value(data: &RData) -> Value {
+    match data {
+        RData::MX(mx) => json!({
+            "preference": mx.preference,
+            "exchange": mx.exchange.to_string(),
+        }),
+        RData::TXT(txt) => {
+            let text: String = txt
+                .txt_data
+                .iter()
+                .map(|part| String::from_utf8_lossy(part))
+                .collect();
+            json!(text)
+        }
+        data => json!(data.to_string()),
+    }
+}
+
+fn describe_error(hostname: &str, record_type: RecordType, e: &NetError) -> String {
+    if e.is_nx_domain() {
+        format!("{hostname} does not exist (NXDOMAIN)")
+    } else if e.is_no_records_found() {
+        format!("{hostname} has no {record_type} records")
+    } else if matches!(e, NetError::Timeout) {
+        format!("looking up {hostname} timed out")
+    } else {
+        format!("failed to look up {hostname}: {e}")
+    }
+}
+
+pub struct DnsLookup {
+    config: DnsLookupConfig,
+}
+
+impl DnsLookup {
+    pub fn new(config: DnsLookupConfig) -> Self {
+        Self { config }
+    }
+
+    async fn lookup(&self, hostname: &str, record_type: RecordType) -> Result<Value, String> {
+        let mut builder = TokioResolver::builder_tokio()
+            .map_err(|e| format!("failed to read the system's DNS configuration: {e}"))?;
+        builder.options_mut().timeout = Duration::from_secs(self.config.timeout_secs);
+        let resolver = builder.build().map_err(|e| e.to_string())?;
+
+        tracing::debug!(hostname, %record_type, "looking up");
+        let lookup = resolver
+            .lookup(hostname, record_type)
+            .await
+            .map_err(|e| describe_error(hostname, record_type, &e))?;
+        // Answers can start with the CNAME chain leading to the requested records.
+        let records: Vec<Value> = lookup
+            .answers()
+            .iter()
+            .filter(|record| record.record_type() == record_type)
+            .map(|record| {
+                json!({
+                    "name": record.name.to_string(),
+                    "ttl": record.ttl,
+                    "value": record_value(&record.data),
+                })
+            })
+            .collect();
+        Ok(json!({
+            "hostname": hostname,
+            "record_type": record_type.to_string(),
+            "records": records,
+        }))
+    }
+}
+
+#[async_trait]
+impl Tool for DnsLookup {
+    fn name(&self) -> String {
+        "dns_lookup".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Resolve a hostname's DNS records, e.g. its IP addresses or mail servers".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "hostname": {
+                    "type": "string",
+                    "description": "The name to resolve, e.g. 'example.com'"
+                },
+                "record_type": {
+                    "type": "string",
+                    "enum": RECORD_TYPES,
+                    "description": "The kind of record to look up, A when omitted"
+                }
+            },
+            "required": ["hostname"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let hostname = args.get_str("hostname")?;
+        let name = args.opt_str("record_type")?.unwrap_or("A");
+        let Some(record_type) = record_type(name) else {
+            let error = format!(
+                "unsupported record type '{name}', use one of {}",
+                RECORD_TYPES.join(", ")
+            );
+            return Ok(json!({ "error": error }).to_string());
+        };
+        let response = self
+            .lookup(hostname.trim(), record_type)
+            .await
+            .unwrap_or_else(|error| json!({ "error": error }));
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hickory_resolver::proto::rr::{
+        rdata::{A, MX, TXT},
+        Name,
+    };
+
+    use super::*;
+
+    #[test]
+    fn record_types_are_case_insensitive() {
+        assert_eq!(record_type("aaaa"), Some(RecordType::AAAA));
+        assert_eq!(record_type("Mx"), Some(RecordType::MX));
+        assert_eq!(record_type("SRV"), None);
+    }
+
+    #[test]
+    fn records_are_shown_as_json() {
+        let a = RData::A(A::new(93, 184, 216, 34));
+        assert_eq!(record_value(&a), "93.184.216.34");
+        let exchange = Name::from_ascii("mail.example.com.").unwrap();
+        let mx = RData::MX(MX::new(10, exchange));
+        assert_eq!(
+            record_value(&mx),
+            json!({"preference": 10, "exchange": "mail.example.com."})
+        );
+        let txt = RData::TXT(TXT::new(vec!["v=spf1 ".to_string(), "-all".to_string()]));
+        assert_eq!(record_value(&txt), "v=spf1 -all");
+    }
+}
@@ -0,0 +1,204 @@
+use std::{collections::BTreeMap, error::Error, time::Duration};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use reqwest::StatusCode;
+use scraper::{Html, Selector};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use super::crates_io_search::{crates_io_client, CRATES_API_URL};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DocsRsLookupConfig {
+    pub timeout_secs: u64,
+}
+
+impl Default for DocsRsLookupConfig {
+    fn default() -> Self {
+        Self { timeout_secs: 15 }
+    }
+}
+
+#[derive(Deserialize)]
+struct CrateResponse {
+    #[serde(rename = "crate")]
+    krate: CrateInfo,
+}
+
+#[derive(Deserialize)]
+struct CrateInfo {
+    description: Option<String>,
+    max_stable_version: Option<String>,
+    max_version: String,
+}
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    version: VersionInfo,
+}
+
+#[derive(Deserialize)]
+struct VersionInfo {
+    num: String,
+    features: BTreeMap<String, Vec<String>>,
+}
+
+/// Looks a crate up on docs.rs. The description, version and features come from the
+/// crates.io API, which reports missing crates reliably, the modules from the rendered docs.
+pub struct DocsRsLookup {
+    client: reqwest::Client,
+}
+
+impl DocsRsLookup {
+    pub fn new(config: DocsRsLookupConfig) -> Self {
+        let client = crates_io_client(Duration::from_secs(config.timeout_secs));
+        Self { client }
+    }
+
+    async fn get(&self, url: &str) -> Result<Option<String>, String> {
+        tracing::debug!(url, "fetching");
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| format!("failed to fetch {url}: {e}"))?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if !status.is_success() => Err(format!("{url} responded with {status}")),
+            _ => response.text().await.map(Some).map_err(|e| e.to_string()),
+        }
+    }
+
+    async fn lookup(&self, name: &str, version: Option<&str>) -> Result<Value, String> {
+        let Some(body) = self.get(&format!("{CRATES_API_URL}/{name}")).await? else {
+            return Err(format!("crate '{name}' doesn't exist on crates.io"));
+        };
+        let krate = serde_json::from_str::<CrateResponse>(&body)
+            .map_err(|e| e.to_string())?
+            .krate;
+
+        let version = match version {
+            Some(version) => version.to_string(),
+            None => krate.max_stable_version.unwrap_or(krate.max_version),
+        };
+        let Some(body) = self
+            .get(&format!("{CRATES_API_URL}/{name}/{version}"))
+            .await?
+        else {
+            return Err(format!("version {version} of '{name}' doesn't exist"));
+        };
+        let version = serde_json::from_str::<VersionResponse>(&body)
+            .map_err(|e| e.to_string())?
+            .version;
+
+        let docs_url = format!(
+            "https://docs.rs/{name}/{}/{}/",
+            version.num,
+            name.replace('-', "_")
+        );
+        let modules = match self.get(&docs_url).await? {
+            Some(html) => top_level_modules(&html),
+            None => Vec::new(),
+        };
+
+        Ok(json!({
+            "crate": name,
+            "version": version.num,
+            "docs_url": docs_url,
+            "description": krate.description.map(|d| d.trim().to_string()),
+            "features": version.features,
+            "modules": modules,
+        }))
+    }
+}
+
+/// Names of the modules listed on a rustdoc crate page.
+fn top_level_modules(html: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let Ok(selector) = Selector::parse("a.mod") else {
+        return Vec::new();
+    };
+
+    let mut modules: Vec<String> = document
+        .select(&selector)
+        .map(|link| link.text().collect::<String>().trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    modules.dedup();
+    modules
+}
+
+#[async_trait]
+impl Tool for DocsRsLookup {
+    fn name(&self) -> String {
+        "docs_rs_lookup".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Look up a Rust crate's documentation on docs.rs: description, feature flags and modules"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "crate_name": {
+                    "type": "string",
+                    "description": "Name of the crate, e.g. 'tokio'"
+                },
+                "version": {
+                    "type": "string",
+                    "description": "Exact version to look up, defaults to the latest release"
+                }
+            },
+            "required": ["crate_name"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let name = args.get_str("crate_name")?.trim();
+        let version = args
+            .opt_str("version")?
+            .map(str::trim)
+            .filter(|v| !v.is_empty() && *v != "latest");
+
+        let valid = |s: &str| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+'))
+        };
+        if !valid(name) || !version.is_none_or(valid) {
+            return Ok(json!({"error": "invalid crate name or version"}).to_string());
+        }
+
+        match self.lookup(name, version).await {
+            Ok(docs) => Ok(docs.to_string()),
+            Err(error) => Ok(json!({ "error": error }).to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_level_modules_reads_rustdoc_item_table() {
+        let html = r#"
+            <h2 id="modules">Modules</h2>
+            <ul class="item-table">
+                <li><div class="item-name"><a class="mod" href="de/index.html">de</a></div></li>
+                <li><div class="item-name"><a class="mod" href="ser/index.html">ser</a></div></li>
+            </ul>
+            <h2 id="macros">Macros</h2>
+            <a class="macro" href="macro.forward.html">forward</a>
+        "#;
+        assert_eq!(top_level_modules(html), vec!["de", "ser"]);
+    }
+}
@@ -0,0 +1,174 @@
+use std::{error::Error, path::PathBuf};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use super::file_reader::resolve_within;
+
+/// Rough size of a token, used to turn the token budget into a chunk length.
+const CHARS_PER_TOKEN: usize = 4;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DocumentLoaderConfig {
+    /// Only documents inside this directory can be read.
+    pub root: PathBuf,
+    /// Approximate number of tokens returned per chunk.
+    pub max_tokens: usize,
+}
+
+impl Default for DocumentLoaderConfig {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("."),
+            max_tokens: 2000,
+        }
+    }
+}
+
+/// Text of a document and, for paged formats, the number of pages.
+struct Document {
+    format: &'static str,
+    text: String,
+    page_count: Option<usize>,
+}
+
+pub struct DocumentLoader {
+    config: DocumentLoaderConfig,
+}
+
+impl DocumentLoader {
+    pub fn new(config: DocumentLoaderConfig) -> Self {
+        Self { config }
+    }
+
+    async fn load(&self, path: &str) -> Result<Document, String> {
+        let resolved = resolve_within(&self.config.root, path)?;
+        let extension = resolved
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match extension.as_str() {
+            "txt" | "md" | "markdown" => {
+                let text = tokio::fs::read_to_string(&resolved)
+                    .await
+                    .map_err(|e| format!("cannot read '{path}': {e}"))?;
+                Ok(Document {
+                    format: "text",
+                    text,
+                    page_count: None,
+                })
+            }
+            "pdf" => {
+                let bytes = tokio::fs::read(&resolved)
+                    .await
+                    .map_err(|e| format!("cannot read '{path}': {e}"))?;
+                // pdf-extract panics on some malformed files, the blocking task contains that.
+                let pages = tokio::task::spawn_blocking(move || {
+                    pdf_extract::extract_text_from_mem_by_pages(&bytes)
+                })
+                .await
+                .map_err(|_| format!("'{path}' is not a readable PDF"))?
+                .map_err(|e| format!("cannot extract text from '{path}': {e}"))?;
+                Ok(Document {
+                    format: "pdf",
+                    page_count: Some(pages.len()),
+                    text: pages.join("\n"),
+                })
+            }
+            _ => Err(format!(
+                "unsupported document type '.{extension}', expected .txt, .md or .pdf"
+            )),
+        }
+    }
+}
+
+/// Splits `text` into pieces of at most `size` characters.
+fn chunks(text: &str, size: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = rest
+            .char_indices()
+            .nth(size)
+            .map_or(rest.len(), |(end, _)| end);
+        chunks.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    chunks
+}
+
+#[async_trait]
+impl Tool for DocumentLoader {
+    fn name(&self) -> String {
+        "document_loader".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Extract the text of a local .txt, .md or .pdf document, chunk by chunk".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the document, relative to the working directory"
+                },
+                "chunk": {
+                    "type": "integer",
+                    "description": "Which chunk of a long document to return, starting at 0"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let path = args.get_str("path")?;
+        let chunk = args.opt_u64("chunk")?.unwrap_or(0) as usize;
+
+        let document = match self.load(path).await {
+            Ok(document) => document,
+            Err(error) => return Ok(json!({ "error": error }).to_string()),
+        };
+
+        let size = self.config.max_tokens.max(1) * CHARS_PER_TOKEN;
+        let chunks = chunks(document.text.trim(), size);
+        let chunk_count = chunks.len();
+        if chunk_count > 0 && chunk >= chunk_count {
+            let error = format!("'{path}' only has {chunk_count} chunks");
+            return Ok(json!({ "error": error }).to_string());
+        }
+
+        let more = chunk + 1 < chunk_count;
+        Ok(json!({
+            "path": path,
+            "format": document.format,
+            "page_count": document.page_count,
+            "chunk": chunk,
+            "chunk_count": chunk_count,
+            "text": chunks.get(chunk).copied().unwrap_or_default(),
+            "note": more.then(|| format!("more text is available, ask for chunk {}", chunk + 1)),
+        })
+        .to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_split_on_char_boundaries() {
+        assert_eq!(chunks("héllo wörld", 4), vec!["héll", "o wö", "rld"]);
+        assert!(chunks("", 4).is_empty());
+    }
+}
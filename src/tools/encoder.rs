@@ -0,0 +1,180 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ollama_rs::generation::functions::tools::Tool;
+use serde_json::{json, Value};
+
+use super::args::{ArgError, Args};
+
+const OPERATIONS: [&str; 2] = ["encode", "decode"];
+const ENCODINGS: [&str; 3] = ["base64", "hex", "url"];
+
+#[derive(Default)]
+pub struct Encoder {}
+
+fn encode(encoding: &str, input: &str) -> String {
+    match encoding {
+        "base64" => STANDARD.encode(input),
+        "hex" => input.bytes().map(|b| format!("{b:02x}")).collect(),
+        _ => input
+            .bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                    (b as char).to_string()
+                }
+                _ => format!("%{b:02X}"),
+            })
+            .collect(),
+    }
+}
+
+fn decode(encoding: &str, input: &str) -> Result<String, String> {
+    let bytes = match encoding {
+        "base64" => STANDARD
+            .decode(input.trim())
+            .map_err(|e| format!("invalid base64: {e}"))?,
+        "hex" => decode_hex(input.trim())?,
+        _ => decode_url(input)?,
+    };
+    String::from_utf8(bytes).map_err(|_| "the decoded bytes are not UTF-8 text".to_string())
+}
+
+fn hex_digit(c: u8) -> Result<u8, String> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(format!("invalid hex character '{}'", c as char)),
+    }
+}
+
+fn decode_hex(input: &str) -> Result<Vec<u8>, String> {
+    if !input.len().is_multiple_of(2) {
+        return Err("invalid hex: odd number of digits".to_string());
+    }
+    input
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| Ok(hex_digit(pair[0])? << 4 | hex_digit(pair[1])?))
+        .collect()
+}
+
+/// Percent-decodes `input`, reading `+` as a space like form encoding does.
+fn decode_url(input: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut rest = input.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        match b {
+            b'%' => {
+                let [high, low, ..] = *rest else {
+                    return Err("invalid url encoding: truncated escape".to_string());
+                };
+                bytes.push(hex_digit(high)? << 4 | hex_digit(low)?);
+                rest = &rest[2..];
+            }
+            b'+' => bytes.push(b' '),
+            _ => bytes.push(b),
+        }
+    }
+    Ok(bytes)
+}
+
+#[async_trait]
+impl Tool for Encoder {
+    fn name(&self) -> String {
+        "encoder".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Encode or decode text as base64, hex or URL percent-encoding".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": OPERATIONS,
+                    "description": "Whether to encode or decode the input"
+                },
+                "encoding": {
+                    "type": "string",
+                    "enum": ENCODINGS,
+                    "description": "The encoding to use"
+                },
+                "input": {
+                    "type": "string",
+                    "description": "The text to encode, or the encoded text to decode"
+                }
+            },
+            "required": ["operation", "encoding", "input"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let operation = args.get_str("operation")?;
+        let encoding = args.get_str("encoding")?;
+        let text = args
+            .opt_str("input")?
+            .ok_or_else(|| ArgError("missing 'input' argument".to_string()))?;
+
+        if !ENCODINGS.contains(&encoding) {
+            let error = format!(
+                "unsupported encoding '{encoding}', expected one of: {}",
+                ENCODINGS.join(", ")
+            );
+            return Ok(json!({ "error": error }).to_string());
+        }
+        let output = match operation {
+            "encode" => Ok(encode(encoding, text)),
+            "decode" => decode(encoding, text),
+            _ => Err(format!(
+                "unsupported operation '{operation}', expected encode or decode"
+            )),
+        };
+
+        let response = match output {
+            Ok(output) => json!({
+                "operation": operation,
+                "encoding": encoding,
+                "output": output,
+            }),
+            Err(error) => json!({ "error": error }),
+        };
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodings_round_trip() {
+        let text = "shark 🦈 & co/?";
+        for encoding in ENCODINGS {
+            let encoded = encode(encoding, text);
+            assert_eq!(decode(encoding, &encoded).unwrap(), text, "{encoding}");
+        }
+        assert_eq!(encode("hex", "hi"), "6869");
+        assert_eq!(encode("url", "a b&c"), "a%20b%26c");
+        assert_eq!(encode("base64", "hi"), "aGk=");
+        assert_eq!(decode("url", "a+b").unwrap(), "a b");
+    }
+
+    #[test]
+    fn invalid_input_is_reported() {
+        assert_eq!(
+            decode("hex", "6g").unwrap_err(),
+            "invalid hex character 'g'"
+        );
+        assert!(decode("hex", "686").is_err());
+        assert!(decode("base64", "not base64!").is_err());
+        assert!(decode("url", "%4").is_err());
+        assert!(decode("hex", "ff").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use std::{collections::BTreeMap, error::Error};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+
+/// Variables returned when no prefix is given, none of them should hold secrets.
+const SAFE_VARIABLES: [&str; 10] = [
+    "PATH",
+    "HOME",
+    "SHELL",
+    "USER",
+    "LANG",
+    "TERM",
+    "EDITOR",
+    "PWD",
+    "TZ",
+    "CARGO_HOME",
+];
+
+/// Keys containing any of these always have their value redacted.
+const REDACTED_KEYS: [&str; 4] = ["SECRET", "TOKEN", "KEY", "PASSWORD"];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct EnvReaderConfig {
+    /// Extra key fragments whose values are redacted, e.g. "CREDENTIAL".
+    pub redact: Vec<String>,
+}
+
+pub struct EnvReader {
+    config: EnvReaderConfig,
+}
+
+impl EnvReader {
+    pub fn new(config: EnvReaderConfig) -> Self {
+        Self { config }
+    }
+
+    fn is_sensitive(&self, key: &str) -> bool {
+        let key = key.to_uppercase();
+        REDACTED_KEYS
+            .iter()
+            .copied()
+            .chain(self.config.redact.iter().map(String::as_str))
+            .any(|fragment| key.contains(&fragment.to_uppercase()))
+    }
+
+    /// Variables matching `prefix`, or the safe set without one, with secrets redacted.
+    fn read(
+        &self,
+        vars: impl Iterator<Item = (String, String)>,
+        prefix: Option<&str>,
+    ) -> BTreeMap<String, String> {
+        vars.filter(|(key, _)| match prefix {
+            Some(prefix) => key.starts_with(prefix),
+            None => SAFE_VARIABLES.contains(&key.as_str()),
+        })
+        .map(|(key, value)| {
+            let value = if self.is_sensitive(&key) {
+                "***".to_string()
+            } else {
+                value
+            };
+            (key, value)
+        })
+        .collect()
+    }
+}
+
+#[async_trait]
+impl Tool for EnvReader {
+    fn name(&self) -> String {
+        "env_reader".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Read environment variables, optionally those starting with a prefix".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "prefix": {
+                    "type": "string",
+                    "description": "Only return variables whose name starts with this, e.g. 'CARGO_'"
+                }
+            }
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let prefix = Args::new(&input)
+            .opt_str("prefix")?
+            .filter(|p| !p.is_empty());
+        let vars = self.read(std::env::vars(), prefix);
+        Ok(json!(vars).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> impl Iterator<Item = (String, String)> {
+        [
+            ("HOME", "/home/me"),
+            ("AWS_SECRET_ACCESS_KEY", "abc"),
+            ("AWS_REGION", "eu-west-1"),
+            ("AWS_CREDENTIAL_FILE", "/home/me/.aws"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+    }
+
+    #[test]
+    fn redacts_sensitive_values() {
+        let reader = EnvReader::new(EnvReaderConfig {
+            redact: vec!["credential".to_string()],
+        });
+        let read = reader.read(vars(), Some("AWS_"));
+        assert_eq!(read["AWS_SECRET_ACCESS_KEY"], "***");
+        assert_eq!(read["AWS_CREDENTIAL_FILE"], "***");
+        assert_eq!(read["AWS_REGION"], "eu-west-1");
+        assert!(!read.contains_key("HOME"));
+    }
+
+    #[test]
+    fn without_prefix_only_safe_variables_are_returned() {
+        let reader = EnvReader::new(EnvReaderConfig::default());
+        let read = reader.read(vars(), None);
+        assert_eq!(read.keys().collect::<Vec<_>>(), vec!["HOME"]);
+    }
+}
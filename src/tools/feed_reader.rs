@@ -0,0 +1,319 @@
+use std::{error::Error, time::Duration};
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use ollama_rs::generation::functions::tools::Tool;
+use quick_xml::{escape::unescape, events::Event, Reader};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use super::http_fetch::{html_to_text, truncate_chars, USER_AGENT};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FeedReaderConfig {
+    /// Items returned at most, newest first as listed by the feed.
+    pub max_items: usize,
+    /// Descriptions are cut down to this many characters.
+    pub description_chars: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for FeedReaderConfig {
+    fn default() -> Self {
+        Self {
+            max_items: 10,
+            description_chars: 300,
+            timeout_secs: 15,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Item {
+    title: String,
+    link: Option<String>,
+    published: Option<String>,
+    /// The description as the feed has it, usually HTML.
+    description: String,
+}
+
+#[derive(Debug, PartialEq)]
+struct Feed {
+    format: &'static str,
+    title: String,
+    items: Vec<Item>,
+}
+
+/// Lowercased name of an element without its namespace prefix, `pubdate` for `<pubDate>`.
+fn local_name(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).to_lowercase()
+}
+
+/// Dates as RFC 3339 when they parse as RFC 2822 (RSS) or RFC 3339 (Atom).
+fn normalize_date(date: &str) -> String {
+    DateTime::parse_from_rfc2822(date)
+        .or_else(|_| DateTime::parse_from_rfc3339(date))
+        .map(|date| date.to_rfc3339())
+        .unwrap_or_else(|_| date.to_string())
+}
+
+/// Reads the first `max_items` items of an RSS 2.0, RSS 1.0 (RDF) or Atom feed.
+fn parse_feed(xml: &str, max_items: usize) -> Result<Feed, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut format = None;
+    let mut title = String::new();
+    let mut items = Vec::new();
+    let mut item: Option<Item> = None;
+    let mut text = String::new();
+
+    while items.len() < max_items {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("invalid XML at byte {}: {e}", reader.error_position()))?;
+        match event {
+            Event::Start(element) | Event::Empty(element) => {
+                let name = local_name(element.local_name().as_ref());
+                if format.is_none() {
+                    format = Some(match name.as_str() {
+                        "rss" | "rdf" => "rss",
+                        "feed" => "atom",
+                        _ => return Err("not an RSS or Atom feed".to_string()),
+                    });
+                }
+                match name.as_str() {
+                    "item" | "entry" => item = Some(Item::default()),
+                    // Atom links are attributes, the alternate one points at the article.
+                    "link" => {
+                        let attribute = |key: &str| {
+                            element
+                                .try_get_attribute(key)
+                                .ok()
+                                .flatten()
+                                .and_then(|attribute| attribute.unescape_value().ok())
+                                .map(|value| value.into_owned())
+                        };
+                        let alternate = attribute("rel").is_none_or(|rel| rel == "alternate");
+                        if let (Some(item), Some(href), true) =
+                            (&mut item, attribute("href"), alternate)
+                        {
+                            item.link.get_or_insert(href);
+                        }
+                    }
+                    _ => {}
+                }
+                text.clear();
+            }
+            Event::Text(chunk) => text.push_str(&chunk.decode().map_err(|e| e.to_string())?),
+            Event::CData(chunk) => text.push_str(&chunk.decode().map_err(|e| e.to_string())?),
+            Event::GeneralRef(entity) => {
+                let entity = format!("&{};", entity.decode().map_err(|e| e.to_string())?);
+                text.push_str(&unescape(&entity).unwrap_or_default());
+            }
+            Event::End(element) => {
+                let name = local_name(element.local_name().as_ref());
+                let value = text.trim().to_string();
+                text.clear();
+                let Some(current) = &mut item else {
+                    if name == "title" && title.is_empty() {
+                        title = value;
+                    }
+                    continue;
+                };
+                match name.as_str() {
+                    "item" | "entry" => items.extend(item.take()),
+                    "title" => current.title = value,
+                    "link" if !value.is_empty() => {
+                        current.link.get_or_insert(value);
+                    }
+                    "published" | "pubdate" => current.published = Some(value),
+                    "updated" | "date" => {
+                        current.published.get_or_insert(value);
+                    }
+                    "description" | "summary" | "content" | "encoded"
+                        if current.description.is_empty() =>
+                    {
+                        current.description = value;
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let format = format.ok_or("not an RSS or Atom feed")?;
+    Ok(Feed {
+        format,
+        title,
+        items,
+    })
+}
+
+pub struct FeedReader {
+    client: reqwest::Client,
+    config: FeedReaderConfig,
+}
+
+impl FeedReader {
+    pub fn new(config: FeedReaderConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .unwrap_or_default();
+        Self { client, config }
+    }
+
+    async fn read(&self, url: &str) -> Result<Value, String> {
+        let url = Url::parse(url).map_err(|e| format!("invalid url '{url}': {e}"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!(
+                "only http and https feeds can be read, got '{url}'"
+            ));
+        }
+
+        tracing::debug!(%url, "fetching feed");
+        let response = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| format!("failed to fetch {url}: {e}"))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("{url} responded with {status}"));
+        }
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        let feed = parse_feed(&body, self.config.max_items).map_err(|e| format!("{url}: {e}"))?;
+
+        let items: Vec<Value> = feed
+            .items
+            .iter()
+            .map(|item| {
+                let (_, text) = html_to_text(&item.description);
+                let (description, truncated) = truncate_chars(&text, self.config.description_chars);
+                let ellipsis = if truncated { "…" } else { "" };
+                json!({
+                    "title": item.title,
+                    "link": item.link,
+                    "published": item.published.as_deref().map(normalize_date),
+                    "description": format!("{description}{ellipsis}"),
+                })
+            })
+            .collect();
+        Ok(json!({
+            "feed": feed.title,
+            "format": feed.format,
+            "items": items,
+        }))
+    }
+}
+
+#[async_trait]
+impl Tool for FeedReader {
+    fn name(&self) -> String {
+        "feed_reader".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Fetch an RSS or Atom feed and return its latest items with title, link, date and a short description"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "The http or https url of the feed"
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let url = Args::new(&input).get_str("url")?;
+        let response = self
+            .read(url)
+            .await
+            .unwrap_or_else(|error| json!({ "error": error }));
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rss_items_are_read() {
+        let rss = r#"<?xml version="1.0"?>
+            <rss version="2.0"><channel>
+              <title>Shark &amp; friends</title>
+              <item>
+                <title>First</title>
+                <link>https://example.com/1</link>
+                <pubDate>Tue, 01 Oct 2024 10:00:00 +0000</pubDate>
+                <description><![CDATA[<p>Hello <b>world</b></p>]]></description>
+              </item>
+              <item><title>Second</title></item>
+              <item><title>Third</title></item>
+            </channel></rss>"#;
+        let feed = parse_feed(rss, 2).unwrap();
+        assert_eq!(feed.format, "rss");
+        assert_eq!(feed.title, "Shark & friends");
+        assert_eq!(feed.items.len(), 2);
+        assert_eq!(
+            feed.items[0],
+            Item {
+                title: "First".to_string(),
+                link: Some("https://example.com/1".to_string()),
+                published: Some("Tue, 01 Oct 2024 10:00:00 +0000".to_string()),
+                description: "<p>Hello <b>world</b></p>".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn atom_entries_are_read() {
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+              <title>Atom blog</title>
+              <link href="https://example.com/"/>
+              <entry>
+                <title type="html">Post</title>
+                <link rel="edit" href="https://example.com/edit/1"/>
+                <link href="https://example.com/post"/>
+                <updated>2024-10-02T08:00:00Z</updated>
+                <published>2024-10-01T08:00:00Z</published>
+                <summary>Short &lt;summary&gt;</summary>
+                <content>Long content</content>
+              </entry>
+            </feed>"#;
+        let feed = parse_feed(atom, 10).unwrap();
+        assert_eq!(feed.format, "atom");
+        assert_eq!(feed.title, "Atom blog");
+        let entry = &feed.items[0];
+        assert_eq!(entry.link.as_deref(), Some("https://example.com/post"));
+        assert_eq!(entry.published.as_deref(), Some("2024-10-01T08:00:00Z"));
+        assert_eq!(entry.description, "Short <summary>");
+    }
+
+    #[test]
+    fn other_documents_are_no_feed() {
+        let html = "<html><head><title>Page</title></head></html>";
+        assert_eq!(parse_feed(html, 10).unwrap_err(), "not an RSS or Atom feed");
+        assert!(parse_feed("", 10).is_err());
+        assert!(parse_feed("<rss><channel></item></rss>", 10).is_err());
+        assert_eq!(
+            normalize_date("Tue, 01 Oct 2024 10:00:00 +0000"),
+            "2024-10-01T10:00:00+00:00"
+        );
+        assert_eq!(normalize_date("yesterday"), "yesterday");
+    }
+}
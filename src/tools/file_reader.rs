@@ -0,0 +1,177 @@
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::AsyncReadExt;
+
+use super::args::Args;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FileReaderConfig {
+    /// Only files inside this directory can be read.
+    pub root: PathBuf,
+    pub max_bytes: usize,
+}
+
+impl Default for FileReaderConfig {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("."),
+            max_bytes: 64 * 1024,
+        }
+    }
+}
+
+pub struct FileReader {
+    config: FileReaderConfig,
+}
+
+impl FileReader {
+    pub fn new(config: FileReaderConfig) -> Self {
+        Self { config }
+    }
+}
+
+/// Resolves `path` against `root`, rejecting anything that ends up outside of it
+/// (`..` components, absolute paths or symlinks pointing elsewhere).
+pub fn resolve_within(root: &Path, path: &str) -> Result<PathBuf, String> {
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("invalid root {}: {e}", root.display()))?;
+    let resolved = root
+        .join(path)
+        .canonicalize()
+        .map_err(|e| format!("cannot open '{path}': {e}"))?;
+
+    if !resolved.starts_with(&root) {
+        return Err(format!(
+            "'{path}' is outside of the allowed directory {}",
+            root.display()
+        ));
+    }
+    Ok(resolved)
+}
+
+#[async_trait]
+impl Tool for FileReader {
+    fn name(&self) -> String {
+        "file_reader".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Read a local text file and return its contents".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the file to read, relative to the working directory"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let path = Args::new(&input).get_str("path")?;
+
+        let resolved = match resolve_within(&self.config.root, path) {
+            Ok(resolved) => resolved,
+            Err(error) => return Ok(json!({ "error": error }).to_string()),
+        };
+
+        // Only the first `max_bytes` are read, however large the file is.
+        let file = tokio::fs::File::open(&resolved).await?;
+        let byte_size = file.metadata().await?.len();
+        let truncated = byte_size > self.config.max_bytes as u64;
+        let mut bytes = Vec::new();
+        file.take(self.config.max_bytes as u64)
+            .read_to_end(&mut bytes)
+            .await?;
+
+        let content = match String::from_utf8(bytes) {
+            Ok(content) => content,
+            // The cut can end in the middle of a character, which is dropped.
+            Err(e) if truncated && e.utf8_error().error_len().is_none() => {
+                let valid = e.utf8_error().valid_up_to();
+                String::from_utf8_lossy(&e.as_bytes()[..valid]).into_owned()
+            }
+            Err(_) => {
+                let error = format!("'{path}' is not a UTF-8 text file");
+                return Ok(json!({ "error": error }).to_string());
+            }
+        };
+        let line_count = content.lines().count();
+
+        Ok(json!({
+            "path": path,
+            "content": content,
+            "line_count": line_count,
+            "byte_size": byte_size,
+            "truncated": truncated,
+        })
+        .to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn reader(root: &Path, max_bytes: usize) -> FileReader {
+        FileReader::new(FileReaderConfig {
+            root: root.to_path_buf(),
+            max_bytes,
+        })
+    }
+
+    #[tokio::test]
+    async fn large_files_are_cut_at_a_character_boundary() {
+        let dir = std::env::temp_dir().join(format!("shark-file-reader-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("notes.txt"), "héllo\nwörld\n").unwrap();
+
+        let output = reader(&dir, 2)
+            .run(json!({"path": "notes.txt"}))
+            .await
+            .unwrap();
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["content"], "h");
+        assert_eq!(output["byte_size"], 14);
+        assert_eq!(output["truncated"], true);
+
+        let output = reader(&dir, 100)
+            .run(json!({"path": "notes.txt"}))
+            .await
+            .unwrap();
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["content"], "héllo\nwörld\n");
+        assert_eq!(output["line_count"], 2);
+        assert_eq!(output["truncated"], false);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn paths_outside_the_root_are_rejected() {
+        let root = Path::new("src");
+        assert!(resolve_within(root, "main.rs").is_ok());
+        let error = resolve_within(root, "../Cargo.toml").unwrap_err();
+        assert!(
+            error.contains("outside of the allowed directory"),
+            "{error}"
+        );
+        assert!(resolve_within(root, "/etc/hostname").is_err());
+    }
+}
@@ -0,0 +1,186 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use tokio::process::Command;
+
+const DEFAULT_LOG_COUNT: u64 = 10;
+const MAX_DIFF_BYTES: usize = 32 * 1024;
+
+#[derive(Default)]
+pub struct GitInspector {}
+
+async fn git(args: &[&str]) -> Result<String, String> {
+    tracing::debug!(?args, "running git");
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => "git is not installed".to_string(),
+            _ => format!("failed to run git: {e}"),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("not a git repository") {
+            return Err("the current directory is not inside a git repository".to_string());
+        }
+        return Err(stderr.trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Splits `git status --porcelain` lines into staged, unstaged and untracked paths.
+/// Staged renames and copies have their old path in `from` and the new one in `to`.
+fn parse_status(porcelain: &str) -> Value {
+    let (mut staged, mut unstaged, mut untracked) = (Vec::new(), Vec::new(), Vec::new());
+    for line in porcelain.lines() {
+        let (Some(code), Some(path)) = (line.get(..2), line.get(3..)) else {
+            continue;
+        };
+        let mut flags = code.chars();
+        let (index, worktree) = (flags.next().unwrap_or(' '), flags.next().unwrap_or(' '));
+
+        if code == "??" {
+            untracked.push(json!(path));
+            continue;
+        }
+        let renamed = path
+            .split_once(" -> ")
+            .filter(|_| matches!(index, 'R' | 'C'));
+        let path = renamed.map_or(path, |(_, to)| to);
+        match renamed {
+            Some((from, to)) => {
+                staged.push(json!({"from": from, "to": to, "status": index.to_string()}));
+            }
+            None if index != ' ' => {
+                staged.push(json!({"path": path, "status": index.to_string()}));
+            }
+            None => {}
+        }
+        if worktree != ' ' {
+            unstaged.push(json!({"path": path, "status": worktree.to_string()}));
+        }
+    }
+
+    json!({"staged": staged, "unstaged": unstaged, "untracked": untracked})
+}
+
+#[async_trait]
+impl Tool for GitInspector {
+    fn name(&self) -> String {
+        "git_inspector".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Inspect the git repository in the current directory: uncommitted changes or recent commits"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["status", "diff", "log"],
+                    "description": "'status' lists changed files, 'diff' shows uncommitted changes, 'log' lists recent commits"
+                },
+                "count": {
+                    "type": "integer",
+                    "description": "Number of commits returned by 'log', 10 by default"
+                }
+            },
+            "required": ["action"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let action = args.get_str("action")?;
+        let response = match action {
+            "status" => git(&["status", "--porcelain"])
+                .await
+                .map(|out| parse_status(&out)),
+            "diff" => {
+                // Before the first commit there's no HEAD, the staged files are all there is.
+                let base = match git(&["rev-parse", "--verify", "--quiet", "HEAD"]).await {
+                    Ok(_) => "HEAD",
+                    Err(_) => "--cached",
+                };
+                git(&["diff", base]).await.map(|diff| {
+                    let truncated = diff.len() > MAX_DIFF_BYTES;
+                    let mut end = diff.len().min(MAX_DIFF_BYTES);
+                    while !diff.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    json!({"diff": &diff[..end], "truncated": truncated})
+                })
+            }
+            "log" => {
+                let count = args
+                    .opt_u64("count")?
+                    .unwrap_or(DEFAULT_LOG_COUNT)
+                    .to_string();
+                git(&["log", "-n", &count, "--format=%h %s"])
+                    .await
+                    .map(|log| {
+                        let commits: Vec<Value> = log
+                            .lines()
+                            .filter_map(|line| line.split_once(' '))
+                            .map(|(hash, subject)| json!({"hash": hash, "subject": subject}))
+                            .collect();
+                        json!({ "commits": commits })
+                    })
+            }
+            other => Err(format!(
+                "unsupported action '{other}', expected one of: status, diff, log"
+            )),
+        };
+
+        Ok(response
+            .unwrap_or_else(|error| json!({ "error": error }))
+            .to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_lines_are_sorted_by_state() {
+        let porcelain = "M  src/staged.rs\n M src/unstaged.rs\nMM src/both.rs\nA  src/new.rs\n\
+                         ?? notes.txt\nR  src/old.rs -> src/renamed.rs\n";
+        assert_eq!(
+            parse_status(porcelain),
+            json!({
+                "staged": [
+                    {"path": "src/staged.rs", "status": "M"},
+                    {"path": "src/both.rs", "status": "M"},
+                    {"path": "src/new.rs", "status": "A"},
+                    {"from": "src/old.rs", "to": "src/renamed.rs", "status": "R"}
+                ],
+                "unstaged": [
+                    {"path": "src/unstaged.rs", "status": "M"},
+                    {"path": "src/both.rs", "status": "M"}
+                ],
+                "untracked": ["notes.txt"]
+            })
+        );
+    }
+
+    #[test]
+    fn renames_changed_since_staging_keep_both_paths() {
+        let status = parse_status("RM a.rs -> b.rs\n");
+        assert_eq!(
+            status["staged"],
+            json!([{"from": "a.rs", "to": "b.rs", "status": "R"}])
+        );
+        assert_eq!(status["unstaged"], json!([{"path": "b.rs", "status": "M"}]));
+    }
+}
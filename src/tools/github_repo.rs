@@ -0,0 +1,323 @@
+use std::{error::Error, time::Duration};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use reqwest::{
+    header::{ACCEPT, AUTHORIZATION},
+    Response, StatusCode,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use super::http_fetch::{truncate_chars, USER_AGENT};
+
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GitHubRepoConfig {
+    /// A personal access token, raising the API's rate limit from 60 requests an hour
+    /// and giving access to private repositories it can read.
+    pub token: Option<String>,
+    /// The README is cut down to this many characters.
+    pub max_readme_chars: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for GitHubRepoConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            max_readme_chars: 6000,
+            timeout_secs: 15,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Repo {
+    full_name: String,
+    description: Option<String>,
+    html_url: String,
+    homepage: Option<String>,
+    language: Option<String>,
+    stargazers_count: u64,
+    forks_count: u64,
+    open_issues_count: u64,
+    license: Option<License>,
+    #[serde(default)]
+    topics: Vec<String>,
+    default_branch: String,
+    archived: bool,
+    pushed_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct License {
+    spdx_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    name: Option<String>,
+    published_at: Option<String>,
+    html_url: String,
+}
+
+/// Owner and repository names as GitHub allows them, so they can't change the API path.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+}
+
+/// The error for a 403 or 429 answer: either the rate limit is used up, which the
+/// `x-ratelimit-remaining` header tells, or access is denied.
+fn forbidden_error(remaining: Option<&str>, reset: Option<&str>, has_token: bool) -> String {
+    if remaining != Some("0") {
+        return "GitHub denied access to the repository".to_string();
+    }
+    let reset = reset
+        .and_then(|reset| reset.parse().ok())
+        .and_then(|reset| chrono::DateTime::from_timestamp(reset, 0))
+        .map(|reset| format!(" until {}", reset.format("%H:%M UTC")))
+        .unwrap_or_default();
+    let hint = if has_token {
+        ""
+    } else {
+        ", set a token under [tools.github_repo] to raise the limit"
+    };
+    format!("GitHub's API rate limit is used up{reset}{hint}")
+}
+
+fn repo_summary(repo: Repo, release: Option<Release>, readme: Option<(&str, bool)>) -> Value {
+    json!({
+        "name": repo.full_name,
+        "description": repo.description,
+        "url": repo.html_url,
+        "homepage": repo.homepage.filter(|homepage| !homepage.is_empty()),
+        "language": repo.language,
+        "stars": repo.stargazers_count,
+        "forks": repo.forks_count,
+        "open_issues": repo.open_issues_count,
+        "license": repo.license.and_then(|license| license.spdx_id),
+        "topics": repo.topics,
+        "default_branch": repo.default_branch,
+        "archived": repo.archived,
+        "last_push": repo.pushed_at,
+        "latest_release": release.map(|release| json!({
+            "tag": release.tag_name,
+            "name": release.name,
+            "published_at": release.published_at,
+            "url": release.html_url,
+        })),
+        "readme": readme.map(|(readme, _)| readme),
+        "readme_truncated": readme.is_some_and(|(_, truncated)| truncated),
+    })
+}
+
+pub struct GitHubRepo {
+    client: reqwest::Client,
+    config: GitHubRepoConfig,
+}
+
+impl GitHubRepo {
+    pub fn new(config: GitHubRepoConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .unwrap_or_default();
+        Self { client, config }
+    }
+
+    /// GETs `path` from the API. A missing resource is `Ok(None)`, any other failure an
+    /// error for the model.
+    async fn get(&self, path: &str, accept: &str) -> Result<Option<Response>, String> {
+        let url = format!("{GITHUB_API_URL}{path}");
+        tracing::debug!(url, "requesting GitHub");
+        let mut request = self
+            .client
+            .get(&url)
+            .header(ACCEPT, accept)
+            .header("X-GitHub-Api-Version", "2022-11-28");
+        if let Some(token) = &self.config.token {
+            request = request.header(AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("failed to reach GitHub: {e}"))?;
+
+        match response.status() {
+            status if status.is_success() => Ok(Some(response)),
+            StatusCode::NOT_FOUND => Ok(None),
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => {
+                let header = |name| {
+                    response
+                        .headers()
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                };
+                Err(forbidden_error(
+                    header("x-ratelimit-remaining"),
+                    header("x-ratelimit-reset"),
+                    self.config.token.is_some(),
+                ))
+            }
+            StatusCode::UNAUTHORIZED => Err("GitHub rejected the configured token".to_string()),
+            status => Err(format!("GitHub responded with {status}")),
+        }
+    }
+
+    async fn lookup(&self, owner: &str, repo: &str) -> Result<Value, String> {
+        for name in [owner, repo] {
+            if !valid_name(name) {
+                return Err(format!("invalid owner or repository name '{name}'"));
+            }
+        }
+        let path = format!("/repos/{owner}/{repo}");
+        let releases = format!("{path}/releases/latest");
+        let readme = format!("{path}/readme");
+        let (metadata, release, readme) = tokio::join!(
+            self.get(&path, "application/vnd.github+json"),
+            self.get(&releases, "application/vnd.github+json"),
+            self.get(&readme, "application/vnd.github.raw+json"),
+        );
+
+        let Some(metadata) = metadata? else {
+            return Err(format!("repository {owner}/{repo} not found"));
+        };
+        let metadata = metadata.text().await.map_err(|e| e.to_string())?;
+        let metadata: Repo = serde_json::from_str(&metadata)
+            .map_err(|e| format!("unexpected answer from GitHub: {e}"))?;
+        // The repository exists, so the release and README are optional extras.
+        let release = match release {
+            Ok(Some(release)) => release
+                .text()
+                .await
+                .ok()
+                .and_then(|release| serde_json::from_str(&release).ok()),
+            _ => None,
+        };
+        let readme = match readme {
+            Ok(Some(readme)) => readme.text().await.ok(),
+            _ => None,
+        };
+        let readme = readme
+            .as_deref()
+            .map(|readme| truncate_chars(readme, self.config.max_readme_chars));
+        Ok(repo_summary(metadata, release, readme))
+    }
+}
+
+#[async_trait]
+impl Tool for GitHubRepo {
+    fn name(&self) -> String {
+        "github_repo".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Look up a GitHub repository: its description, stars, language, license, latest release and README"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "owner": {
+                    "type": "string",
+                    "description": "The user or organization owning the repository, e.g. 'tokio-rs'"
+                },
+                "repo": {
+                    "type": "string",
+                    "description": "The repository name, e.g. 'tokio'"
+                }
+            },
+            "required": ["owner", "repo"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let owner = args.get_str("owner")?;
+        let repo = args.get_str("repo")?;
+        let response = self
+            .lookup(owner.trim(), repo.trim())
+            .await
+            .unwrap_or_else(|error| json!({ "error": error }));
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_cannot_change_the_path() {
+        assert!(valid_name("tokio-rs"));
+        assert!(valid_name("serde_json.rs"));
+        assert!(!valid_name("tokio/../../users"));
+        assert!(!valid_name(".."));
+        assert!(!valid_name(""));
+    }
+
+    #[test]
+    fn rate_limits_are_told_apart_from_denied_access() {
+        assert_eq!(
+            forbidden_error(Some("0"), Some("1700000000"), false),
+            "GitHub's API rate limit is used up until 22:13 UTC, \
+             set a token under [tools.github_repo] to raise the limit"
+        );
+        assert_eq!(
+            forbidden_error(Some("0"), None, true),
+            "GitHub's API rate limit is used up"
+        );
+        assert_eq!(
+            forbidden_error(Some("42"), None, false),
+            "GitHub denied access to the repository"
+        );
+    }
+
+    #[test]
+    fn repos_are_summarized() {
+        let repo: Repo = serde_json::from_value(json!({
+            "full_name": "tokio-rs/tokio",
+            "description": "A runtime for writing reliable asynchronous applications with Rust.",
+            "html_url": "https://github.com/tokio-rs/tokio",
+            "homepage": "",
+            "language": "Rust",
+            "stargazers_count": 28000,
+            "forks_count": 2500,
+            "open_issues_count": 300,
+            "license": {"key": "mit", "spdx_id": "MIT"},
+            "topics": ["async", "rust"],
+            "default_branch": "master",
+            "archived": false,
+            "pushed_at": "2024-12-01T10:00:00Z"
+        }))
+        .unwrap();
+        let release: Release = serde_json::from_value(json!({
+            "tag_name": "tokio-1.42.0",
+            "name": "Tokio v1.42.0",
+            "published_at": "2024-12-03T00:00:00Z",
+            "html_url": "https://github.com/tokio-rs/tokio/releases/tag/tokio-1.42.0"
+        }))
+        .unwrap();
+
+        let summary = repo_summary(repo, Some(release), Some(("# Tokio", false)));
+        assert_eq!(summary["stars"], 28000);
+        assert_eq!(summary["license"], "MIT");
+        assert_eq!(summary["homepage"], Value::Null);
+        assert_eq!(summary["latest_release"]["tag"], "tokio-1.42.0");
+        assert_eq!(summary["readme"], "# Tokio");
+        assert_eq!(summary["readme_truncated"], false);
+    }
+}
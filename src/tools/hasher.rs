@@ -0,0 +1,195 @@
+use std::{error::Error, path::PathBuf};
+
+use async_trait::async_trait;
+use md5::Md5;
+use ollama_rs::generation::functions::tools::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+
+use super::args::Args;
+use super::file_reader::resolve_within;
+
+const ALGORITHMS: [&str; 3] = ["sha256", "md5", "blake3"];
+
+/// Files are hashed in pieces of this size instead of being read whole.
+const READ_BUFFER_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HasherConfig {
+    /// Only files inside this directory can be hashed.
+    pub root: PathBuf,
+}
+
+impl Default for HasherConfig {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("."),
+        }
+    }
+}
+
+/// Running state of one of the supported algorithms.
+enum HashState {
+    Sha256(Sha256),
+    Md5(Md5),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl HashState {
+    fn new(algorithm: &str) -> Option<Self> {
+        match algorithm {
+            "sha256" => Some(Self::Sha256(Sha256::new())),
+            "md5" => Some(Self::Md5(Md5::new())),
+            "blake3" => Some(Self::Blake3(Box::default())),
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(bytes),
+            Self::Md5(hasher) => hasher.update(bytes),
+            Self::Blake3(hasher) => {
+                hasher.update(bytes);
+            }
+        }
+    }
+
+    fn hex_digest(self) -> String {
+        let bytes = match self {
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+            Self::Md5(hasher) => hasher.finalize().to_vec(),
+            Self::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+        };
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
+
+pub struct Hasher {
+    config: HasherConfig,
+}
+
+impl Hasher {
+    pub fn new(config: HasherConfig) -> Self {
+        Self { config }
+    }
+
+    async fn hash_file(&self, mut state: HashState, path: &str) -> Result<String, String> {
+        let resolved = resolve_within(&self.config.root, path)?;
+        let mut file = tokio::fs::File::open(&resolved)
+            .await
+            .map_err(|e| format!("cannot open '{path}': {e}"))?;
+        let mut buffer = vec![0; READ_BUFFER_BYTES];
+        loop {
+            let read = file
+                .read(&mut buffer)
+                .await
+                .map_err(|e| format!("cannot read '{path}': {e}"))?;
+            if read == 0 {
+                return Ok(state.hex_digest());
+            }
+            state.update(&buffer[..read]);
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for Hasher {
+    fn name(&self) -> String {
+        "hasher".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Compute the sha256, md5 or blake3 checksum of a string or a local file".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "algorithm": {
+                    "type": "string",
+                    "enum": ALGORITHMS,
+                    "description": "The hash algorithm to use"
+                },
+                "input": {
+                    "type": "string",
+                    "description": "Text to hash, leave out when hashing a file"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Path of a file to hash, relative to the working directory"
+                }
+            },
+            "required": ["algorithm"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let algorithm = args.get_str("algorithm")?;
+        let Some(state) = HashState::new(algorithm) else {
+            let error = format!(
+                "unsupported algorithm '{algorithm}', expected one of: {}",
+                ALGORITHMS.join(", ")
+            );
+            return Ok(json!({ "error": error }).to_string());
+        };
+
+        let response = match (args.opt_str("input")?, args.opt_str("path")?) {
+            (Some(text), None) => {
+                let mut state = state;
+                state.update(text.as_bytes());
+                json!({"algorithm": algorithm, "digest": state.hex_digest()})
+            }
+            (None, Some(path)) => match self.hash_file(state, path).await {
+                Ok(digest) => json!({"algorithm": algorithm, "path": path, "digest": digest}),
+                Err(error) => json!({ "error": error }),
+            },
+            _ => json!({"error": "pass either 'input' or 'path'"}),
+        };
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(algorithm: &str, text: &str) -> String {
+        let mut state = HashState::new(algorithm).unwrap();
+        state.update(text.as_bytes());
+        state.hex_digest()
+    }
+
+    #[test]
+    fn digests_match_known_values() {
+        assert_eq!(
+            digest("sha256", "abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(digest("md5", "abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            digest("blake3", "abc"),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+        assert!(HashState::new("sha1").is_none());
+    }
+
+    #[tokio::test]
+    async fn files_hash_like_their_contents() {
+        let hasher = Hasher::new(HasherConfig::default());
+        let contents = std::fs::read_to_string("Cargo.toml").unwrap();
+
+        let state = HashState::new("sha256").unwrap();
+        assert_eq!(
+            hasher.hash_file(state, "Cargo.toml").await.unwrap(),
+            digest("sha256", &contents)
+        );
+        let state = HashState::new("sha256").unwrap();
+        assert!(hasher.hash_file(state, "missing.txt").await.is_err());
+    }
+}
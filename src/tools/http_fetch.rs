@@ -0,0 +1,360 @@
+use std::{
+    error::Error,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect, Response, Url,
+};
+use scraper::{Html, Node, Selector};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+
+pub const USER_AGENT: &str = concat!("shark/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HttpFetchConfig {
+    /// Hosts that may be fetched, including their subdomains. Empty allows any host.
+    pub allowed_hosts: Vec<String>,
+    /// Hosts that are never fetched, including their subdomains.
+    pub blocked_hosts: Vec<String>,
+    /// Refuse IP addresses that don't belong to the public internet, like loopback,
+    /// link-local or private network addresses, whether they are written in the url or
+    /// a hostname resolves to them.
+    pub block_private_ips: bool,
+    pub max_chars: usize,
+    /// Pages are read up to this many bytes before their text is extracted.
+    pub max_body_bytes: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for HttpFetchConfig {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: Vec::new(),
+            blocked_hosts: vec!["localhost".to_string(), "127.0.0.1".to_string()],
+            block_private_ips: true,
+            max_chars: 8000,
+            max_body_bytes: 2 * 1024 * 1024,
+            timeout_secs: 15,
+        }
+    }
+}
+
+pub struct HttpFetch {
+    client: reqwest::Client,
+    config: HttpFetchConfig,
+}
+
+const MAX_REDIRECTS: usize = 10;
+
+impl HttpFetch {
+    pub fn new(config: HttpFetchConfig) -> Self {
+        // Every redirect is checked too, or an allowed host could send shark anywhere.
+        let redirects = config.clone();
+        let policy = redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            match check_url(&redirects, attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(error) => attempt.error(error),
+            }
+        });
+        let mut client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .redirect(policy);
+        if config.block_private_ips {
+            client = client.dns_resolver(Arc::new(PublicResolver));
+        }
+        Self {
+            client: client.build().unwrap_or_default(),
+            config,
+        }
+    }
+}
+
+/// Resolves hostnames to their public addresses only, so that a name pointing at
+/// `127.0.0.1` or `169.254.169.254` can't reach what [`check_url`] refuses as an IP.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, 0))
+                .await?
+                .filter(|addr| !is_private(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} only resolves to private addresses").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Whether `ip` is reachable only from this machine or its network, e.g. a router's admin
+/// page or a cloud metadata service.
+fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_private_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_private_v4(ip),
+            None => is_private_v6(ip),
+        },
+    }
+}
+
+fn is_private_v4(ip: Ipv4Addr) -> bool {
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        // Carrier-grade NAT, 100.64.0.0/10.
+        || (ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64)
+}
+
+fn is_private_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        // Unique local, fc00::/7, and link-local, fe80::/10, addresses.
+        || first & 0xfe00 == 0xfc00
+        || first & 0xffc0 == 0xfe80
+}
+
+/// Checks `url` against the scheme, the host lists and, for IP addresses, whether they are
+/// private.
+fn check_url(config: &HttpFetchConfig, url: &Url) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!(
+            "only http and https urls can be fetched, got '{url}'"
+        ));
+    }
+
+    let host = url.host_str().unwrap_or_default();
+    let matches = |entry: &String| host == entry || host.ends_with(&format!(".{entry}"));
+    if config.blocked_hosts.iter().any(matches) {
+        return Err(format!("fetching from {host} is blocked"));
+    }
+    // IPv6 hosts are written in brackets, like `[::1]`.
+    let ip = host.trim_start_matches('[').trim_end_matches(']').parse();
+    if config.block_private_ips && ip.is_ok_and(is_private) {
+        return Err(format!(
+            "fetching from the private address {host} is blocked"
+        ));
+    }
+    if !config.allowed_hosts.is_empty() && !config.allowed_hosts.iter().any(matches) {
+        return Err(format!("fetching from {host} is not allowed"));
+    }
+    Ok(())
+}
+
+/// Reads the body of `response`, stopping after `max_bytes`.
+async fn read_body(mut response: Response, max_bytes: usize) -> reqwest::Result<String> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        let room = max_bytes - body.len();
+        body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        if body.len() == max_bytes {
+            break;
+        }
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Returns the page title and the readable text of its body, skipping scripts and styles.
+pub fn html_to_text(html: &str) -> (Option<String>, String) {
+    let document = Html::parse_document(html);
+    let title = Selector::parse("title")
+        .ok()
+        .and_then(|selector| document.select(&selector).next())
+        .map(|title| title.text().collect::<String>().trim().to_string());
+
+    let root = Selector::parse("body")
+        .ok()
+        .and_then(|selector| document.select(&selector).next())
+        .unwrap_or_else(|| document.root_element());
+
+    let mut text = String::new();
+    for node in root.descendants() {
+        let Node::Text(chunk) = node.value() else {
+            continue;
+        };
+        let hidden = node
+            .parent()
+            .and_then(|parent| parent.value().as_element().map(|e| e.name()))
+            .is_some_and(|name| matches!(name, "script" | "style" | "noscript" | "template"));
+        if hidden {
+            continue;
+        }
+        for word in chunk.split_whitespace() {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(word);
+        }
+    }
+
+    (title, text)
+}
+
+/// Cuts `text` down to at most `max_chars` characters.
+pub fn truncate_chars(text: &str, max_chars: usize) -> (&str, bool) {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => (&text[..end], true),
+        None => (text, false),
+    }
+}
+
+#[async_trait]
+impl Tool for HttpFetch {
+    fn name(&self) -> String {
+        "http_fetch".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Fetch a web page by url and return its title and readable text".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "The http or https url of the page to read"
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let url = Args::new(&input).get_str("url")?;
+
+        let url = match Url::parse(url) {
+            Ok(url) => url,
+            Err(e) => return Ok(json!({"error": format!("invalid url '{url}': {e}")}).to_string()),
+        };
+        if let Err(error) = check_url(&self.config, &url) {
+            return Ok(json!({ "error": error }).to_string());
+        }
+
+        tracing::debug!(%url, "fetching page");
+        let response = match self.client.get(url.clone()).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                // A refused redirect or address only says why in the error's innermost source.
+                let mut source = e.source();
+                while let Some(inner) = source.and_then(Error::source) {
+                    source = Some(inner);
+                }
+                let reason = source.map(|source| format!(": {source}"));
+                let error = format!("failed to fetch {url}: {e}{}", reason.unwrap_or_default());
+                return Ok(json!({ "error": error }).to_string());
+            }
+        };
+        let status = response.status();
+        if !status.is_success() {
+            return Ok(json!({"error": format!("{url} responded with {status}")}).to_string());
+        }
+
+        let body = read_body(response, self.config.max_body_bytes).await?;
+        let (title, text) = html_to_text(&body);
+        let (text, truncated) = truncate_chars(&text, self.config.max_chars);
+
+        Ok(json!({
+            "url": url.as_str(),
+            "title": title,
+            "text": text,
+            "truncated": truncated,
+        })
+        .to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(url: &str) -> Result<(), String> {
+        check_url(&HttpFetchConfig::default(), &Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn public_urls_are_allowed() {
+        assert_eq!(check("https://docs.rs/tokio"), Ok(()));
+        assert_eq!(check("http://93.184.216.34/"), Ok(()));
+        assert_eq!(check("http://[2606:2800:220:1::]/"), Ok(()));
+    }
+
+    #[test]
+    fn local_and_private_addresses_are_blocked() {
+        for url in [
+            "http://localhost:8080/",
+            "http://api.localhost/",
+            "http://127.0.0.1/",
+            "http://127.1.2.3/",
+            "http://[::1]/",
+            "http://0.0.0.0/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.1/",
+            "http://192.168.1.1/",
+            "http://172.16.0.1/",
+            "http://100.64.0.1/",
+            "http://[fe80::1]/",
+            "http://[fd00::1]/",
+            "http://[::ffff:127.0.0.1]/",
+        ] {
+            assert!(check(url).unwrap_err().contains("blocked"), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn hostnames_of_private_addresses_are_not_resolved() {
+        let Err(error) = PublicResolver.resolve("localhost".parse().unwrap()).await else {
+            panic!("localhost resolved");
+        };
+        assert!(error.to_string().contains("private addresses"), "{error}");
+    }
+
+    #[test]
+    fn schemes_and_host_lists_are_enforced() {
+        assert!(check("file:///etc/passwd")
+            .unwrap_err()
+            .contains("only http"));
+
+        let config = HttpFetchConfig {
+            allowed_hosts: vec!["docs.rs".to_string()],
+            ..HttpFetchConfig::default()
+        };
+        let check = |url| check_url(&config, &Url::parse(url).unwrap());
+        assert_eq!(check("https://api.docs.rs/"), Ok(()));
+        assert!(check("https://example.com/")
+            .unwrap_err()
+            .contains("not allowed"));
+    }
+
+    #[test]
+    fn private_addresses_can_be_allowed() {
+        let config = HttpFetchConfig {
+            blocked_hosts: Vec::new(),
+            block_private_ips: false,
+            ..HttpFetchConfig::default()
+        };
+        let url = Url::parse("http://192.168.1.1/").unwrap();
+        assert_eq!(check_url(&config, &url), Ok(()));
+    }
+}
@@ -0,0 +1,224 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde_json::{json, Value};
+
+use super::args::{ArgError, Args};
+
+const OPERATIONS: [&str; 3] = ["validate", "format", "minify"];
+
+#[derive(Default)]
+pub struct JsonTool {}
+
+/// Parses `input`, reporting where it stops being valid JSON.
+fn parse(input: &str) -> Result<Value, Value> {
+    serde_json::from_str(input).map_err(|e| {
+        let message = e.to_string();
+        let message = match message.rfind(" at line ") {
+            Some(end) => message[..end].to_string(),
+            None => message,
+        };
+        json!({"message": message, "line": e.line(), "column": e.column()})
+    })
+}
+
+/// One step of a path: an object key or an array index, negative from the end.
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(i64),
+}
+
+/// Parses a jq-style path like `.items[0].name` or `.["a key"][-1]`.
+fn parse_path(path: &str) -> Result<Vec<Segment>, String> {
+    let invalid = |reason: &str| format!("invalid path '{path}': {reason}");
+    let mut segments = Vec::new();
+    let mut rest = path.trim();
+    if rest.is_empty() || rest == "." {
+        return Ok(segments);
+    }
+    if !rest.starts_with(['.', '[']) {
+        return Err(invalid("it must start with '.'"));
+    }
+
+    while !rest.is_empty() {
+        if let Some(bracket) = rest.strip_prefix('[').or_else(|| rest.strip_prefix(".[")) {
+            let end = if let Some(quoted) = bracket.strip_prefix('"') {
+                quoted.find('"').ok_or_else(|| invalid("unclosed quote"))? + 2
+            } else {
+                bracket.find(']').ok_or_else(|| invalid("unclosed '['"))?
+            };
+            let inner = &bracket[..end];
+            rest = bracket[end..]
+                .strip_prefix(']')
+                .ok_or_else(|| invalid("expected ']'"))?;
+            segments.push(match inner.strip_prefix('"') {
+                Some(key) => Segment::Key(key.trim_end_matches('"').to_string()),
+                None => Segment::Index(
+                    inner
+                        .trim()
+                        .parse()
+                        .map_err(|_| invalid(&format!("'{inner}' is not an index")))?,
+                ),
+            });
+        } else if let Some(key) = rest.strip_prefix('.') {
+            let end = key.find(['.', '[']).unwrap_or(key.len());
+            if end == 0 {
+                return Err(invalid("empty key"));
+            }
+            segments.push(Segment::Key(key[..end].to_string()));
+            rest = &key[end..];
+        } else {
+            return Err(invalid(&format!("unexpected '{rest}'")));
+        }
+    }
+    Ok(segments)
+}
+
+/// The value at `path` inside `value`.
+fn select<'a>(value: &'a Value, path: &str) -> Result<&'a Value, String> {
+    let mut current = value;
+    for segment in parse_path(path)? {
+        current = match (&segment, current) {
+            (Segment::Key(key), Value::Object(fields)) => fields.get(key),
+            (Segment::Index(index), Value::Array(items)) => {
+                let index = if *index < 0 {
+                    items.len() as i64 + index
+                } else {
+                    *index
+                };
+                usize::try_from(index).ok().and_then(|i| items.get(i))
+            }
+            (Segment::Key(key), _) => {
+                return Err(format!("cannot read key '{key}' of a non-object"));
+            }
+            (Segment::Index(index), _) => {
+                return Err(format!("cannot read index {index} of a non-array"));
+            }
+        }
+        .ok_or_else(|| format!("nothing at path '{path}'"))?;
+    }
+    Ok(current)
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[async_trait]
+impl Tool for JsonTool {
+    fn name(&self) -> String {
+        "json_tool".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Validate, pretty-print or minify JSON, optionally extracting a value by path".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": OPERATIONS,
+                    "description": "Check that the input is valid JSON, pretty-print it or minify it"
+                },
+                "input": {
+                    "type": "string",
+                    "description": "The JSON text"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "jq-style path of the value to use instead of the whole document, e.g. '.items[0].name'"
+                }
+            },
+            "required": ["operation", "input"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let operation = args.get_str("operation")?;
+        let text = args
+            .opt_str("input")?
+            .ok_or_else(|| ArgError("missing 'input' argument".to_string()))?;
+        let path = args.opt_str("path")?;
+
+        if !OPERATIONS.contains(&operation) {
+            let error = format!(
+                "unsupported operation '{operation}', expected one of: {}",
+                OPERATIONS.join(", ")
+            );
+            return Ok(json!({ "error": error }).to_string());
+        }
+        let document = match parse(text) {
+            Ok(document) => document,
+            Err(error) if operation == "validate" => {
+                return Ok(json!({"valid": false, "error": error}).to_string());
+            }
+            Err(error) => return Ok(json!({ "error": error }).to_string()),
+        };
+        let value = match path.map_or(Ok(&document), |path| select(&document, path)) {
+            Ok(value) => value,
+            Err(error) => return Ok(json!({ "error": error }).to_string()),
+        };
+
+        let response = match operation {
+            "validate" => json!({"valid": true, "type": type_name(value)}),
+            "format" => json!({"output": serde_json::to_string_pretty(value)?}),
+            _ => json!({"output": serde_json::to_string(value)?}),
+        };
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_errors_have_a_position() {
+        let error = parse("{\n  \"a\": 1,\n  \"b\": x\n}").unwrap_err();
+        assert_eq!(error["line"], 3);
+        assert_eq!(error["column"], 8);
+        assert_eq!(error["message"], "expected value");
+        assert!(parse("[1, 2]").is_ok());
+    }
+
+    #[test]
+    fn paths_select_nested_values() {
+        let document = json!({"items": [{"name": "a"}, {"name": "b"}], "a key": {"x": true}});
+        assert_eq!(select(&document, ".").unwrap(), &document);
+        assert_eq!(select(&document, ".items[1].name").unwrap(), "b");
+        assert_eq!(select(&document, ".items[-2].name").unwrap(), "a");
+        assert_eq!(select(&document, ".[\"a key\"].x").unwrap(), true);
+        assert_eq!(
+            select(&document, ".items[5]").unwrap_err(),
+            "nothing at path '.items[5]'"
+        );
+        assert!(select(&document, ".items.name").is_err());
+        assert!(select(&document, "items").is_err());
+        assert!(select(&document, ".items[x]").is_err());
+        assert!(select(&document, ".items[0").is_err());
+    }
+
+    #[tokio::test]
+    async fn formatting_keeps_key_order() {
+        let tool = JsonTool::default();
+        let output = tool
+            .run(json!({"operation": "minify", "input": "{ \"z\": 1,\n \"a\": [ 2 ] }"}))
+            .await
+            .unwrap();
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["output"], r#"{"z":1,"a":[2]}"#);
+    }
+}
@@ -0,0 +1,212 @@
+use std::{error::Error, time::Duration};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::process::Command;
+
+use super::args::Args;
+use super::http_fetch::truncate_chars;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ManPageConfig {
+    /// The description is cut down to this many characters.
+    pub max_chars: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for ManPageConfig {
+    fn default() -> Self {
+        Self {
+            max_chars: 4000,
+            timeout_secs: 10,
+        }
+    }
+}
+
+/// Page names like `git-commit` or `g++`, so the name can't be taken for an option.
+fn valid_command(command: &str) -> bool {
+    !command.is_empty()
+        && !command.starts_with('-')
+        && command
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._+-:".contains(c))
+}
+
+/// Sections like `1`, `3p` or `n`.
+fn valid_section(section: &str) -> bool {
+    !section.is_empty() && section.len() <= 8 && section.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Drops the backspace overstrikes man uses for bold and underlined text, like `col -b`.
+fn strip_overstrike(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\u{8}' {
+            plain.pop();
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// The body of the section headed `name`, e.g. `SYNOPSIS`, with the indentation removed.
+/// Headings are the lines that start at the left margin.
+fn section(page: &str, name: &str) -> Option<String> {
+    let mut lines = page.lines().skip_while(|line| line.trim_end() != name);
+    lines.next()?;
+    let body: Vec<&str> = lines
+        .take_while(|line| line.is_empty() || line.starts_with(char::is_whitespace))
+        .map(str::trim)
+        .collect();
+    let body = body.join("\n").trim().to_string();
+    Some(body)
+}
+
+pub struct ManPage {
+    config: ManPageConfig,
+}
+
+impl ManPage {
+    pub fn new(config: ManPageConfig) -> Self {
+        Self { config }
+    }
+
+    async fn read(&self, command: &str, section_number: Option<&str>) -> Result<Value, String> {
+        if !valid_command(command) {
+            return Err(format!("invalid command name '{command}'"));
+        }
+        if let Some(section) = section_number.filter(|section| !valid_section(section)) {
+            return Err(format!("invalid man page section '{section}'"));
+        }
+
+        tracing::debug!(command, section = section_number, "reading man page");
+        let mut man = Command::new("man");
+        man.args(section_number)
+            .arg(command)
+            .env("MANPAGER", "cat")
+            .env("MANWIDTH", "80")
+            .env("GROFF_NO_SGR", "1")
+            .kill_on_drop(true);
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let output = match tokio::time::timeout(timeout, man.output()).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => return Err(format!("failed to run man: {e}")),
+            Err(_) => return Err(format!("man timed out after {}s", timeout.as_secs())),
+        };
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let error = match stderr.trim() {
+                "" => format!("no man page for '{command}'"),
+                stderr => stderr.to_string(),
+            };
+            return Err(error);
+        }
+
+        let page = strip_overstrike(&String::from_utf8_lossy(&output.stdout));
+        let description = section(&page, "DESCRIPTION").unwrap_or_default();
+        let (description, truncated) = truncate_chars(&description, self.config.max_chars);
+        Ok(json!({
+            "command": command,
+            "section": section_number,
+            "name": section(&page, "NAME"),
+            "synopsis": section(&page, "SYNOPSIS"),
+            "description": description,
+            "truncated": truncated,
+        }))
+    }
+}
+
+#[async_trait]
+impl Tool for ManPage {
+    fn name(&self) -> String {
+        "man_page".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Read the man page of a command and return its synopsis and description".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": "The command or function to look up, e.g. 'tar' or 'printf'"
+                },
+                "section": {
+                    "type": "string",
+                    "description": "The manual section, e.g. '1' for commands or '3' for C functions"
+                }
+            },
+            "required": ["command"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let command = args.get_str("command")?;
+        let section = args.opt_str("section")?;
+        let response = self
+            .read(command, section)
+            .await
+            .unwrap_or_else(|error| json!({ "error": error }));
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "LS(1)                    User Commands                   LS(1)
+
+N\u{8}NA\u{8}AM\u{8}ME\u{8}E
+       ls - list directory contents
+
+SYNOPSIS
+       ls [_\u{8}O_\u{8}P_\u{8}T_\u{8}I_\u{8}O_\u{8}N]... [FILE]...
+
+DESCRIPTION
+       List information about the FILEs.
+
+       -a, --all
+              do not ignore entries starting with .
+
+AUTHOR
+       Written by Richard M. Stallman.
+";
+
+    #[test]
+    fn sections_are_parsed() {
+        let page = strip_overstrike(PAGE);
+        assert_eq!(
+            section(&page, "NAME").as_deref(),
+            Some("ls - list directory contents")
+        );
+        assert_eq!(
+            section(&page, "SYNOPSIS").as_deref(),
+            Some("ls [OPTION]... [FILE]...")
+        );
+        assert_eq!(
+            section(&page, "DESCRIPTION").as_deref(),
+            Some("List information about the FILEs.\n\n-a, --all\ndo not ignore entries starting with .")
+        );
+        assert_eq!(section(&page, "EXAMPLES"), None);
+    }
+
+    #[test]
+    fn names_cannot_pass_for_options() {
+        assert!(valid_command("git-commit"));
+        assert!(valid_command("g++"));
+        assert!(!valid_command("-k"));
+        assert!(!valid_command("ls; rm -rf /"));
+        assert!(!valid_command(""));
+        assert!(valid_section("3p"));
+        assert!(!valid_section("1 -k"));
+    }
+}
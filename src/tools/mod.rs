@@ -1 +1,514 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde::Deserialize;
+use serde_json::Value;
+
+use calculator::Calculator;
+use cargo_add::CargoAdd;
+use cargo_manifest::CargoManifest;
+use cargo_runner::{CargoRunner, CargoRunnerConfig};
+use clipboard::{Clipboard, ClipboardConfig};
+use code_search::{CodeSearch, CodeSearchConfig};
+use crates_io_search::{CratesIoSearch, CratesIoSearchConfig};
+use datetime::DateTimeTool;
+use ddg_searcher::{DdgSearcher, DdgSearcherConfig};
+use dir_list::{DirList, DirListConfig};
+use dns_lookup::{DnsLookup, DnsLookupConfig};
+use docs_rs_lookup::{DocsRsLookup, DocsRsLookupConfig};
+use document_loader::{DocumentLoader, DocumentLoaderConfig};
+use encoder::Encoder;
+use env_reader::{EnvReader, EnvReaderConfig};
+use feed_reader::{FeedReader, FeedReaderConfig};
+use file_reader::{FileReader, FileReaderConfig};
+use git_inspector::GitInspector;
+use github_repo::{GitHubRepo, GitHubRepoConfig};
+use hasher::{Hasher, HasherConfig};
+use http_fetch::{HttpFetch, HttpFetchConfig};
+use json_tool::JsonTool;
+#[cfg(unix)]
+use man_page::{ManPage, ManPageConfig};
+use process_list::{ProcessList, ProcessListConfig};
+use random_id::RandomId;
+use run_binary::{RunBinary, RunBinaryConfig};
+use rust_format::RustFormat;
+use rust_toolchain_list::RustToolchainList;
+use rust_toolchain_switcher::RustToolchainSwitcher;
+use shell_command::{ShellCommand, ShellCommandConfig};
+use sqlite_query::{SqliteQuery, SqliteQueryConfig};
+use system_info::SystemInfo;
+use translator::{Translator, TranslatorConfig};
+use unit_converter::{UnitConverter, UnitConverterConfig};
+use weather::{Weather, WeatherConfig};
+use wikipedia::{Wikipedia, WikipediaConfig};
+
+pub mod args;
+pub mod calculator;
+pub mod cargo_add;
+pub mod cargo_manifest;
+pub mod cargo_runner;
+pub mod clipboard;
+pub mod code_search;
+pub mod crates_io_search;
+pub mod datetime;
+pub mod ddg_searcher;
+pub mod dir_list;
+pub mod dns_lookup;
+pub mod docs_rs_lookup;
+pub mod document_loader;
+pub mod encoder;
+pub mod env_reader;
+pub mod feed_reader;
+pub mod file_reader;
+pub mod git_inspector;
+pub mod github_repo;
+pub mod hasher;
+pub mod http_fetch;
+pub mod json_tool;
+#[cfg(unix)]
+pub mod man_page;
+pub mod process_list;
+pub mod random_id;
+pub mod run_binary;
+pub mod rust_format;
+pub mod rust_toolchain_list;
 pub mod rust_toolchain_switcher;
+pub mod shell_command;
+pub mod sqlite_query;
+pub mod system_info;
+pub mod translator;
+pub mod unit_converter;
+pub mod weather;
+pub mod wikipedia;
+
+/// Per-tool settings, read from the `[tools.<name>]` tables of the config file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ToolsConfig {
+    pub ddg_searcher: DdgSearcherConfig,
+    pub shell_command: ShellCommandConfig,
+    pub run_binary: RunBinaryConfig,
+    pub cargo_runner: CargoRunnerConfig,
+    pub file_reader: FileReaderConfig,
+    pub dir_list: DirListConfig,
+    pub http_fetch: HttpFetchConfig,
+    pub crates_io_search: CratesIoSearchConfig,
+    pub docs_rs_lookup: DocsRsLookupConfig,
+    pub document_loader: DocumentLoaderConfig,
+    pub weather: WeatherConfig,
+    pub env_reader: EnvReaderConfig,
+    pub code_search: CodeSearchConfig,
+    pub sqlite_query: SqliteQueryConfig,
+    pub hasher: HasherConfig,
+    pub unit_converter: UnitConverterConfig,
+    pub wikipedia: WikipediaConfig,
+    pub feed_reader: FeedReaderConfig,
+    pub translator: TranslatorConfig,
+    pub process_list: ProcessListConfig,
+    pub dns_lookup: DnsLookupConfig,
+    pub clipboard: ClipboardConfig,
+    pub github_repo: GitHubRepoConfig,
+    #[cfg(unix)]
+    pub man_page: ManPageConfig,
+}
+
+/// An entry of the `functions` config list: a tool name, or a table that can also
+/// disable the tool, replace the description the model sees or mark it as destructive.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "FunctionEntry")]
+pub struct FunctionConfig {
+    pub name: String,
+    pub enabled: bool,
+    pub description_override: Option<String>,
+    /// Whether the tool changes the system, the registry's default when unset.
+    pub destructive: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FunctionEntry {
+    Name(String),
+    Table {
+        name: String,
+        #[serde(default = "enabled_by_default")]
+        enabled: bool,
+        description_override: Option<String>,
+        destructive: Option<bool>,
+    },
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl From<FunctionEntry> for FunctionConfig {
+    fn from(entry: FunctionEntry) -> Self {
+        match entry {
+            FunctionEntry::Name(name) => name.as_str().into(),
+            FunctionEntry::Table {
+                name,
+                enabled,
+                description_override,
+                destructive,
+            } => Self {
+                name,
+                enabled,
+                description_override,
+                destructive,
+            },
+        }
+    }
+}
+
+impl From<&str> for FunctionConfig {
+    fn from(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            enabled: true,
+            description_override: None,
+            destructive: None,
+        }
+    }
+}
+
+/// A tool presented to the model with another description.
+pub struct DescribedTool {
+    tool: Arc<dyn Tool>,
+    description: String,
+}
+
+impl DescribedTool {
+    pub fn new(tool: Arc<dyn Tool>, description: impl ToString) -> Self {
+        Self {
+            tool,
+            description: description.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for DescribedTool {
+    fn name(&self) -> String {
+        self.tool.name()
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    fn parameters(&self) -> Value {
+        self.tool.parameters()
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        self.tool.run(input).await
+    }
+}
+
+/// How `--explain-tools` shows a tool: its name and description, then a line per
+/// parameter with its type, whether it is required and what it is for.
+pub fn explain(tool: &dyn Tool, destructive: bool) -> String {
+    let mut text = tool.name();
+    if destructive {
+        text.push_str(" (destructive)");
+    }
+    text.push_str(&format!("\n  {}\n", tool.description()));
+
+    let parameters = tool.parameters();
+    let required: Vec<&str> = parameters["required"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .collect();
+    let Some(properties) = parameters["properties"]
+        .as_object()
+        .filter(|properties| !properties.is_empty())
+    else {
+        text.push_str("  no parameters\n");
+        return text;
+    };
+    text.push_str("  parameters:\n");
+    for (name, schema) in properties {
+        let kind = match &schema["type"] {
+            Value::String(kind) => kind.clone(),
+            Value::Array(kinds) => kinds
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(" | "),
+            _ => "any".to_string(),
+        };
+        let required = if required.contains(&name.as_str()) {
+            ", required"
+        } else {
+            ""
+        };
+        text.push_str(&format!("    {name} ({kind}{required})"));
+        if let Some(description) = schema["description"].as_str() {
+            text.push_str(&format!(": {description}"));
+        }
+        if let Some(values) = schema["enum"].as_array() {
+            let values: Vec<String> = values
+                .iter()
+                .map(|value| {
+                    value
+                        .as_str()
+                        .map_or_else(|| value.to_string(), str::to_string)
+                })
+                .collect();
+            text.push_str(&format!(", one of {}", values.join(", ")));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+pub type ToolFactory = Box<dyn Fn() -> Arc<dyn Tool> + Send + Sync>;
+
+/// Constructors for every tool that can be enabled through the `functions` config list.
+#[derive(Default)]
+pub struct ToolRegistry {
+    factories: HashMap<String, ToolFactory>,
+    /// Tools that change the system, which may need the user's confirmation to run.
+    destructive: HashSet<String>,
+}
+
+impl ToolRegistry {
+    /// Registry holding all the tools bundled with shark, configured from `config`.
+    pub fn with_builtin_tools(config: &ToolsConfig) -> Self {
+        let mut registry = Self::default();
+
+        registry.register_tool("rust_toolchain_switcher", || {
+            Arc::new(RustToolchainSwitcher::default())
+        });
+        registry.register_tool("rust_toolchain_list", || {
+            Arc::new(RustToolchainList::default())
+        });
+        registry.register_tool("cargo_add", || Arc::new(CargoAdd::default()));
+        registry.register_tool("cargo_manifest", || Arc::new(CargoManifest::default()));
+        registry.register_tool("rust_format", || Arc::new(RustFormat::default()));
+        registry.register_tool("calculator", || Arc::new(Calculator::default()));
+        registry.register_tool("git_inspector", || Arc::new(GitInspector::default()));
+        registry.register_tool("datetime", || Arc::new(DateTimeTool::default()));
+        registry.register_tool("encoder", || Arc::new(Encoder::default()));
+        registry.register_tool("json_tool", || Arc::new(JsonTool::default()));
+        registry.register_tool("system_info", || Arc::new(SystemInfo::default()));
+        registry.register_tool("random_id", || Arc::new(RandomId::default()));
+
+        let ddg_searcher = config.ddg_searcher.clone();
+        registry.register_tool("ddg_searcher", move || {
+            Arc::new(DdgSearcher::new(ddg_searcher.clone()))
+        });
+        let shell_command = config.shell_command.clone();
+        registry.register_tool("shell_command", move || {
+            Arc::new(ShellCommand::new(shell_command.clone()))
+        });
+        let cargo_runner = config.cargo_runner.clone();
+        registry.register_tool("cargo_runner", move || {
+            Arc::new(CargoRunner::new(cargo_runner.clone()))
+        });
+        let run_binary = config.run_binary.clone();
+        registry.register_tool("run_binary", move || {
+            Arc::new(RunBinary::new(run_binary.clone()))
+        });
+        let file_reader = config.file_reader.clone();
+        registry.register_tool("file_reader", move || {
+            Arc::new(FileReader::new(file_reader.clone()))
+        });
+        let dir_list = config.dir_list.clone();
+        registry.register_tool("dir_list", move || Arc::new(DirList::new(dir_list.clone())));
+        let http_fetch = config.http_fetch.clone();
+        registry.register_tool("http_fetch", move || {
+            Arc::new(HttpFetch::new(http_fetch.clone()))
+        });
+        let crates_io_search = config.crates_io_search.clone();
+        registry.register_tool("crates_io_search", move || {
+            Arc::new(CratesIoSearch::new(crates_io_search.clone()))
+        });
+        let docs_rs_lookup = config.docs_rs_lookup.clone();
+        registry.register_tool("docs_rs_lookup", move || {
+            Arc::new(DocsRsLookup::new(docs_rs_lookup.clone()))
+        });
+        let document_loader = config.document_loader.clone();
+        registry.register_tool("document_loader", move || {
+            Arc::new(DocumentLoader::new(document_loader.clone()))
+        });
+        let weather = config.weather.clone();
+        registry.register_tool("weather", move || Arc::new(Weather::new(weather.clone())));
+        let env_reader = config.env_reader.clone();
+        registry.register_tool("env_reader", move || {
+            Arc::new(EnvReader::new(env_reader.clone()))
+        });
+        let code_search = config.code_search.clone();
+        registry.register_tool("code_search", move || {
+            Arc::new(CodeSearch::new(code_search.clone()))
+        });
+        let sqlite_query = config.sqlite_query.clone();
+        registry.register_tool("sqlite_query", move || {
+            Arc::new(SqliteQuery::new(sqlite_query.clone()))
+        });
+        let hasher = config.hasher.clone();
+        registry.register_tool("hasher", move || Arc::new(Hasher::new(hasher.clone())));
+        let unit_converter = config.unit_converter.clone();
+        registry.register_tool("unit_converter", move || {
+            Arc::new(UnitConverter::new(unit_converter.clone()))
+        });
+        let wikipedia = config.wikipedia.clone();
+        registry.register_tool("wikipedia", move || {
+            Arc::new(Wikipedia::new(wikipedia.clone()))
+        });
+        let feed_reader = config.feed_reader.clone();
+        registry.register_tool("feed_reader", move || {
+            Arc::new(FeedReader::new(feed_reader.clone()))
+        });
+        let translator = config.translator.clone();
+        registry.register_tool("translator", move || {
+            Arc::new(Translator::new(translator.clone()))
+        });
+        let process_list = config.process_list.clone();
+        registry.register_tool("process_list", move || {
+            Arc::new(ProcessList::new(process_list.clone()))
+        });
+        let dns_lookup = config.dns_lookup.clone();
+        registry.register_tool("dns_lookup", move || {
+            Arc::new(DnsLookup::new(dns_lookup.clone()))
+        });
+        let clipboard = config.clipboard.clone();
+        registry.register_tool("clipboard", move || {
+            Arc::new(Clipboard::new(clipboard.clone()))
+        });
+        let github_repo = config.github_repo.clone();
+        registry.register_tool("github_repo", move || {
+            Arc::new(GitHubRepo::new(github_repo.clone()))
+        });
+        #[cfg(unix)]
+        {
+            let man_page = config.man_page.clone();
+            registry.register_tool("man_page", move || Arc::new(ManPage::new(man_page.clone())));
+        }
+
+        for name in [
+            "rust_toolchain_switcher",
+            "cargo_add",
+            "rust_format",
+            "shell_command",
+            "run_binary",
+            "clipboard",
+        ] {
+            registry.mark_destructive(name);
+        }
+
+        registry
+    }
+
+    pub fn register_tool(
+        &mut self,
+        name: impl ToString,
+        factory: impl Fn() -> Arc<dyn Tool> + Send + Sync + 'static,
+    ) {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    pub fn mark_destructive(&mut self, name: impl ToString) {
+        self.destructive.insert(name.to_string());
+    }
+
+    pub fn is_destructive(&self, name: &str) -> bool {
+        self.destructive.contains(name)
+    }
+
+    pub fn build(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.factories.get(name).map(|factory| factory())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.factories.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_tools_are_registered_under_their_own_name() {
+        let registry = ToolRegistry::with_builtin_tools(&ToolsConfig::default());
+        for name in registry.names() {
+            assert_eq!(registry.build(name).unwrap().name(), name);
+        }
+    }
+
+    #[test]
+    fn register_tool_adds_and_replaces_factories() {
+        let mut registry = ToolRegistry::default();
+        assert!(registry.build("calculator").is_none());
+
+        registry.register_tool("calculator", || Arc::new(Calculator::default()));
+        registry.register_tool("calculator", || Arc::new(Calculator::default()));
+        assert_eq!(registry.names(), vec!["calculator"]);
+        assert!(registry.build("calculator").is_some());
+    }
+
+    #[test]
+    fn tools_are_explained_with_their_parameters() {
+        let tool = DescribedTool::new(Arc::new(Clipboard::default()), "Use the clipboard");
+        assert_eq!(
+            explain(&tool, true),
+            "clipboard (destructive)
+  Use the clipboard
+  parameters:
+    operation (string, required): 'read' to get the clipboard text, 'write' to replace it, one of read, write
+    text (string): The text to put on the clipboard, for 'write'
+"
+        );
+        assert_eq!(
+            explain(&SystemInfo::default(), false),
+            format!(
+                "system_info\n  {}\n  no parameters\n",
+                SystemInfo::default().description()
+            )
+        );
+    }
+
+    #[test]
+    fn functions_accept_names_and_tables() {
+        #[derive(Deserialize)]
+        struct Config {
+            functions: Vec<FunctionConfig>,
+        }
+
+        let config: Config = toml::from_str(
+            r#"
+            functions = ["calculator", { name = "weather", enabled = false }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.functions[0], "calculator".into());
+        assert!(!config.functions[1].enabled);
+
+        let config: Config = toml::from_str(
+            r#"
+            [[functions]]
+            name = "rust_toolchain_switcher"
+            description_override = "Switch the Rust toolchain, e.g. to nightly"
+            destructive = false
+            "#,
+        )
+        .unwrap();
+        let function = &config.functions[0];
+        assert!(function.enabled);
+        assert_eq!(function.destructive, Some(false));
+        assert_eq!(
+            function.description_override.as_deref(),
+            Some("Switch the Rust toolchain, e.g. to nightly")
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, MINIMUM_CPU_UPDATE_INTERVAL};
+
+use super::args::Args;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProcessListConfig {
+    /// Processes listed at most without a filter, the busiest first.
+    pub max_processes: usize,
+}
+
+impl Default for ProcessListConfig {
+    fn default() -> Self {
+        Self { max_processes: 25 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct ProcessInfo {
+    pid: u32,
+    name: String,
+    cpu_percent: f32,
+    memory_mb: f64,
+}
+
+/// Snapshot of the processes the current user can see. CPU usage is measured between two
+/// refreshes, so this blocks for [`MINIMUM_CPU_UPDATE_INTERVAL`].
+fn snapshot() -> Vec<ProcessInfo> {
+    let mut system = System::new();
+    let refresh = ProcessRefreshKind::nothing().with_cpu().with_memory();
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh);
+    std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, refresh);
+
+    system
+        .processes()
+        .values()
+        // Kernel threads and processes whose details can't be read have no name.
+        .filter(|process| !process.name().is_empty())
+        .map(|process| ProcessInfo {
+            pid: process.pid().as_u32(),
+            name: process.name().to_string_lossy().into_owned(),
+            cpu_percent: (process.cpu_usage() * 10.0).round() / 10.0,
+            memory_mb: (process.memory() as f64 / 1024.0 / 1024.0 * 10.0).round() / 10.0,
+        })
+        .collect()
+}
+
+/// The processes whose name contains `filter`, ignoring case, or else the `max` busiest.
+/// Returns how many matched before the cap, too.
+fn select(
+    mut processes: Vec<ProcessInfo>,
+    filter: Option<&str>,
+    max: usize,
+) -> (Vec<ProcessInfo>, usize) {
+    processes.sort_by(|a, b| {
+        b.cpu_percent
+            .total_cmp(&a.cpu_percent)
+            .then(b.memory_mb.total_cmp(&a.memory_mb))
+            .then(a.pid.cmp(&b.pid))
+    });
+    if let Some(filter) = filter {
+        let filter = filter.to_lowercase();
+        processes.retain(|process| process.name.to_lowercase().contains(&filter));
+        let matched = processes.len();
+        return (processes, matched);
+    }
+    let total = processes.len();
+    processes.truncate(max);
+    (processes, total)
+}
+
+#[derive(Default)]
+pub struct ProcessList {
+    config: ProcessListConfig,
+}
+
+impl ProcessList {
+    pub fn new(config: ProcessListConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl Tool for ProcessList {
+    fn name(&self) -> String {
+        "process_list".to_string()
+    }
+
+    fn description(&self) -> String {
+        "List running processes with their PID, name, CPU and memory usage, e.g. to check whether a program is running"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "filter": {
+                    "type": "string",
+                    "description": "Only list processes whose name contains this text, e.g. 'postgres'"
+                }
+            }
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let filter = Args::new(&input)
+            .opt_str("filter")?
+            .filter(|filter| !filter.trim().is_empty());
+        let processes = tokio::task::spawn_blocking(snapshot).await?;
+        let (processes, matched) = select(processes, filter, self.config.max_processes);
+
+        let response = json!({
+            "processes": processes,
+            "matched": matched,
+            "truncated": matched > processes.len(),
+        });
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, cpu_percent: f32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            cpu_percent,
+            memory_mb: 10.0,
+        }
+    }
+
+    #[test]
+    fn filter_matches_names_and_the_busiest_are_listed_otherwise() {
+        let processes = vec![
+            process(1, "init", 0.0),
+            process(20, "postgres", 1.5),
+            process(21, "postgres: writer", 0.2),
+            process(30, "cargo", 90.0),
+        ];
+
+        let (matching, matched) = select(processes.clone(), Some("Postgres"), 1);
+        assert_eq!(matched, 2);
+        let pids: Vec<u32> = matching.iter().map(|process| process.pid).collect();
+        assert_eq!(pids, [20, 21]);
+
+        let (busiest, total) = select(processes, None, 2);
+        assert_eq!(total, 4);
+        let names: Vec<&str> = busiest
+            .iter()
+            .map(|process| process.name.as_str())
+            .collect();
+        assert_eq!(names, ["cargo", "postgres"]);
+    }
+
+    #[tokio::test]
+    async fn the_current_process_is_listed() {
+        let output = ProcessList::default()
+            .run(json!({"filter": "shark"}))
+            .await
+            .unwrap();
+        let output: Value = serde_json::from_str(&output).unwrap();
+        let pid = std::process::id();
+        let listed = output["processes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|process| process["pid"] == pid);
+        assert!(listed, "{output}");
+    }
+}
@@ -0,0 +1,133 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::args::Args;
+
+const KINDS: [&str; 4] = ["uuid_v4", "uuid_v7", "hex", "alphanumeric"];
+const DEFAULT_LENGTH: u64 = 32;
+const MAX_LENGTH: u64 = 1024;
+
+const ALPHANUMERIC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+#[derive(Default)]
+pub struct RandomId {}
+
+/// `length` hex digits from the operating system's secure random source.
+fn hex(length: usize) -> Result<String, getrandom::Error> {
+    let mut bytes = vec![0; length.div_ceil(2)];
+    getrandom::fill(&mut bytes)?;
+    let mut hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    hex.truncate(length);
+    Ok(hex)
+}
+
+/// `length` letters and digits from the operating system's secure random source.
+/// Bytes past the largest multiple of 62 are thrown away, so every character is as likely.
+fn alphanumeric(length: usize) -> Result<String, getrandom::Error> {
+    let limit = 256 - 256 % ALPHANUMERIC.len();
+    let mut token = String::with_capacity(length);
+    let mut bytes = [0; 64];
+    while token.len() < length {
+        getrandom::fill(&mut bytes)?;
+        token.extend(
+            bytes
+                .iter()
+                .filter(|&&b| usize::from(b) < limit)
+                .map(|&b| ALPHANUMERIC[usize::from(b) % ALPHANUMERIC.len()] as char)
+                .take(length - token.len()),
+        );
+    }
+    Ok(token)
+}
+
+fn generate(kind: &str, length: usize) -> Result<String, String> {
+    match kind {
+        "uuid_v4" => Ok(Uuid::new_v4().to_string()),
+        "uuid_v7" => Ok(Uuid::now_v7().to_string()),
+        "hex" => hex(length).map_err(|e| e.to_string()),
+        "alphanumeric" => alphanumeric(length).map_err(|e| e.to_string()),
+        _ => Err(format!(
+            "unsupported kind '{kind}', expected one of: {}",
+            KINDS.join(", ")
+        )),
+    }
+}
+
+#[async_trait]
+impl Tool for RandomId {
+    fn name(&self) -> String {
+        "random_id".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Generate a UUID or a cryptographically secure random token, e.g. for an API key or password"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "kind": {
+                    "type": "string",
+                    "enum": KINDS,
+                    "description": "A random (v4) or time-ordered (v7) UUID, or a token of hex digits or letters and digits"
+                },
+                "length": {
+                    "type": "integer",
+                    "description": "Characters in a hex or alphanumeric token, 32 by default"
+                }
+            },
+            "required": ["kind"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let kind = args.get_str("kind")?;
+        let length = args.opt_u64("length")?.unwrap_or(DEFAULT_LENGTH);
+        if !(1..=MAX_LENGTH).contains(&length) {
+            let error = format!("length must be between 1 and {MAX_LENGTH}");
+            return Ok(json!({ "error": error }).to_string());
+        }
+
+        let response = match generate(kind, length as usize) {
+            Ok(value) => json!({
+                "kind": kind,
+                "value": value,
+            }),
+            Err(error) => json!({ "error": error }),
+        };
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_have_the_requested_length_and_alphabet() {
+        let token = hex(7).unwrap();
+        assert_eq!(token.len(), 7);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+
+        let token = alphanumeric(200).unwrap();
+        assert_eq!(token.len(), 200);
+        assert!(token.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(alphanumeric(32).unwrap(), alphanumeric(32).unwrap());
+    }
+
+    #[test]
+    fn uuids_have_their_version() {
+        let v4: Uuid = generate("uuid_v4", 0).unwrap().parse().unwrap();
+        assert_eq!(v4.get_version_num(), 4);
+        let v7: Uuid = generate("uuid_v7", 0).unwrap().parse().unwrap();
+        assert_eq!(v7.get_version_num(), 7);
+        assert!(generate("uuid_v1", 0).is_err());
+    }
+}
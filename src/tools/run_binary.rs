@@ -0,0 +1,181 @@
+use std::{error::Error, time::Duration};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{args::Args, cargo_runner::forbidden_flag};
+use tokio::process::Command;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RunBinaryConfig {
+    /// Programs the model may run, looked up on `PATH`. Empty by default, since build tools
+    /// like make or npm run whatever the project's scripts say.
+    pub allowlist: Vec<String>,
+    pub timeout_secs: u64,
+}
+
+impl Default for RunBinaryConfig {
+    fn default() -> Self {
+        Self {
+            allowlist: Vec::new(),
+            timeout_secs: 120,
+        }
+    }
+}
+
+pub struct RunBinary {
+    config: RunBinaryConfig,
+}
+
+impl RunBinary {
+    pub fn new(config: RunBinaryConfig) -> Self {
+        Self { config }
+    }
+
+    /// Only bare names are allowed, so `./make` or `/tmp/npm` can't pass for an allowed program.
+    fn is_allowed(&self, program: &str) -> bool {
+        !program.contains(['/', '\\']) && self.config.allowlist.iter().any(|p| p == program)
+    }
+}
+
+#[async_trait]
+impl Tool for RunBinary {
+    fn name(&self) -> String {
+        "run_binary".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Run an allowed development program like make, just or npm with arguments and return its output"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "program": {
+                    "type": "string",
+                    "description": format!(
+                        "The program to run, one of: {}",
+                        self.config.allowlist.join(", ")
+                    )
+                },
+                "args": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Arguments passed to the program, e.g. ['test', '--watch=false']"
+                }
+            },
+            "required": ["program"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let program = args.get_str("program")?;
+        let program_args = args.opt_str_list("args")?.unwrap_or_default();
+
+        if self.config.allowlist.is_empty() {
+            let error = "no program is allowed, list them in the allowlist of [tools.run_binary]";
+            return Ok(json!({ "error": error }).to_string());
+        }
+        if !self.is_allowed(program) {
+            let error = format!(
+                "'{program}' is not allowed, permitted programs are: {}",
+                self.config.allowlist.join(", ")
+            );
+            return Ok(json!({ "error": error }).to_string());
+        }
+        // Flags like `--config target.<triple>.runner=...` would get around cargo_runner's check.
+        if program == "cargo" {
+            if let Some(flag) = forbidden_flag(&program_args) {
+                let error = format!("'{flag}' can't be passed to cargo");
+                return Ok(json!({ "error": error }).to_string());
+            }
+        }
+
+        tracing::debug!(program, args = ?program_args, "running program");
+        let child = Command::new(program)
+            .args(&program_args)
+            .kill_on_drop(true)
+            .output();
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let output = match tokio::time::timeout(timeout, child).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => {
+                let error = format!("failed to run '{program}': {e}");
+                return Ok(json!({ "error": error }).to_string());
+            }
+            Err(_) => {
+                let error = format!("'{program}' timed out after {}s", timeout.as_secs());
+                return Ok(json!({ "error": error }).to_string());
+            }
+        };
+
+        Ok(json!({
+            "program": program,
+            "args": program_args,
+            "stdout": String::from_utf8_lossy(&output.stdout),
+            "stderr": String::from_utf8_lossy(&output.stderr),
+            "exit_code": output.status.code(),
+        })
+        .to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_listed_bare_programs_are_allowed() {
+        let tool = RunBinary::new(RunBinaryConfig {
+            allowlist: vec!["make".to_string(), "npm".to_string()],
+            timeout_secs: 10,
+        });
+        assert!(tool.is_allowed("make"));
+        assert!(tool.is_allowed("npm"));
+        assert!(!tool.is_allowed("rm"));
+        assert!(!tool.is_allowed("./make"));
+        assert!(!tool.is_allowed("/usr/bin/make"));
+        assert!(!tool.is_allowed("make "));
+    }
+
+    #[tokio::test]
+    async fn nothing_runs_by_default_and_cargo_flags_are_checked() {
+        let output = RunBinary::new(RunBinaryConfig::default())
+            .run(json!({"program": "make"}))
+            .await
+            .unwrap();
+        assert!(output.contains("no program is allowed"), "{output}");
+
+        let tool = RunBinary::new(RunBinaryConfig {
+            allowlist: vec!["cargo".to_string()],
+            timeout_secs: 10,
+        });
+        let output = tool
+            .run(json!({"program": "cargo", "args": ["--config=target.x.runner='sh'", "run"]}))
+            .await
+            .unwrap();
+        assert!(output.contains("can't be passed to cargo"), "{output}");
+    }
+
+    #[tokio::test]
+    async fn output_and_exit_code_are_returned() {
+        let tool = RunBinary::new(RunBinaryConfig {
+            allowlist: vec!["sh".to_string()],
+            timeout_secs: 10,
+        });
+        let output = tool
+            .run(json!({"program": "sh", "args": ["-c", "echo out; echo err >&2; exit 3"]}))
+            .await
+            .unwrap();
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["stdout"], "out\n");
+        assert_eq!(output["stderr"], "err\n");
+        assert_eq!(output["exit_code"], 3);
+    }
+}
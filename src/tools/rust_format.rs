@@ -0,0 +1,146 @@
+use std::{error::Error, path::Path};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use tokio::process::Command;
+
+use super::{file_reader::resolve_within, http_fetch::truncate_chars};
+
+const MAX_DIFF_CHARS: usize = 4000;
+
+#[derive(Default)]
+pub struct RustFormat {}
+
+/// Runs rustfmt on a file, or cargo fmt on the project in a directory.
+fn formatter(path: &Path, check: bool) -> Command {
+    let mut command = if path.is_dir() {
+        let mut command = Command::new("cargo");
+        command
+            .arg("fmt")
+            .arg("--manifest-path")
+            .arg(path.join("Cargo.toml"));
+        if check {
+            command.arg("--check");
+        }
+        command.args(["--", "--color", "never"]);
+        command
+    } else {
+        let mut command = Command::new("rustfmt");
+        if check {
+            command.arg("--check");
+        }
+        command.args(["--color", "never"]).arg(path);
+        command
+    };
+    command.kill_on_drop(true);
+    command
+}
+
+/// Files named in the `Diff in <file>:N:` headers of a `--check` diff,
+/// or `Diff in <file> at line N:` with older rustfmt versions.
+fn changed_files(diff: &str) -> Vec<&str> {
+    let mut files: Vec<&str> = diff
+        .lines()
+        .filter_map(|line| line.strip_prefix("Diff in ")?.strip_suffix(':'))
+        .filter_map(|rest| {
+            rest.rsplit_once(" at line ")
+                .or_else(|| rest.rsplit_once(':'))
+                .map(|(file, _)| file)
+        })
+        .collect();
+    files.dedup();
+    files
+}
+
+#[async_trait]
+impl Tool for RustFormat {
+    fn name(&self) -> String {
+        "rust_format".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Format a Rust file with rustfmt, or a whole cargo project given its directory".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "A .rs file, or the directory of a cargo project"
+                },
+                "check": {
+                    "type": "boolean",
+                    "description": "Only report what would change, without modifying files"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let path = args.get_str("path")?;
+        let check = args.opt_bool("check")?.unwrap_or(false);
+
+        let resolved = match resolve_within(Path::new("."), path) {
+            Ok(resolved) => resolved,
+            Err(error) => return Ok(json!({ "error": error }).to_string()),
+        };
+        if resolved.is_dir() && !resolved.join("Cargo.toml").is_file() {
+            let error = format!("'{path}' is a directory without a Cargo.toml");
+            return Ok(json!({ "error": error }).to_string());
+        }
+
+        // The check run always comes first, its diff is the summary of the changes.
+        tracing::debug!(?resolved, check, "checking formatting");
+        let checked = match formatter(&resolved, true).output().await {
+            Ok(output) => output,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let error = "rustfmt is not installed, run `rustup component add rustfmt`";
+                return Ok(json!({ "error": error }).to_string());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let diff = String::from_utf8_lossy(&checked.stdout);
+        let needs_formatting = !diff.trim().is_empty();
+        let mut stderr = String::from_utf8_lossy(&checked.stderr).into_owned();
+
+        let mut modified = false;
+        if !check && needs_formatting {
+            let output = formatter(&resolved, false).output().await?;
+            modified = output.status.success();
+            stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        }
+
+        let (diff_excerpt, truncated) = truncate_chars(&diff, MAX_DIFF_CHARS);
+        Ok(json!({
+            "path": path,
+            "check": check,
+            "modified": modified,
+            "needs_formatting": needs_formatting && !modified,
+            "changed_files": changed_files(&diff),
+            "diff": diff_excerpt,
+            "diff_truncated": truncated,
+            "stderr": stderr,
+        })
+        .to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_files_reads_diff_headers() {
+        let diff = "Diff in /p/src/main.rs:1:\n-fn main(){}\n+fn main() {}\n\
+                    Diff in /p/src/main.rs:9:\n-x\n+y\n\
+                    Diff in /p/src/lib.rs at line 3:\n-a\n+b\n";
+        assert_eq!(changed_files(diff), vec!["/p/src/main.rs", "/p/src/lib.rs"]);
+    }
+}
@@ -0,0 +1,98 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde_json::{json, Value};
+use tokio::process::Command;
+
+#[derive(Default)]
+pub struct RustToolchainList {}
+
+async fn rustup(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("rustup")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                "rustup is not installed, see https://rustup.rs to install it".to_string()
+            }
+            _ => format!("failed to run rustup: {e}"),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "rustup {} failed: {}",
+            args.join(" "),
+            stderr.trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// The toolchains of `rustup toolchain list`, marking the one `rustup show active-toolchain`
+/// names as active.
+fn parse_toolchains(list: &str, active: &str) -> Vec<Value> {
+    let active = active.split_whitespace().next().unwrap_or_default();
+    list.lines()
+        .filter(|line| !line.starts_with("no installed toolchains"))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|name| json!({"name": name, "active": name == active}))
+        .collect()
+}
+
+#[async_trait]
+impl Tool for RustToolchainList {
+    fn name(&self) -> String {
+        "rust_toolchain_list".to_string()
+    }
+
+    fn description(&self) -> String {
+        "List the installed rust toolchains and which one is active".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {}
+        })
+    }
+
+    async fn run(&self, _input: Value) -> Result<String, Box<dyn Error>> {
+        let (list, active) = match (
+            rustup(&["toolchain", "list"]).await,
+            rustup(&["show", "active-toolchain"]).await,
+        ) {
+            (Ok(list), Ok(active)) => (list, active),
+            (Err(error), _) | (_, Err(error)) => return Ok(json!({ "error": error }).to_string()),
+        };
+
+        let toolchains = parse_toolchains(&list, &active);
+        Ok(json!({ "toolchains": toolchains }).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_active_toolchain_is_marked() {
+        let list = "stable-x86_64-unknown-linux-gnu (active, default)\n\
+                    nightly-2024-11-01-x86_64-unknown-linux-gnu\n";
+        let active = "stable-x86_64-unknown-linux-gnu (default)\n";
+        assert_eq!(
+            parse_toolchains(list, active),
+            [
+                json!({"name": "stable-x86_64-unknown-linux-gnu", "active": true}),
+                json!({"name": "nightly-2024-11-01-x86_64-unknown-linux-gnu", "active": false}),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_toolchains_is_an_empty_list() {
+        assert!(parse_toolchains("no installed toolchains\n", "").is_empty());
+    }
+}
@@ -8,6 +8,28 @@ use tokio::process::Command;
 #[derive(Default)]
 pub struct RustToolchainSwitcher {}
 
+fn parse_toolchain(input: &Value) -> Result<&str, String> {
+    match input.get("toolchain") {
+        Some(Value::String(toolchain)) if !toolchain.trim().is_empty() => Ok(toolchain.trim()),
+        Some(Value::String(_)) => Err("'toolchain' must not be empty".to_string()),
+        Some(other) => Err(format!("'toolchain' must be a string, got {other}")),
+        None => Err("missing 'toolchain' argument".to_string()),
+    }
+}
+
+/// Checks `toolchain` against the output of `rustup toolchain list`, where entries
+/// look like `stable-x86_64-unknown-linux-gnu (default)`.
+fn is_installed(toolchain_list: &str, toolchain: &str) -> bool {
+    toolchain_list
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .any(|name| name == toolchain || name.starts_with(&format!("{toolchain}-")))
+}
+
+fn error_response(error: impl Into<String>) -> Result<String, Box<dyn Error>> {
+    Ok(json!({ "error": error.into() }).to_string())
+}
+
 #[async_trait]
 impl Tool for RustToolchainSwitcher {
     fn name(&self) -> String {
@@ -32,19 +54,34 @@ impl Tool for RustToolchainSwitcher {
     }
 
     async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
-        let version = input["toolchain"].as_str().unwrap();
+        let version = match parse_toolchain(&input) {
+            Ok(version) => version,
+            Err(error) => return error_response(error),
+        };
+
+        let installed = Command::new("rustup")
+            .args(["toolchain", "list"])
+            .output()
+            .await?;
+        let installed = String::from_utf8_lossy(&installed.stdout);
+        if !is_installed(&installed, version) {
+            return error_response(format!(
+                "toolchain '{version}' is not installed, install it with `rustup toolchain install {version}`"
+            ));
+        }
+
         let output = Command::new("rustup")
             .args(["default", version])
             .output()
             .await?;
 
-        let output_content = if output.stdout.len() > 0 {
+        let output_content = if !output.stdout.is_empty() {
             String::from_utf8(output.stdout)?
         } else {
             String::default()
         };
 
-        let output_error = if output.stderr.len() > 0 {
+        let output_error = if !output.stderr.is_empty() {
             String::from_utf8(output.stderr)?
         } else {
             String::default()
@@ -57,3 +94,54 @@ impl Tool for RustToolchainSwitcher {
         Ok(serde_json::to_string(&response)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOOLCHAIN_LIST: &str = "stable-x86_64-unknown-linux-gnu (default)
+nightly-2024-12-01-x86_64-unknown-linux-gnu
+1.80.0-x86_64-unknown-linux-gnu
+";
+
+    #[test]
+    fn parse_toolchain_rejects_malformed_input() {
+        assert!(parse_toolchain(&json!({})).is_err());
+        assert!(parse_toolchain(&json!({"toolchain": 1})).is_err());
+        assert!(parse_toolchain(&json!({"toolchain": null})).is_err());
+        assert!(parse_toolchain(&json!({"toolchain": ["stable"]})).is_err());
+        assert!(parse_toolchain(&json!({"toolchain": "  "})).is_err());
+        assert!(parse_toolchain(&json!("stable")).is_err());
+    }
+
+    #[test]
+    fn parse_toolchain_accepts_string() {
+        assert_eq!(
+            parse_toolchain(&json!({"toolchain": " nightly "})),
+            Ok("nightly")
+        );
+    }
+
+    #[test]
+    fn is_installed_matches_toolchain_names() {
+        assert!(is_installed(TOOLCHAIN_LIST, "stable"));
+        assert!(is_installed(TOOLCHAIN_LIST, "nightly"));
+        assert!(is_installed(TOOLCHAIN_LIST, "1.80.0"));
+        assert!(is_installed(
+            TOOLCHAIN_LIST,
+            "stable-x86_64-unknown-linux-gnu"
+        ));
+        assert!(!is_installed(TOOLCHAIN_LIST, "beta"));
+        assert!(!is_installed(TOOLCHAIN_LIST, "1.80"));
+    }
+
+    #[tokio::test]
+    async fn run_returns_error_for_malformed_input() {
+        let switcher = RustToolchainSwitcher::default();
+        for input in [json!({}), json!({"toolchain": 42}), json!(null)] {
+            let response = switcher.run(input).await.unwrap();
+            let response: Value = serde_json::from_str(&response).unwrap();
+            assert!(response["error"].is_string());
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::{collections::HashMap, error::Error, time::Duration};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::Args;
+use tokio::process::Command;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ShellCommandConfig {
+    /// Command prefixes the model may run, e.g. `"ls"` or `"git log"`.
+    pub allowlist: Vec<String>,
+    pub timeout_secs: u64,
+}
+
+impl Default for ShellCommandConfig {
+    fn default() -> Self {
+        Self {
+            allowlist: Vec::new(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+pub struct ShellCommand {
+    config: ShellCommandConfig,
+}
+
+impl ShellCommand {
+    pub fn new(config: ShellCommandConfig) -> Self {
+        Self { config }
+    }
+
+    /// Commands are split into words and executed without a shell, so a prefix
+    /// match can't be bypassed with `;`, `&&` or pipes.
+    fn is_allowed(&self, args: &[String]) -> bool {
+        self.config.allowlist.iter().any(|prefix| {
+            let prefix: Vec<&str> = prefix.split_whitespace().collect();
+            !prefix.is_empty()
+                && args.len() >= prefix.len()
+                && prefix.iter().zip(args).all(|(p, a)| p == a)
+        })
+    }
+}
+
+#[async_trait]
+impl Tool for ShellCommand {
+    fn name(&self) -> String {
+        "shell_command".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Run a shell command on the user's machine and return its output".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": "The command line to run, e.g. 'ls -la'"
+                }
+            },
+            "required": ["command"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let command = Args::new(&input).get_str("command")?;
+
+        let Some(args) = shlex::split(command).filter(|args| !args.is_empty()) else {
+            return Ok(json!({"error": format!("could not parse command '{command}'")}).to_string());
+        };
+
+        if !self.is_allowed(&args) {
+            let error = format!(
+                "'{command}' is not allowed, permitted command prefixes are: {}",
+                self.config.allowlist.join(", ")
+            );
+            return Ok(json!({ "error": error }).to_string());
+        }
+
+        tracing::debug!(?args, "running command");
+        let child = Command::new(&args[0])
+            .args(&args[1..])
+            .kill_on_drop(true)
+            .output();
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let output = match tokio::time::timeout(timeout, child).await {
+            Ok(output) => output?,
+            Err(_) => {
+                let error = format!("'{command}' timed out after {}s", timeout.as_secs());
+                return Ok(json!({ "error": error }).to_string());
+            }
+        };
+
+        let mut response = HashMap::new();
+        response.insert("stdout", json!(String::from_utf8_lossy(&output.stdout)));
+        response.insert("stderr", json!(String::from_utf8_lossy(&output.stderr)));
+        response.insert("exit_code", json!(output.status.code()));
+
+        Ok(serde_json::to_string(&response)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(allowlist: &[&str]) -> ShellCommand {
+        ShellCommand::new(ShellCommandConfig {
+            allowlist: allowlist.iter().map(|prefix| prefix.to_string()).collect(),
+            timeout_secs: 10,
+        })
+    }
+
+    fn allowed(tool: &ShellCommand, command: &str) -> bool {
+        tool.is_allowed(&shlex::split(command).unwrap())
+    }
+
+    #[test]
+    fn prefixes_match_whole_words() {
+        let tool = tool(&["git log"]);
+        assert!(allowed(&tool, "git log"));
+        assert!(allowed(&tool, "git log -n1"));
+        assert!(!allowed(&tool, "git"));
+        assert!(!allowed(&tool, "gitx log"));
+        assert!(!allowed(&tool, "git logx"));
+        assert!(!allowed(&tool, "git status"));
+    }
+
+    #[test]
+    fn an_empty_allowlist_allows_nothing() {
+        let empty = tool(&[]);
+        assert!(!allowed(&empty, "ls"));
+        assert!(!allowed(&empty, "git log"));
+        assert!(!empty.is_allowed(&[]));
+        assert!(!tool(&[""]).is_allowed(&[]));
+    }
+
+    #[tokio::test]
+    async fn unparsable_commands_are_rejected() {
+        let tool = tool(&["echo"]);
+        for command in ["echo 'unterminated", "echo \"open"] {
+            let output = tool.run(json!({ "command": command })).await.unwrap();
+            assert!(output.contains("could not parse command"), "{output}");
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::{error::Error, path::PathBuf};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use rusqlite::{types::ValueRef, Connection, OpenFlags};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use super::args::Args;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SqliteQueryConfig {
+    /// Database the model may query, the tool refuses to run without one.
+    pub path: Option<PathBuf>,
+    pub max_rows: usize,
+}
+
+impl Default for SqliteQueryConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            max_rows: 100,
+        }
+    }
+}
+
+pub struct SqliteQuery {
+    config: SqliteQueryConfig,
+}
+
+impl SqliteQuery {
+    pub fn new(config: SqliteQueryConfig) -> Self {
+        Self { config }
+    }
+}
+
+/// Only plain `SELECT` statements are run, anything else is rejected before touching the
+/// database. The connection is opened read-only on top of that.
+fn is_select(query: &str) -> bool {
+    query
+        .split_whitespace()
+        .next()
+        .is_some_and(|keyword| keyword.eq_ignore_ascii_case("select"))
+}
+
+fn to_json(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => json!(i),
+        ValueRef::Real(f) => json!(f),
+        ValueRef::Text(text) => json!(String::from_utf8_lossy(text)),
+        ValueRef::Blob(blob) => json!(format!("<{} byte blob>", blob.len())),
+    }
+}
+
+/// Runs `query`, returning at most `max_rows` rows and whether more were left out.
+fn run_query(
+    connection: &Connection,
+    query: &str,
+    max_rows: usize,
+) -> rusqlite::Result<(Vec<Value>, bool)> {
+    let mut statement = connection.prepare(query)?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+
+    let mut rows = statement.query([])?;
+    let mut results = Vec::new();
+    while let Some(row) = rows.next()? {
+        if results.len() == max_rows {
+            return Ok((results, true));
+        }
+        let mut object = Map::new();
+        for (i, column) in columns.iter().enumerate() {
+            object.insert(column.clone(), to_json(row.get_ref(i)?));
+        }
+        results.push(Value::Object(object));
+    }
+    Ok((results, false))
+}
+
+#[async_trait]
+impl Tool for SqliteQuery {
+    fn name(&self) -> String {
+        "sqlite_query".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Run a read-only SQL SELECT query against the user's SQLite database".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "A single SELECT statement, e.g. 'SELECT title FROM tasks WHERE done = 0'"
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let query = Args::new(&input).get_str("query")?.to_string();
+        let Some(path) = self.config.path.clone() else {
+            let error = "no database configured, set path in [tools.sqlite_query]";
+            return Ok(json!({ "error": error }).to_string());
+        };
+        if !is_select(&query) {
+            return Ok(json!({"error": "only SELECT statements are allowed"}).to_string());
+        }
+
+        let max_rows = self.config.max_rows;
+        let result = tokio::task::spawn_blocking(move || {
+            let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+            let connection = Connection::open_with_flags(&path, flags)
+                .map_err(|e| format!("cannot open {}: {e}", path.display()))?;
+            run_query(&connection, &query, max_rows).map_err(|e| format!("query failed: {e}"))
+        })
+        .await?;
+
+        let response = match result {
+            Ok((rows, truncated)) => json!({
+                "row_count": rows.len(),
+                "rows": rows,
+                "truncated": truncated,
+            }),
+            Err(error) => json!({ "error": error }),
+        };
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_select_statements_are_accepted() {
+        assert!(is_select("SELECT * FROM tasks"));
+        assert!(is_select("  select\ncount(*) from tasks"));
+        assert!(!is_select("DELETE FROM tasks"));
+        assert!(!is_select("selection"));
+        assert!(!is_select(""));
+    }
+
+    #[test]
+    fn rows_are_capped_and_errors_reported() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE tasks (id INTEGER, title TEXT, score REAL);
+                 INSERT INTO tasks VALUES (1, 'write', 0.5), (2, 'test', NULL), (3, 'ship', 1.0);",
+            )
+            .unwrap();
+
+        let (rows, truncated) =
+            run_query(&connection, "SELECT id, title, score FROM tasks", 2).unwrap();
+        assert!(truncated);
+        assert_eq!(
+            rows,
+            vec![
+                json!({"id": 1, "title": "write", "score": 0.5}),
+                json!({"id": 2, "title": "test", "score": null}),
+            ]
+        );
+
+        let (rows, truncated) = run_query(&connection, "SELECT id FROM tasks", 3).unwrap();
+        assert_eq!((rows.len(), truncated), (3, false));
+
+        assert!(run_query(&connection, "SELECT FROM WHERE", 2).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde_json::{json, Value};
+use sysinfo::{Disks, System, MINIMUM_CPU_UPDATE_INTERVAL};
+
+/// Bytes as gigabytes with two decimals, which is what people ask about.
+fn gb(bytes: u64) -> f64 {
+    (bytes as f64 / 1e9 * 100.0).round() / 100.0
+}
+
+/// Share of `used` in `total` as a percentage with one decimal.
+fn percent(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (used as f64 / total as f64 * 1000.0).round() / 10.0
+}
+
+/// Reads CPU, memory and disk usage. CPU usage is measured between two refreshes, so this
+/// blocks for [`MINIMUM_CPU_UPDATE_INTERVAL`].
+fn report() -> Value {
+    let mut system = System::new();
+    system.refresh_cpu_usage();
+    std::thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+    system.refresh_cpu_usage();
+    system.refresh_memory();
+
+    let load = System::load_average();
+    let disks: Vec<Value> = Disks::new_with_refreshed_list()
+        .list()
+        .iter()
+        .map(|disk| {
+            let total = disk.total_space();
+            let used = total.saturating_sub(disk.available_space());
+            json!({
+                "mount_point": disk.mount_point(),
+                "file_system": disk.file_system().to_string_lossy(),
+                "total_gb": gb(total),
+                "used_gb": gb(used),
+                "available_gb": gb(disk.available_space()),
+                "used_percent": percent(used, total),
+            })
+        })
+        .collect();
+
+    json!({
+        "os": System::long_os_version().or_else(System::name),
+        "kernel": System::kernel_version(),
+        "uptime_secs": System::uptime(),
+        "cpu": {
+            "logical_cores": system.cpus().len(),
+            "physical_cores": System::physical_core_count(),
+            "usage_percent": (system.global_cpu_usage() * 10.0).round() / 10.0,
+            "load_average": [load.one, load.five, load.fifteen],
+        },
+        "memory": {
+            "total_gb": gb(system.total_memory()),
+            "used_gb": gb(system.used_memory()),
+            "available_gb": gb(system.available_memory()),
+            "used_percent": percent(system.used_memory(), system.total_memory()),
+        },
+        "swap": {
+            "total_gb": gb(system.total_swap()),
+            "used_gb": gb(system.used_swap()),
+        },
+        "disks": disks,
+    })
+}
+
+#[derive(Default)]
+pub struct SystemInfo {}
+
+#[async_trait]
+impl Tool for SystemInfo {
+    fn name(&self) -> String {
+        "system_info".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Report this machine's OS, uptime, CPU cores and load, memory, swap and disk usage"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {}
+        })
+    }
+
+    async fn run(&self, _input: Value) -> Result<String, Box<dyn Error>> {
+        let report = tokio::task::spawn_blocking(report).await?;
+        Ok(report.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_are_rounded() {
+        assert_eq!(gb(16_777_216_000), 16.78);
+        assert_eq!(gb(0), 0.0);
+        assert_eq!(percent(1, 3), 33.3);
+        assert_eq!(percent(5, 0), 0.0);
+    }
+
+    #[tokio::test]
+    async fn memory_and_cpus_are_reported() {
+        let output = SystemInfo::default().run(Value::Null).await.unwrap();
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert!(output["cpu"]["logical_cores"].as_u64().unwrap() > 0);
+        assert!(output["memory"]["total_gb"].as_f64().unwrap() > 0.0);
+        assert!(output["disks"].is_array());
+    }
+}
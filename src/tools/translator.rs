@@ -0,0 +1,293 @@
+use std::{error::Error, time::Duration};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+use super::args::Args;
+use super::http_fetch::USER_AGENT;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TranslatorConfig {
+    /// A LibreTranslate-compatible `/translate` endpoint. Without one, the model translates.
+    pub api_url: Option<String>,
+    pub api_key: Option<String>,
+    /// Ollama server asked for translations, the configured `addr` when unset.
+    pub addr: Option<String>,
+    /// Bearer token for that server, the configured `api_token` when `addr` is unset.
+    pub api_token: Option<String>,
+    /// Model asked for translations, the configured `model` when unset.
+    pub model: Option<String>,
+    pub timeout_secs: u64,
+}
+
+impl Default for TranslatorConfig {
+    fn default() -> Self {
+        Self {
+            api_url: None,
+            api_key: None,
+            addr: None,
+            api_token: None,
+            model: None,
+            timeout_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Translation {
+    text: String,
+    /// The language of the original text, when it had to be detected.
+    detected: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    #[serde(rename = "translatedText")]
+    translated_text: String,
+    #[serde(rename = "detectedLanguage")]
+    detected_language: Option<DetectedLanguage>,
+}
+
+#[derive(Deserialize)]
+struct DetectedLanguage {
+    language: String,
+}
+
+#[derive(Deserialize)]
+struct GenerateResponse {
+    response: String,
+}
+
+/// The JSON object the model is asked to answer with.
+#[derive(Deserialize)]
+struct ModelAnswer {
+    translation: String,
+    source_lang: Option<String>,
+}
+
+/// Instructions for the model, which answers in JSON so the detected language can be read back.
+fn prompt(text: &str, source_lang: Option<&str>, target_lang: &str) -> String {
+    let source = match source_lang {
+        Some(source_lang) => format!("from {source_lang} "),
+        None => String::new(),
+    };
+    format!(
+        "Translate the text between <text> tags {source}into {target_lang}. \
+         Answer only with a JSON object with the keys \"translation\", the translated text, \
+         and \"source_lang\", the ISO 639-1 code of the language the text is written in.\n\
+         <text>{text}</text>"
+    )
+}
+
+fn parse_model_answer(answer: &str, detect: bool) -> Result<Translation, String> {
+    let answer: ModelAnswer = serde_json::from_str(answer.trim())
+        .map_err(|e| format!("the model didn't answer with a translation: {e}"))?;
+    Ok(Translation {
+        text: answer.translation,
+        detected: answer.source_lang.filter(|_| detect),
+    })
+}
+
+pub struct Translator {
+    client: reqwest::Client,
+    config: TranslatorConfig,
+}
+
+impl Translator {
+    pub fn new(config: TranslatorConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .unwrap_or_default();
+        Self { client, config }
+    }
+
+    async fn post<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        body: Value,
+        token: Option<&str>,
+    ) -> Result<T, String> {
+        tracing::debug!(url, "requesting translation");
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("{url} unreachable: {e}"))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("{url} responded with {status}"));
+        }
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        serde_json::from_str(&body).map_err(|e| e.to_string())
+    }
+
+    async fn with_api(
+        &self,
+        api_url: &str,
+        text: &str,
+        source_lang: Option<&str>,
+        target_lang: &str,
+    ) -> Result<Translation, String> {
+        let response: ApiResponse = self
+            .post(
+                api_url,
+                json!({
+                    "q": text,
+                    "source": source_lang.unwrap_or("auto"),
+                    "target": target_lang,
+                    "format": "text",
+                    "api_key": self.config.api_key,
+                }),
+                None,
+            )
+            .await?;
+        Ok(Translation {
+            text: response.translated_text,
+            detected: response.detected_language.map(|detected| detected.language),
+        })
+    }
+
+    async fn with_model(
+        &self,
+        text: &str,
+        source_lang: Option<&str>,
+        target_lang: &str,
+    ) -> Result<Translation, String> {
+        let (Some(addr), Some(model)) = (&self.config.addr, &self.config.model) else {
+            return Err("no model is configured for translations".to_string());
+        };
+        let url = format!("{}/api/generate", addr.trim_end_matches('/'));
+        let response: GenerateResponse = self
+            .post(
+                &url,
+                json!({
+                    "model": model,
+                    "prompt": prompt(text, source_lang, target_lang),
+                    "format": "json",
+                    "stream": false,
+                }),
+                self.config.api_token.as_deref(),
+            )
+            .await?;
+        parse_model_answer(&response.response, source_lang.is_none())
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_lang: Option<&str>,
+        target_lang: &str,
+    ) -> Result<Value, String> {
+        let translation = match &self.config.api_url {
+            Some(api_url) => {
+                self.with_api(api_url, text, source_lang, target_lang)
+                    .await?
+            }
+            None => self.with_model(text, source_lang, target_lang).await?,
+        };
+        let mut response = json!({
+            "translation": translation.text,
+            "source_lang": source_lang.or(translation.detected.as_deref()),
+            "target_lang": target_lang,
+        });
+        if source_lang.is_none() {
+            response["detected"] = json!(translation.detected.is_some());
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl Tool for Translator {
+    fn name(&self) -> String {
+        "translator".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Translate text into another language, detecting the original language when it isn't given"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": {
+                    "type": "string",
+                    "description": "The text to translate"
+                },
+                "source_lang": {
+                    "type": "string",
+                    "description": "Language of the text as an ISO 639-1 code like 'de', detected when omitted"
+                },
+                "target_lang": {
+                    "type": "string",
+                    "description": "Language to translate into as an ISO 639-1 code like 'en'"
+                }
+            },
+            "required": ["text", "target_lang"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let text = args.get_str("text")?;
+        let source_lang = args.opt_str("source_lang")?.filter(|lang| *lang != "auto");
+        let target_lang = args.get_str("target_lang")?;
+        let response = self
+            .translate(text, source_lang, target_lang)
+            .await
+            .unwrap_or_else(|error| json!({ "error": error }));
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_names_the_languages() {
+        let with_source = prompt("Hallo Welt", Some("de"), "en");
+        assert!(with_source.contains("from de into en"), "{with_source}");
+        assert!(with_source.ends_with("<text>Hallo Welt</text>"));
+        assert!(prompt("Hallo Welt", None, "fr").contains("tags into fr"));
+    }
+
+    #[test]
+    fn model_answers_are_parsed() {
+        let answer = r#" {"translation": "Hello world", "source_lang": "de"} "#;
+        assert_eq!(
+            parse_model_answer(answer, true),
+            Ok(Translation {
+                text: "Hello world".to_string(),
+                detected: Some("de".to_string()),
+            })
+        );
+        assert_eq!(parse_model_answer(answer, false).unwrap().detected, None);
+        assert!(parse_model_answer("Hello world", true).is_err());
+    }
+
+    #[test]
+    fn api_responses_are_parsed() {
+        let response: ApiResponse = serde_json::from_value(json!({
+            "translatedText": "Hello world",
+            "detectedLanguage": {"confidence": 90.0, "language": "de"},
+        }))
+        .unwrap();
+        assert_eq!(response.translated_text, "Hello world");
+        assert_eq!(response.detected_language.unwrap().language, "de");
+    }
+}
@@ -0,0 +1,317 @@
+use std::{collections::HashMap, error::Error, time::Duration};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::args::{ArgError, Args};
+use super::http_fetch::USER_AGENT;
+use crate::cache::Cache;
+
+/// Exchange rates are fetched again once they are older than this.
+const RATES_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Category {
+    Length,
+    Mass,
+    Temperature,
+    DataSize,
+}
+
+impl Category {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Length => "length",
+            Self::Mass => "mass",
+            Self::Temperature => "temperature",
+            Self::DataSize => "data size",
+        }
+    }
+}
+
+use Category::{DataSize, Length, Mass, Temperature};
+
+/// Units as `|`-separated singular spellings and their size in the base unit of the category:
+/// meters, kilograms and bytes. Temperatures store the scale used by [`to_kelvin`].
+const UNITS: &[(Category, &str, f64)] = &[
+    (Length, "mm|millimeter|millimetre", 0.001),
+    (Length, "cm|centimeter|centimetre", 0.01),
+    (Length, "m|meter|metre", 1.0),
+    (Length, "km|kilometer|kilometre", 1000.0),
+    (Length, "in|inch|inches", 0.0254),
+    (Length, "ft|foot|feet", 0.3048),
+    (Length, "yd|yard", 0.9144),
+    (Length, "mi|mile", 1609.344),
+    (Length, "nmi|nautical mile", 1852.0),
+    (Mass, "mg|milligram", 0.000_001),
+    (Mass, "g|gram", 0.001),
+    (Mass, "kg|kilogram", 1.0),
+    (Mass, "t|tonne", 1000.0),
+    (Mass, "oz|ounce", 0.028_349_523_125),
+    (Mass, "lb|pound", 0.453_592_37),
+    (Mass, "st|stone", 6.350_293_18),
+    (Temperature, "c|°c|celsius", 0.0),
+    (Temperature, "f|°f|fahrenheit", 1.0),
+    (Temperature, "k|kelvin", 2.0),
+    (DataSize, "bit", 0.125),
+    (DataSize, "b|byte", 1.0),
+    (DataSize, "kb|kilobyte", 1e3),
+    (DataSize, "mb|megabyte", 1e6),
+    (DataSize, "gb|gigabyte", 1e9),
+    (DataSize, "tb|terabyte", 1e12),
+    (DataSize, "kib|kibibyte", 1024.0),
+    (DataSize, "mib|mebibyte", 1_048_576.0),
+    (DataSize, "gib|gibibyte", 1_073_741_824.0),
+    (DataSize, "tib|tebibyte", 1_099_511_627_776.0),
+];
+
+fn find_unit(name: &str) -> Option<(Category, f64)> {
+    let name = name.trim().to_lowercase();
+    let singular = name.strip_suffix('s').filter(|stem| stem.len() > 1);
+    UNITS
+        .iter()
+        .find(|(_, names, _)| {
+            names
+                .split('|')
+                .any(|alias| alias == name || Some(alias) == singular)
+        })
+        .map(|&(category, _, factor)| (category, factor))
+}
+
+fn to_kelvin(value: f64, scale: f64) -> f64 {
+    match scale as u8 {
+        0 => value + 273.15,
+        1 => (value - 32.0) * 5.0 / 9.0 + 273.15,
+        _ => value,
+    }
+}
+
+fn from_kelvin(value: f64, scale: f64) -> f64 {
+    match scale as u8 {
+        0 => value - 273.15,
+        1 => (value - 273.15) * 9.0 / 5.0 + 32.0,
+        _ => value,
+    }
+}
+
+/// Converts between units of the same category.
+fn convert(value: f64, from: &str, to: &str) -> Result<f64, String> {
+    let (from_category, from_factor) =
+        find_unit(from).ok_or_else(|| format!("unknown unit '{from}'"))?;
+    let (to_category, to_factor) = find_unit(to).ok_or_else(|| format!("unknown unit '{to}'"))?;
+    if from_category != to_category {
+        return Err(format!(
+            "cannot convert {} ({from}) to {} ({to})",
+            from_category.name(),
+            to_category.name()
+        ));
+    }
+
+    Ok(match from_category {
+        Category::Temperature => from_kelvin(to_kelvin(value, from_factor), to_factor),
+        _ => value * from_factor / to_factor,
+    })
+}
+
+/// Three letters that aren't a unit, like `EUR` or `jpy`.
+fn currency_code(code: &str) -> Option<String> {
+    let code = code.trim();
+    (code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) && find_unit(code).is_none())
+        .then(|| code.to_uppercase())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UnitConverterConfig {
+    /// Convert between ISO currency codes using `rates_url`.
+    pub currency: bool,
+    /// Frankfurter-compatible API returning `{"date": .., "rates": {..}}` for `?base=`.
+    pub rates_url: String,
+    pub timeout_secs: u64,
+}
+
+impl Default for UnitConverterConfig {
+    fn default() -> Self {
+        Self {
+            currency: true,
+            rates_url: "https://api.frankfurter.dev/v1/latest".to_string(),
+            timeout_secs: 15,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Rates {
+    date: String,
+    rates: HashMap<String, f64>,
+}
+
+pub struct UnitConverter {
+    client: reqwest::Client,
+    config: UnitConverterConfig,
+    cache: Cache,
+}
+
+impl UnitConverter {
+    pub fn new(config: UnitConverterConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .unwrap_or_default();
+        let cache = Cache::new(Cache::default_dir().join("rates"), RATES_TTL);
+        Self {
+            client,
+            config,
+            cache,
+        }
+    }
+
+    /// Rates from `base` to every other currency, cached on disk for [`RATES_TTL`].
+    async fn rates(&self, base: &str) -> Result<Rates, String> {
+        if let Some(rates) = self.cache.get(base) {
+            if let Ok(rates) = serde_json::from_str(&rates) {
+                return Ok(rates);
+            }
+        }
+
+        let url = Url::parse_with_params(&self.config.rates_url, [("base", base)])
+            .map_err(|e| e.to_string())?;
+        tracing::debug!(%url, "fetching exchange rates");
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| format!("exchange rate service unreachable: {e}"))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("no exchange rates for {base} ({status})"));
+        }
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        let rates = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+        if let Err(e) = self.cache.put(base, &body) {
+            tracing::debug!("failed to cache exchange rates: {e}");
+        }
+        Ok(rates)
+    }
+
+    async fn convert_currency(&self, value: f64, from: &str, to: &str) -> Result<Value, String> {
+        let rates = self.rates(from).await?;
+        let rate = if from == to {
+            1.0
+        } else {
+            *rates
+                .rates
+                .get(to)
+                .ok_or_else(|| format!("no exchange rate from {from} to {to}"))?
+        };
+        Ok(json!({
+            "value": value,
+            "from": from,
+            "to": to,
+            "result": value * rate,
+            "rate": rate,
+            "date": rates.date,
+        }))
+    }
+}
+
+#[async_trait]
+impl Tool for UnitConverter {
+    fn name(&self) -> String {
+        "unit_converter".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Convert a value between units of length, mass, temperature or data size, or between currencies"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "value": {
+                    "type": "number",
+                    "description": "The amount to convert"
+                },
+                "from": {
+                    "type": "string",
+                    "description": "Unit or ISO currency code to convert from, e.g. 'km', 'lb', 'F', 'GiB' or 'USD'"
+                },
+                "to": {
+                    "type": "string",
+                    "description": "Unit or ISO currency code to convert to, e.g. 'mi', 'kg', 'C', 'MB' or 'EUR'"
+                }
+            },
+            "required": ["value", "from", "to"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let args = Args::new(&input);
+        let value = args
+            .opt_f64("value")?
+            .ok_or_else(|| ArgError("missing 'value' argument".to_string()))?;
+        let from = args.get_str("from")?;
+        let to = args.get_str("to")?;
+
+        let currencies = currency_code(from).zip(currency_code(to));
+        let response = match currencies {
+            Some((from, to)) if self.config.currency => {
+                self.convert_currency(value, &from, &to).await
+            }
+            _ => convert(value, from, to)
+                .map(|result| json!({"value": value, "from": from, "to": to, "result": result})),
+        };
+        let response = response.unwrap_or_else(|error| json!({ "error": error }));
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn converts_within_a_category() {
+        assert!(close(
+            convert(5.0, "km", "mi").unwrap(),
+            3.106_855_961_185_92
+        ));
+        assert!(close(convert(2.0, "lbs", "grams").unwrap(), 907.184_74));
+        assert!(close(convert(100.0, "C", "F").unwrap(), 212.0));
+        assert!(close(convert(32.0, "fahrenheit", "K").unwrap(), 273.15));
+        assert!(close(convert(1.0, "GiB", "MB").unwrap(), 1_073.741_824));
+        assert!(close(convert(8.0, "bits", "B").unwrap(), 1.0));
+    }
+
+    #[test]
+    fn rejects_unknown_and_mismatched_units() {
+        assert_eq!(
+            convert(1.0, "kg", "m").unwrap_err(),
+            "cannot convert mass (kg) to length (m)"
+        );
+        assert_eq!(
+            convert(1.0, "parsec", "m").unwrap_err(),
+            "unknown unit 'parsec'"
+        );
+    }
+
+    #[test]
+    fn currency_codes_are_three_letters_that_are_no_unit() {
+        assert_eq!(currency_code("eur").as_deref(), Some("EUR"));
+        assert_eq!(currency_code("USD").as_deref(), Some("USD"));
+        assert_eq!(currency_code("nmi"), None);
+        assert_eq!(currency_code("kib"), None);
+        assert_eq!(currency_code("EURO"), None);
+    }
+}
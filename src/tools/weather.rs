@@ -0,0 +1,291 @@
+use std::{error::Error, time::Duration};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+use super::args::Args;
+use super::http_fetch::USER_AGENT;
+
+const GEOCODING_URL: &str = "https://geocoding-api.open-meteo.com/v1/search";
+const FORECAST_URL: &str = "https://api.open-meteo.com/v1/forecast";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WeatherConfig {
+    pub units: Units,
+    pub forecast_days: u8,
+    pub timeout_secs: u64,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        Self {
+            units: Units::Metric,
+            forecast_days: 3,
+            timeout_secs: 15,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GeocodingResponse {
+    #[serde(default)]
+    results: Vec<Place>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Place {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    country: Option<String>,
+    country_code: Option<String>,
+    admin1: Option<String>,
+}
+
+impl Place {
+    fn label(&self) -> String {
+        [
+            Some(&self.name),
+            self.admin1.as_ref(),
+            self.country.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+
+    /// Whether a qualifier like "France", "FR" or "Texas" describes this place.
+    fn matches(&self, qualifier: &str) -> bool {
+        [&self.country, &self.country_code, &self.admin1]
+            .into_iter()
+            .flatten()
+            .any(|field| field.eq_ignore_ascii_case(qualifier))
+    }
+}
+
+#[derive(Deserialize)]
+struct Forecast {
+    current: Current,
+    daily: Daily,
+}
+
+#[derive(Deserialize)]
+struct Current {
+    temperature_2m: f64,
+    weather_code: u8,
+    wind_speed_10m: f64,
+}
+
+#[derive(Deserialize)]
+struct Daily {
+    time: Vec<String>,
+    weather_code: Vec<u8>,
+    temperature_2m_max: Vec<f64>,
+    temperature_2m_min: Vec<f64>,
+}
+
+pub struct Weather {
+    client: reqwest::Client,
+    config: WeatherConfig,
+}
+
+impl Weather {
+    pub fn new(config: WeatherConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .unwrap_or_default();
+        Self { client, config }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: Url) -> Result<T, String> {
+        tracing::debug!(%url, "fetching");
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| format!("weather service unreachable: {e}"))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("weather service responded with {status}"));
+        }
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        serde_json::from_str(&body).map_err(|e| e.to_string())
+    }
+
+    /// Finds `location`, which may be narrowed down like "Paris, Texas" or "Paris, FR".
+    async fn geocode(&self, location: &str) -> Result<Place, String> {
+        let mut parts = location.split(',').map(str::trim);
+        let name = parts.next().unwrap_or_default();
+        let qualifiers: Vec<&str> = parts.filter(|q| !q.is_empty()).collect();
+
+        let url = Url::parse_with_params(GEOCODING_URL, [("name", name), ("count", "10")])
+            .map_err(|e| e.to_string())?;
+        let places = self.get::<GeocodingResponse>(url).await?.results;
+        if places.is_empty() {
+            return Err(format!("no place called '{name}' was found"));
+        }
+
+        let matching = places
+            .iter()
+            .find(|place| qualifiers.iter().all(|q| place.matches(q)));
+        match matching {
+            Some(place) => Ok(place.clone()),
+            None => {
+                let closest: Vec<String> = places.iter().take(5).map(Place::label).collect();
+                Err(format!(
+                    "'{location}' is ambiguous or unknown, closest matches: {}",
+                    closest.join("; ")
+                ))
+            }
+        }
+    }
+
+    async fn forecast(&self, place: &Place) -> Result<Forecast, String> {
+        let (temperature_unit, wind_speed_unit) = match self.config.units {
+            Units::Metric => ("celsius", "kmh"),
+            Units::Imperial => ("fahrenheit", "mph"),
+        };
+        let url = Url::parse_with_params(
+            FORECAST_URL,
+            [
+                ("latitude", place.latitude.to_string().as_str()),
+                ("longitude", place.longitude.to_string().as_str()),
+                ("current", "temperature_2m,weather_code,wind_speed_10m"),
+                (
+                    "daily",
+                    "weather_code,temperature_2m_max,temperature_2m_min",
+                ),
+                ("forecast_days", &self.config.forecast_days.to_string()),
+                ("temperature_unit", temperature_unit),
+                ("wind_speed_unit", wind_speed_unit),
+                ("timezone", "auto"),
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        self.get(url).await
+    }
+}
+
+/// Describes a WMO weather interpretation code as used by Open-Meteo.
+fn conditions(code: u8) -> &'static str {
+    match code {
+        0 => "clear sky",
+        1 => "mainly clear",
+        2 => "partly cloudy",
+        3 => "overcast",
+        45 | 48 => "fog",
+        51 | 53 | 55 => "drizzle",
+        56 | 57 => "freezing drizzle",
+        61 | 63 | 65 => "rain",
+        66 | 67 => "freezing rain",
+        71 | 73 | 75 | 77 => "snow",
+        80..=82 => "rain showers",
+        85 | 86 => "snow showers",
+        95 => "thunderstorm",
+        96 | 99 => "thunderstorm with hail",
+        _ => "unknown",
+    }
+}
+
+#[async_trait]
+impl Tool for Weather {
+    fn name(&self) -> String {
+        "weather".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Get the current weather and a short forecast for a city or place".to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "location": {
+                    "type": "string",
+                    "description": "Name of the place, optionally with its region or country, e.g. 'Paris, France'"
+                }
+            },
+            "required": ["location"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let location = Args::new(&input).get_str("location")?;
+
+        let place = match self.geocode(location).await {
+            Ok(place) => place,
+            Err(error) => return Ok(json!({ "error": error }).to_string()),
+        };
+        let forecast = match self.forecast(&place).await {
+            Ok(forecast) => forecast,
+            Err(error) => return Ok(json!({ "error": error }).to_string()),
+        };
+
+        let (temperature_unit, speed_unit) = match self.config.units {
+            Units::Metric => ("°C", "km/h"),
+            Units::Imperial => ("°F", "mph"),
+        };
+        let daily = &forecast.daily;
+        let days: Vec<Value> = (0..daily.time.len())
+            .map(|i| {
+                json!({
+                    "date": daily.time[i],
+                    "conditions": daily.weather_code.get(i).copied().map(conditions),
+                    "min": daily.temperature_2m_min.get(i),
+                    "max": daily.temperature_2m_max.get(i),
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "location": place.label(),
+            "temperature_unit": temperature_unit,
+            "current": {
+                "temperature": forecast.current.temperature_2m,
+                "conditions": conditions(forecast.current.weather_code),
+                "wind_speed": format!("{} {speed_unit}", forecast.current.wind_speed_10m),
+            },
+            "forecast": days,
+        })
+        .to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qualifiers_match_country_code_and_region() {
+        let place = Place {
+            name: "Paris".to_string(),
+            latitude: 33.66,
+            longitude: -95.55,
+            country: Some("United States".to_string()),
+            country_code: Some("US".to_string()),
+            admin1: Some("Texas".to_string()),
+        };
+        assert!(place.matches("texas"));
+        assert!(place.matches("us"));
+        assert!(!place.matches("France"));
+        assert_eq!(place.label(), "Paris, Texas, United States");
+    }
+}
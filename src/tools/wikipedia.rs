@@ -0,0 +1,273 @@
+use std::{error::Error, time::Duration};
+
+use async_trait::async_trait;
+use ollama_rs::generation::functions::tools::Tool;
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+use super::args::Args;
+use super::http_fetch::USER_AGENT;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WikipediaConfig {
+    /// Subdomain of the Wikipedia to search, e.g. "en" or "de".
+    pub language: String,
+    /// Titles listed at most when the query hits a disambiguation page.
+    pub max_candidates: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for WikipediaConfig {
+    fn default() -> Self {
+        Self {
+            language: "en".to_string(),
+            max_candidates: 10,
+            timeout_secs: 15,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    query: SearchQuery,
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    search: Vec<SearchHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchHit {
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct LinksResponse {
+    query: LinksQuery,
+}
+
+#[derive(Deserialize)]
+struct LinksQuery {
+    pages: Vec<LinksPage>,
+}
+
+#[derive(Deserialize)]
+struct LinksPage {
+    #[serde(default)]
+    links: Vec<SearchHit>,
+}
+
+#[derive(Deserialize)]
+struct Summary {
+    #[serde(rename = "type")]
+    kind: String,
+    title: String,
+    #[serde(default)]
+    extract: String,
+    content_urls: Option<ContentUrls>,
+}
+
+#[derive(Deserialize)]
+struct ContentUrls {
+    desktop: PageUrl,
+}
+
+#[derive(Deserialize)]
+struct PageUrl {
+    page: String,
+}
+
+/// `https://<language>.wikipedia.org`, for language codes like "en" or "zh-yue".
+fn wiki_url(language: &str) -> Result<Url, String> {
+    let valid =
+        !language.is_empty() && language.chars().all(|c| c.is_ascii_lowercase() || c == '-');
+    if !valid {
+        return Err(format!("invalid Wikipedia language '{language}'"));
+    }
+    Url::parse(&format!("https://{language}.wikipedia.org")).map_err(|e| e.to_string())
+}
+
+pub struct Wikipedia {
+    client: reqwest::Client,
+    config: WikipediaConfig,
+}
+
+impl Wikipedia {
+    pub fn new(config: WikipediaConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .unwrap_or_default();
+        Self { client, config }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: Url) -> Result<T, String> {
+        tracing::debug!(%url, "fetching");
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| format!("Wikipedia unreachable: {e}"))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("Wikipedia responded with {status}"));
+        }
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        serde_json::from_str(&body).map_err(|e| e.to_string())
+    }
+
+    /// The title of the best matching page.
+    async fn search(&self, base: &Url, query: &str) -> Result<Option<String>, String> {
+        let mut url = base.join("/w/api.php").map_err(|e| e.to_string())?;
+        url.query_pairs_mut().extend_pairs([
+            ("action", "query"),
+            ("list", "search"),
+            ("srsearch", query),
+            ("srlimit", "1"),
+            ("format", "json"),
+            ("formatversion", "2"),
+        ]);
+        let response: SearchResponse = self.get(url).await?;
+        Ok(response
+            .query
+            .search
+            .into_iter()
+            .next()
+            .map(|hit| hit.title))
+    }
+
+    async fn summary(&self, base: &Url, title: &str) -> Result<Summary, String> {
+        let mut url = base
+            .join("/api/rest_v1/page/summary/")
+            .map_err(|e| e.to_string())?;
+        url.path_segments_mut()
+            .map_err(|_| "invalid Wikipedia url".to_string())?
+            .pop_if_empty()
+            .push(&title.replace(' ', "_"));
+        self.get(url).await
+    }
+
+    /// The articles a disambiguation page links to.
+    async fn candidates(&self, base: &Url, title: &str) -> Result<Vec<String>, String> {
+        let mut url = base.join("/w/api.php").map_err(|e| e.to_string())?;
+        url.query_pairs_mut().extend_pairs([
+            ("action", "query"),
+            ("prop", "links"),
+            ("titles", title),
+            ("plnamespace", "0"),
+            ("pllimit", &self.config.max_candidates.to_string()),
+            ("format", "json"),
+            ("formatversion", "2"),
+        ]);
+        let response: LinksResponse = self.get(url).await?;
+        Ok(response
+            .query
+            .pages
+            .into_iter()
+            .flat_map(|page| page.links)
+            .map(|link| link.title)
+            .collect())
+    }
+
+    async fn lookup(&self, query: &str) -> Result<Value, String> {
+        let base = wiki_url(&self.config.language)?;
+        let Some(title) = self.search(&base, query).await? else {
+            return Err(format!("no Wikipedia article matches '{query}'"));
+        };
+        let summary = self.summary(&base, &title).await?;
+        let url = summary.content_urls.map(|urls| urls.desktop.page);
+
+        if summary.kind == "disambiguation" {
+            let candidates = self.candidates(&base, &summary.title).await?;
+            return Ok(json!({
+                "title": summary.title,
+                "url": url,
+                "disambiguation": true,
+                "candidates": candidates,
+            }));
+        }
+        Ok(json!({
+            "title": summary.title,
+            "extract": summary.extract,
+            "url": url,
+        }))
+    }
+}
+
+#[async_trait]
+impl Tool for Wikipedia {
+    fn name(&self) -> String {
+        "wikipedia".to_string()
+    }
+
+    fn description(&self) -> String {
+        "Look up a topic on Wikipedia and return the summary of the best matching article"
+            .to_string()
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "The topic to look up, e.g. 'Rust programming language'"
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    async fn run(&self, input: Value) -> Result<String, Box<dyn Error>> {
+        let query = Args::new(&input).get_str("query")?;
+        let response = self
+            .lookup(query)
+            .await
+            .unwrap_or_else(|error| json!({ "error": error }));
+        Ok(response.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_must_be_subdomains() {
+        assert_eq!(
+            wiki_url("de").unwrap().as_str(),
+            "https://de.wikipedia.org/"
+        );
+        assert!(wiki_url("zh-yue").is_ok());
+        assert!(wiki_url("").is_err());
+        assert!(wiki_url("evil.com/").is_err());
+        assert!(wiki_url("EN").is_err());
+    }
+
+    #[test]
+    fn summaries_are_parsed() {
+        let summary: Summary = serde_json::from_value(json!({
+            "type": "disambiguation",
+            "title": "Mercury",
+            "extract": "Mercury commonly refers to:",
+            "content_urls": {"desktop": {"page": "https://en.wikipedia.org/wiki/Mercury"}},
+        }))
+        .unwrap();
+        assert_eq!(summary.kind, "disambiguation");
+        assert_eq!(
+            summary.content_urls.unwrap().desktop.page,
+            "https://en.wikipedia.org/wiki/Mercury"
+        );
+
+        let links: LinksResponse = serde_json::from_value(json!({
+            "query": {"pages": [{"title": "Mercury", "links": [{"ns": 0, "title": "Mercury (planet)"}]}]}
+        }))
+        .unwrap();
+        assert_eq!(links.query.pages[0].links[0].title, "Mercury (planet)");
+    }
+}
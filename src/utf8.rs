@@ -0,0 +1,74 @@
+/// Decodes UTF-8 that arrives in pieces, like network chunks, holding back a multibyte
+/// character cut in half until the next piece completes it. Invalid bytes become U+FFFD.
+#[derive(Debug, Default)]
+pub struct Utf8Buffer {
+    pending: Vec<u8>,
+}
+
+impl Utf8Buffer {
+    /// Takes the next piece and returns the text that is complete so far.
+    pub fn push(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let mut text = String::new();
+        loop {
+            match std::str::from_utf8(&self.pending) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    self.pending.clear();
+                    return text;
+                }
+                Err(e) => {
+                    let valid = e.valid_up_to();
+                    text.push_str(std::str::from_utf8(&self.pending[..valid]).unwrap());
+                    match e.error_len() {
+                        // The input ends in the middle of a character.
+                        None => {
+                            self.pending.drain(..valid);
+                            return text;
+                        }
+                        Some(invalid) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            self.pending.drain(..valid + invalid);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns what is still held back once the input ends.
+    pub fn finish(&mut self) -> String {
+        let rest = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        rest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn characters_split_across_pieces_stay_whole() {
+        let bytes = "grüße 🦈".as_bytes();
+        let mut buffer = Utf8Buffer::default();
+        let mut text = String::new();
+        for byte in bytes {
+            text.push_str(&buffer.push(&[*byte]));
+        }
+        assert_eq!(text, "grüße 🦈");
+        assert_eq!(buffer.finish(), "");
+
+        let (first, second) = bytes.split_at(3);
+        assert_eq!(buffer.push(first), "gr");
+        assert_eq!(buffer.push(second), "üße 🦈");
+    }
+
+    #[test]
+    fn invalid_bytes_are_replaced() {
+        let mut buffer = Utf8Buffer::default();
+        assert_eq!(buffer.push(b"a\xffb"), "a\u{fffd}b");
+        assert_eq!(buffer.push(&"🦈".as_bytes()[..2]), "");
+        assert_eq!(buffer.finish(), "\u{fffd}");
+    }
+}
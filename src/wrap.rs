@@ -0,0 +1,110 @@
+/// Word-wraps streamed text at a fixed width.
+///
+/// Model chunks can end in the middle of a word, so the trailing word is held back
+/// until whitespace shows where it ends. Spaces are held back too, so none dangle
+/// at the end of a wrapped line.
+pub struct Wrapper {
+    width: usize,
+    column: usize,
+    word: String,
+    space: bool,
+}
+
+impl Wrapper {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width: width.max(1),
+            column: 0,
+            word: String::new(),
+            space: false,
+        }
+    }
+
+    /// Takes the next chunk and returns the text that can be printed so far.
+    pub fn push(&mut self, chunk: &str) -> String {
+        let mut out = String::new();
+        for c in chunk.chars() {
+            match c {
+                '\n' => {
+                    self.flush_word(&mut out);
+                    out.push('\n');
+                    self.column = 0;
+                    self.space = false;
+                }
+                c if c.is_whitespace() => {
+                    self.flush_word(&mut out);
+                    self.space = self.column > 0;
+                }
+                c => self.word.push(c),
+            }
+        }
+        out
+    }
+
+    /// Returns the word still held back, for the end of the answer.
+    pub fn finish(&mut self) -> String {
+        let mut out = String::new();
+        self.flush_word(&mut out);
+        self.column = 0;
+        self.space = false;
+        out
+    }
+
+    fn flush_word(&mut self, out: &mut String) {
+        let mut len = self.word.chars().count();
+        if len == 0 {
+            return;
+        }
+        if std::mem::take(&mut self.space) {
+            if self.column + 1 + len <= self.width {
+                out.push(' ');
+                self.column += 1;
+            } else {
+                out.push('\n');
+                self.column = 0;
+            }
+        }
+
+        // Words longer than a whole line are broken wherever the line ends.
+        let mut word = std::mem::take(&mut self.word);
+        while len > self.width - self.column {
+            let split = word
+                .char_indices()
+                .nth(self.width - self.column)
+                .map_or(word.len(), |(i, _)| i);
+            out.push_str(&word[..split]);
+            out.push('\n');
+            word = word[split..].to_string();
+            len -= self.width - self.column;
+            self.column = 0;
+        }
+        out.push_str(&word);
+        self.column += len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wrap(width: usize, chunks: &[&str]) -> String {
+        let mut wrapper = Wrapper::new(width);
+        let mut out: String = chunks.iter().map(|chunk| wrapper.push(chunk)).collect();
+        out.push_str(&wrapper.finish());
+        out
+    }
+
+    #[test]
+    fn words_split_across_chunks_stay_whole() {
+        assert_eq!(
+            wrap(10, &["The qu", "ick bro", "wn fox jum", "ps"]),
+            "The quick\nbrown fox\njumps"
+        );
+    }
+
+    #[test]
+    fn newlines_reset_the_column_and_long_words_are_broken() {
+        assert_eq!(wrap(5, &["ab\ncd ef"]), "ab\ncd ef");
+        assert_eq!(wrap(4, &["abcdefghij"]), "abcd\nefgh\nij");
+    }
+}